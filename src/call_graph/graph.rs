@@ -2,7 +2,7 @@ use super::{
     import::{ExternalImport, Import, ImportMap, LocalImport},
     manifest::Manifest,
 };
-use crate::utils::to_snake_case;
+use crate::code::imports::{self, ImportKind};
 use anyhow::Context;
 use petgraph::{
     dot::{Config, Dot},
@@ -13,7 +13,7 @@ use proc_macro2::LineColumn;
 use std::{
     collections::HashMap,
     fs,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 use syn::{
     spanned::Spanned, visit::Visit, Expr, ExprCall, ExprMethodCall, ExprPath, File, ImplItem,
@@ -117,14 +117,23 @@ impl From<(&ImplItemFn, &ItemImpl)> for CallNode {
 #[derive(Debug)]
 pub struct Edge {}
 
+/// Files `syn` couldn't parse while building the graph, skipped so one bad file doesn't
+/// keep the rest of the crate out of it
+#[derive(Debug, Default)]
+pub struct UnparsableFiles {
+    pub skipped: Vec<PathBuf>,
+}
+
 pub struct CallGraph {
     manifest: Manifest,
-    imports: ImportMap,
+    /// the imports of every file crawled so far, collected once a file
+    imports: HashMap<PathBuf, ImportMap>,
     graph: DiGraph<String, Edge>,
     nodes_map: HashMap<String, CallNode>,
     nodes_index_map: HashMap<String, NodeIndex>,
     entry_file: PathBuf,
     entrypoint: EntryPoint,
+    unparsable: UnparsableFiles,
 }
 
 impl CallGraph {
@@ -139,26 +148,35 @@ impl CallGraph {
 
         Ok(Self {
             manifest: Manifest::try_new(&root_dir)?,
-            imports: ImportMap::new(),
+            imports: HashMap::new(),
             graph: Graph::new(),
             nodes_map: HashMap::new(),
             nodes_index_map: HashMap::new(),
             entry_file: entry_file.to_owned(),
             entrypoint,
+            unparsable: UnparsableFiles::default(),
         })
     }
 
+    /// Files left out of the graph because they couldn't be parsed
+    pub fn skipped_files(&self) -> &[PathBuf] {
+        &self.unparsable.skipped
+    }
+
     pub fn build(&mut self) -> anyhow::Result<()> {
         CallGraphBuilder::new(
-            &self.entry_file,
-            self.entrypoint.clone(),
-            None,
+            Crawl {
+                entry_file: self.entry_file.clone(),
+                entrypoint: self.entrypoint.clone(),
+                parent_node_key: None,
+                depth: 0,
+            },
             &mut self.graph,
             &mut self.nodes_map,
             &mut self.nodes_index_map,
             &mut self.imports,
             &self.manifest,
-            0,
+            &mut self.unparsable,
         )
         .build()?;
         println!(
@@ -170,13 +188,42 @@ impl CallGraph {
     }
 }
 
+/// Builds the part of a node key that identifies `file`: its path relative to
+/// `root_dir` with `/` separators and no `.rs` extension, so the same crate
+/// yields the same keys on every platform
+fn file_node_key(file: &Path, root_dir: &Path) -> String {
+    let relative = file.strip_prefix(root_dir).unwrap_or(file);
+    let mut segments = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<String>>();
+    if let Some(last) = segments.last_mut() {
+        if let Some(stem) = last.strip_suffix(".rs") {
+            *last = stem.to_owned();
+        }
+    }
+    segments.join("/")
+}
+
+/// The function a `CallGraphBuilder` crawls, and where it was called from
+struct Crawl {
+    entry_file: PathBuf,
+    entrypoint: EntryPoint,
+    parent_node_key: Option<String>,
+    depth: usize,
+}
+
 struct CallGraphBuilder<'builder> {
     graph: &'builder mut DiGraph<String, Edge>,
     nodes_map: &'builder mut HashMap<String, CallNode>,
     nodes_index_map: &'builder mut HashMap<String, NodeIndex>,
-    imports: &'builder mut ImportMap,
+    imports: &'builder mut HashMap<PathBuf, ImportMap>,
     parent_node_key: Option<String>,
     manifest: &'builder Manifest,
+    unparsable: &'builder mut UnparsableFiles,
     variables: Vec<Variable>,
     entry_file: PathBuf,
     entrypoint: EntryPoint,
@@ -186,18 +233,22 @@ struct CallGraphBuilder<'builder> {
 
 impl<'builder> CallGraphBuilder<'builder> {
     pub fn new(
-        entry_file: &PathBuf,
-        entrypoint: EntryPoint,
-        parent_node_key: Option<String>,
+        crawl: Crawl,
         graph: &'builder mut DiGraph<String, Edge>,
         nodes_map: &'builder mut HashMap<String, CallNode>,
         nodes_index_map: &'builder mut HashMap<String, NodeIndex>,
-        imports: &'builder mut ImportMap,
+        imports: &'builder mut HashMap<PathBuf, ImportMap>,
         manifest: &'builder Manifest,
-        depth: usize,
+        unparsable: &'builder mut UnparsableFiles,
     ) -> Self {
+        let Crawl {
+            entry_file,
+            entrypoint,
+            parent_node_key,
+            depth,
+        } = crawl;
         Self {
-            entry_file: entry_file.to_owned(),
+            entry_file,
             entrypoint,
             parent_node_key,
             graph,
@@ -206,6 +257,7 @@ impl<'builder> CallGraphBuilder<'builder> {
             nodes_index_map,
             imports,
             manifest,
+            unparsable,
             error: None,
             depth,
         }
@@ -217,7 +269,31 @@ impl<'builder> CallGraphBuilder<'builder> {
         //     self.entrypoint, self.entry_file
         // );
         let code = fs::read_to_string(&self.entry_file)?;
-        let file: File = syn::parse_file(&code)?;
+        let file: File = match syn::parse_file(&code) {
+            Ok(file) => file,
+            Err(e) => {
+                self.print(&format!(
+                    "skipping {:?}, it could not be parsed: {e}",
+                    self.entry_file
+                ));
+                if !self.unparsable.skipped.contains(&self.entry_file) {
+                    self.unparsable.skipped.push(self.entry_file.clone());
+                }
+                return Ok(());
+            }
+        };
+
+        // a file's imports are the same every time one of its functions is crawled
+        if !self.imports.contains_key(&self.entry_file) {
+            let mut collector = UseCollector { uses: vec![] };
+            collector.visit_file(&file);
+            let mut imports = ImportMap::new();
+            for item in collector.uses {
+                self.process_use_tree(&item.tree, &mut Vec::new(), &mut imports)?;
+            }
+            self.imports.insert(self.entry_file.clone(), imports);
+        }
+
         self.visit_file(&file);
         if let Some(e) = self.error.take() {
             return Err(e);
@@ -229,22 +305,23 @@ impl<'builder> CallGraphBuilder<'builder> {
         &mut self,
         tree: &UseTree,
         path_prefix: &mut Vec<String>,
+        imports: &mut ImportMap,
     ) -> anyhow::Result<()> {
         match tree {
             UseTree::Path(path) => {
                 path_prefix.push(path.ident.to_string());
-                self.process_use_tree(&path.tree, path_prefix)?;
+                self.process_use_tree(&path.tree, path_prefix, imports)?;
                 path_prefix.pop();
             }
             UseTree::Group(group) => {
                 for tree in &group.items {
-                    self.process_use_tree(tree, path_prefix)?;
+                    self.process_use_tree(tree, path_prefix, imports)?;
                 }
             }
             UseTree::Name(name) => {
                 path_prefix.push(name.ident.to_string());
                 let import = self.resolve_import(path_prefix)?;
-                self.imports.insert(import);
+                imports.insert(import);
                 path_prefix.pop();
             }
             _ => todo!("not sure how to handle glob and rename yet"),
@@ -253,84 +330,60 @@ impl<'builder> CallGraphBuilder<'builder> {
         Ok(())
     }
 
-    fn resolve_import(&self, path_prefix: &mut Vec<String>) -> anyhow::Result<Import> {
-        if let Some(first) = path_prefix.first() {
-            match first.as_str() {
-                "crate" | "self" | "super" => {
-                    let import = LocalImport::try_new(
-                        &path_prefix[..],
-                        &self.entry_file.parent().unwrap_or_else(|| Path::new(".")),
-                        &self
-                            .manifest
-                            .package_name()
-                            .map(|n| to_snake_case(&n))
-                            .unwrap(),
-                    )?;
-                    return Ok(Import::Local(import));
-                }
-                first
-                    if Some(first.to_owned())
-                        == self.manifest.package_name().map(|n| to_snake_case(&n)) =>
-                {
-                    let import = LocalImport::try_new(
-                        &path_prefix[..],
-                        &self.entry_file.parent().unwrap_or_else(|| Path::new(".")),
-                        &self
-                            .manifest
-                            .package_name()
-                            .map(|n| to_snake_case(&n))
-                            .unwrap(),
-                    )?;
-                    return Ok(Import::Local(import));
-                }
-                _ => {}
-            }
+    fn resolve_import(&self, path_prefix: &[String]) -> anyhow::Result<Import> {
+        let base_dir = imports::module_dir_of(&self.entry_file).unwrap_or_else(|| ".".into());
+        match imports::resolve(&path_prefix.join("::"), self.manifest, &base_dir)? {
+            ImportKind::Local(file) => Ok(Import::Local(LocalImport::try_new(path_prefix, file)?)),
+            _ => Ok(Import::External(ExternalImport::new(path_prefix))),
         }
+    }
 
-        Ok(Import::External(ExternalImport::new(&path_prefix[..])))
+    /// Links `parent_node_key` to `node_key` if that function is already in the graph,
+    /// e.g. when a recursive call leads back up the chain, so it isn't crawled again
+    fn link_visited(&mut self, parent_node_key: &str, node_key: &str) -> bool {
+        let Some(node_index) = self.nodes_index_map.get(node_key) else {
+            return false;
+        };
+        if let Some(parent_node_index) = self.nodes_index_map.get(parent_node_key) {
+            self.graph
+                .update_edge(*parent_node_index, *node_index, Edge {});
+        }
+        true
+    }
+
+    /// The import `ident` names in the file being crawled
+    fn import(&self, ident: &str) -> Option<Import> {
+        self.imports.get(&self.entry_file)?.get(ident).cloned()
     }
 
     fn resolve_node_key(&self) -> String {
         match &self.entrypoint {
             EntryPoint::Func(s) => {
-                self.entry_file
-                    .iter()
-                    .map(|i| i.to_string_lossy().to_string())
-                    .collect::<Vec<String>>()
-                    .join("::")
-                    .replace(".rs", "")
-                    + "::"
-                    + s
+                file_node_key(&self.entry_file, self.manifest.root_dir()) + "::" + s
             }
             EntryPoint::MethodCall {
                 target_struct,
                 method,
             } => {
-                self.entry_file
-                    .iter()
-                    .map(|i| i.to_string_lossy().to_string())
-                    .collect::<Vec<String>>()
-                    .join("::")
-                    .replace(".rs", "")
+                file_node_key(&self.entry_file, self.manifest.root_dir())
                     + format!("::{target_struct}::{method}").as_str()
             }
         }
     }
 }
 
-impl<'ast, 'cgb> Visit<'ast> for CallGraphBuilder<'cgb> {
+/// Collects every `use` item of a file, including those inside function bodies
+struct UseCollector<'ast> {
+    uses: Vec<&'ast ItemUse>,
+}
+
+impl<'ast> Visit<'ast> for UseCollector<'ast> {
     fn visit_item_use(&mut self, node: &'ast ItemUse) {
-        // println!(
-        //     "found a use {:#?} in file {:#?}",
-        //     node.tree, self.entry_file
-        // );
-        if let Err(e) = self.process_use_tree(&node.tree, &mut Vec::new()) {
-            self.error = Some(e);
-            return;
-        }
-        syn::visit::visit_item_use(self, node);
+        self.uses.push(node);
     }
+}
 
+impl<'ast, 'cgb> Visit<'ast> for CallGraphBuilder<'cgb> {
     fn visit_local(&mut self, node: &'ast Local) {
         println!("[visit_local] {:#?}", node);
         // Variable {
@@ -398,14 +451,9 @@ impl<'ast, 'cgb> Visit<'ast> for CallGraphBuilder<'cgb> {
                                     self.print(&format!("found a method call: {}", method));
                                     let entry_node = CallNode::from((method_node, node));
 
-                                    let node_key = self
-                                        .entry_file
-                                        .iter()
-                                        .map(|i| i.to_string_lossy().to_string())
-                                        .collect::<Vec<String>>()
-                                        .join("::")
-                                        .replace(".rs", "")
-                                        + format!("::{target_struct}::{method}").as_str();
+                                    let node_key =
+                                        file_node_key(&self.entry_file, self.manifest.root_dir())
+                                            + format!("::{target_struct}::{method}").as_str();
                                     if !self.nodes_map.contains_key(&node_key) {
                                         self.nodes_map.insert(node_key.clone(), entry_node);
                                         let node_index = self.graph.add_node(node_key.clone());
@@ -515,23 +563,37 @@ impl<'fcb, 'cgb, 'pn> FunctionCallBuilder<'fcb, 'cgb, 'pn> {
             ParentNode::Method { node_key, .. } => node_key.clone(),
         };
 
-        if let Some(import) = self.call_graph_builder.imports.get(&ident) {
+        if let Some(import) = self.call_graph_builder.import(&ident) {
             self.print(&format!("found fn call: {}", ident));
-            let mut import_map = ImportMap::new();
             let depth = self.depth + 1;
 
             match import {
                 Import::Local(import) => {
-                    let mut builder = CallGraphBuilder::new(
+                    let node_key = file_node_key(
                         &import.module_file_path,
-                        EntryPoint::Func(ident.to_string()),
-                        Some(parent_node_key),
+                        self.call_graph_builder.manifest.root_dir(),
+                    ) + "::"
+                        + &ident;
+                    if self
+                        .call_graph_builder
+                        .link_visited(&parent_node_key, &node_key)
+                    {
+                        return;
+                    }
+
+                    let mut builder = CallGraphBuilder::new(
+                        Crawl {
+                            entry_file: import.module_file_path.clone(),
+                            entrypoint: EntryPoint::Func(ident.to_string()),
+                            parent_node_key: Some(parent_node_key),
+                            depth,
+                        },
                         &mut self.call_graph_builder.graph,
                         &mut self.call_graph_builder.nodes_map,
                         &mut self.call_graph_builder.nodes_index_map,
-                        &mut import_map,
+                        self.call_graph_builder.imports,
                         &self.call_graph_builder.manifest,
-                        depth,
+                        self.call_graph_builder.unparsable,
                     );
 
                     if let Err(e) = builder.build() {
@@ -619,15 +681,10 @@ impl<'ast, 'fcb, 'cgb, 'pn> Visit<'ast> for FunctionCallBuilder<'fcb, 'cgb, 'pn>
                                             method,
                                         } => format!("{target_struct}::{method}"),
                                     };
-                                    let node_key = self
-                                        .call_graph_builder
-                                        .entry_file
-                                        .iter()
-                                        .map(|i| i.to_string_lossy().to_string())
-                                        .collect::<Vec<String>>()
-                                        .join("::")
-                                        .replace(".rs", "")
-                                        + "::"
+                                    let node_key = file_node_key(
+                                        &self.call_graph_builder.entry_file,
+                                        self.call_graph_builder.manifest.root_dir(),
+                                    ) + "::"
                                         + &s;
                                     if !self.call_graph_builder.nodes_map.contains_key(&node_key) {
                                         self.call_graph_builder
@@ -675,27 +732,39 @@ impl<'ast, 'fcb, 'cgb, 'pn> Visit<'ast> for FunctionCallBuilder<'fcb, 'cgb, 'pn>
                             }
                         }
                     }
-                } else if let Some(import) = self.call_graph_builder.imports.get(&import_identifier)
-                {
+                } else if let Some(import) = self.call_graph_builder.import(&import_identifier) {
                     if let Import::Local(import) = import {
                         self.print(&format!("found=====: {}", import_identifier.to_string()));
                         let last = expr_path.path.segments.last().unwrap();
                         let method = last.ident.to_string();
-                        let mut import_map = ImportMap::new();
+                        let node_key = file_node_key(
+                            &import.module_file_path,
+                            self.call_graph_builder.manifest.root_dir(),
+                        ) + format!("::{import_identifier}::{method}").as_str();
+                        if self
+                            .call_graph_builder
+                            .link_visited(&parent_node_key, &node_key)
+                        {
+                            return;
+                        }
+
                         let depth = self.depth + 1;
                         let mut builder = CallGraphBuilder::new(
-                            &import.module_file_path,
-                            EntryPoint::MethodCall {
-                                target_struct: import_identifier,
-                                method,
+                            Crawl {
+                                entry_file: import.module_file_path.clone(),
+                                entrypoint: EntryPoint::MethodCall {
+                                    target_struct: import_identifier,
+                                    method,
+                                },
+                                parent_node_key: Some(parent_node_key),
+                                depth,
                             },
-                            Some(parent_node_key),
                             &mut self.call_graph_builder.graph,
                             &mut self.call_graph_builder.nodes_map,
                             &mut self.call_graph_builder.nodes_index_map,
-                            &mut import_map,
+                            self.call_graph_builder.imports,
                             &self.call_graph_builder.manifest,
-                            depth,
+                            self.call_graph_builder.unparsable,
                         );
 
                         if let Err(e) = builder.build() {
@@ -729,3 +798,88 @@ impl<'a, 'b, 'c> Printer for FunctionCallBuilder<'a, 'b, 'c> {
         self.depth
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{file_node_key, CallGraph, EntryPoint};
+    use crate::utils::fixtures::CrateFixture;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn skips_unparsable_files() {
+        let krate = CrateFixture::package("api");
+        let main = krate.write("src/main.rs", "fn main() { let = ; }\n");
+
+        let mut graph = CallGraph::try_new(&main, EntryPoint::Func("main".to_owned())).unwrap();
+        assert!(graph.build().is_ok());
+        assert_eq!(graph.skipped_files(), std::slice::from_ref(&main));
+    }
+
+    #[test]
+    fn collects_each_files_imports_once() {
+        let krate = CrateFixture::package("api");
+        let main = krate.write(
+            "src/main.rs",
+            "use crate::handlers::{create, list};\n\nfn main() { list(); create(); }\n",
+        );
+        let handlers = krate.write(
+            "src/handlers.rs",
+            "use crate::db::query;\n\npub fn list() { query(); }\n\npub fn create() { query(); }\n",
+        );
+        let db = krate.write("src/db.rs", "pub fn query() {}\n");
+
+        let mut graph = CallGraph::try_new(&main, EntryPoint::Func("main".to_owned())).unwrap();
+        graph.build().unwrap();
+
+        let mut files = graph.imports.keys().cloned().collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, vec![db, handlers.clone(), main]);
+        // both handlers reach `query` through the imports `handlers.rs` was crawled with first
+        assert!(graph.imports[&handlers].get("query").is_some());
+        assert!(graph.nodes_map.contains_key("src/db::query"));
+    }
+
+    #[test]
+    fn links_recursive_calls_without_crawling_them_again() {
+        let krate = CrateFixture::package("api");
+        let main = krate.write(
+            "src/main.rs",
+            "use crate::ping::ping;\n\nfn main() { ping(); }\n",
+        );
+        krate.write(
+            "src/ping.rs",
+            "use crate::pong::pong;\n\npub fn ping() { pong(); }\n",
+        );
+        krate.write(
+            "src/pong.rs",
+            "use crate::ping::ping;\n\npub fn pong() { ping(); }\n",
+        );
+
+        let mut graph = CallGraph::try_new(&main, EntryPoint::Func("main".to_owned())).unwrap();
+        graph.build().unwrap();
+
+        assert_eq!(graph.graph.node_count(), 3);
+        let ping = graph.nodes_index_map["src/ping::ping"];
+        let pong = graph.nodes_index_map["src/pong::pong"];
+        assert!(graph.graph.contains_edge(ping, pong));
+        assert!(graph.graph.contains_edge(pong, ping));
+    }
+
+    #[test]
+    fn file_node_keys_are_platform_independent() {
+        let root = Path::new("/work/api");
+        assert_eq!(
+            file_node_key(&root.join("src").join("routes").join("users.rs"), root),
+            "src/routes/users"
+        );
+        assert_eq!(
+            file_node_key(&PathBuf::from("src/routes/mod.rs"), Path::new("")),
+            "src/routes/mod"
+        );
+        // only the extension is dropped, not every `.rs` in the path
+        assert_eq!(
+            file_node_key(&root.join("src").join("users.rsx").join("api.rs"), root),
+            "src/users.rsx/api"
+        );
+    }
+}
@@ -1,12 +1,8 @@
-use std::{
-    collections::HashMap,
-    fmt::Display,
-    path::{Path, PathBuf},
-};
+use std::{collections::HashMap, fmt::Display, path::PathBuf};
 
 use anyhow::anyhow;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LocalImport {
     identifier: String,
     #[allow(unused)]
@@ -15,70 +11,21 @@ pub struct LocalImport {
 }
 
 impl LocalImport {
-    pub fn try_new(
-        path_segments: &[String],
-        base_dir: &Path,
-        crate_name: &str,
-    ) -> anyhow::Result<Self> {
-        let module_file_path = Self::resolve_import_module_path(
-            &path_segments[..path_segments.len() - 1],
-            base_dir,
-            crate_name,
-        )
-        .ok_or(anyhow!(
-            "failed to resolve import module path or {}",
-            path_segments.join("::")
-        ))?;
+    /// `module_file_path` is the file defining the imported item, see `imports::resolve`
+    pub fn try_new(path_segments: &[String], module_file_path: PathBuf) -> anyhow::Result<Self> {
+        let Some(identifier) = path_segments.last() else {
+            return Err(anyhow!("cannot resolve an empty import path"));
+        };
 
         Ok(Self {
-            identifier: path_segments.last().unwrap().to_owned(),
+            identifier: identifier.to_owned(),
             full_path: path_segments.join("::"),
             module_file_path,
         })
     }
-
-    pub fn resolve_import_module_path(
-        segments: &[String],
-        base_dir: &Path,
-        crate_name: &str,
-    ) -> Option<PathBuf> {
-        let Some(first) = segments.first() else {
-            return None;
-        };
-
-        let (mut module_dir, skip_segment) = match first.as_str() {
-            first if first == crate_name || first == "crate" => {
-                // src directory
-                let dir = base_dir
-                    .ancestors()
-                    .find(|d| d.join("src").exists())
-                    .map(|d| d.join("src"))?;
-                (dir, 1)
-            }
-            "self" => (base_dir.to_path_buf(), 1),
-            "super" => (base_dir.parent()?.to_path_buf(), 1),
-            _ => return None,
-        };
-
-        for seg in &segments[skip_segment..segments.len() - 1] {
-            module_dir = module_dir.join(seg);
-        }
-
-        let module = segments.last()?;
-        let file_rs = module_dir.join(format!("{}.rs", module));
-        let mod_rs = module_dir.join(module).join("mod.rs");
-
-        if file_rs.exists() {
-            Some(file_rs)
-        } else if mod_rs.exists() {
-            Some(mod_rs)
-        } else {
-            None
-        }
-    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExternalImport {
     identifier: String,
     #[allow(unused)]
@@ -94,7 +41,7 @@ impl ExternalImport {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Import {
     Local(LocalImport),
     External(ExternalImport),
@@ -1,10 +1,22 @@
-use anyhow::Context;
-use cargo_toml::Manifest as CargoManifest;
-use std::path::PathBuf;
+use crate::utils::to_snake_case;
+use anyhow::{bail, Context};
+use cargo_toml::{Manifest as CargoManifest, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug)]
 pub struct Manifest {
     manifest: CargoManifest,
+    /// local crates (workspace members and path dependencies) keyed by the
+    /// snake_cased name they're imported with, mapped to their root directory
+    local_crates: HashMap<String, PathBuf>,
+    /// package names of the members of the workspace this crate is part of, and their
+    /// root directory, sorted by name
+    workspace_members: Vec<(String, PathBuf)>,
+    root_dir: PathBuf,
 }
 
 impl Manifest {
@@ -12,10 +24,193 @@ impl Manifest {
         let manifest = CargoManifest::from_path(root_dir.join("Cargo.toml"))
             .context(format!("failed to read Cargo.toml at {:?}", root_dir))?;
 
-        Ok(Self { manifest })
+        let mut local_crates = HashMap::new();
+        let mut workspace_members = Vec::new();
+
+        if let Some((workspace_root, workspace)) = Self::find_workspace(root_dir) {
+            for member_dir in Self::expand_workspace_members(&workspace_root, &workspace) {
+                if let Some(name) = Self::read_package_name(&member_dir) {
+                    local_crates.insert(to_snake_case(&name), member_dir.clone());
+                    workspace_members.push((name, member_dir));
+                }
+            }
+            workspace_members.sort();
+
+            if let Some(workspace) = &workspace.workspace {
+                for (name, dep) in &workspace.dependencies {
+                    if let Some(path) = dep.detail().and_then(|d| d.path.as_ref()) {
+                        local_crates.insert(to_snake_case(name), workspace_root.join(path));
+                    }
+                }
+            }
+        }
+
+        for (name, dep) in &manifest.dependencies {
+            if let Some(path) = dep.detail().and_then(|d| d.path.as_ref()) {
+                local_crates.insert(to_snake_case(name), root_dir.join(path));
+            }
+        }
+
+        Ok(Self {
+            manifest,
+            local_crates,
+            workspace_members,
+            root_dir: root_dir.to_owned(),
+        })
     }
 
     pub fn package_name(&self) -> Option<String> {
         self.manifest.package.clone().map(|p| p.name.to_owned())
     }
+
+    /// The `[package] version`, unless it's inherited from a workspace that couldn't be read
+    pub fn package_version(&self) -> Option<String> {
+        let package = self.manifest.package.as_ref()?;
+        package.version.get().ok().cloned()
+    }
+
+    pub fn package_description(&self) -> Option<String> {
+        let package = self.manifest.package.as_ref()?;
+        package.description.as_ref()?.get().ok().cloned()
+    }
+
+    /// The `[package.metadata]` table, where tools keep their own settings
+    pub fn package_metadata(&self) -> Option<&Value> {
+        self.manifest.package.as_ref()?.metadata.as_ref()
+    }
+
+    /// Names of the crates in `[dependencies]`, as published rather than as renamed
+    pub fn dependency_names(&self) -> Vec<String> {
+        self.manifest
+            .dependencies
+            .iter()
+            .map(|(name, dep)| dep.package().unwrap_or(name).to_owned())
+            .collect()
+    }
+
+    /// The package name as it would appear in a `use` path
+    pub fn crate_name(&self) -> Option<String> {
+        self.package_name().map(|n| to_snake_case(&n))
+    }
+
+    /// The directory holding this crate's `Cargo.toml`
+    pub fn root_dir(&self) -> &Path {
+        &self.root_dir
+    }
+
+    /// Returns the root directory of a sibling workspace crate or path dependency
+    /// that is imported as `crate_name`
+    pub fn local_crate_dir(&self, crate_name: &str) -> Option<&PathBuf> {
+        self.local_crates.get(crate_name)
+    }
+
+    /// The root directory of the workspace member named `package`, failing with the names
+    /// of the members when there's none
+    pub fn member_dir(&self, package: &str) -> anyhow::Result<PathBuf> {
+        if let Some((_, dir)) = self
+            .workspace_members
+            .iter()
+            .find(|(name, _)| name == package)
+        {
+            return Ok(dir.clone());
+        }
+
+        if self.workspace_members.is_empty() {
+            bail!(
+                "{} is not part of a workspace, so it has no package `{package}`",
+                self.root_dir.display()
+            );
+        }
+        bail!(
+            "no package `{package}` in the workspace, available packages: {}",
+            self.workspace_members
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    /// Walks up from `root_dir` looking for the closest `Cargo.toml` with a `[workspace]` table
+    fn find_workspace(root_dir: &Path) -> Option<(PathBuf, CargoManifest)> {
+        root_dir.ancestors().find_map(|dir| {
+            let manifest = CargoManifest::from_path(dir.join("Cargo.toml")).ok()?;
+            manifest
+                .workspace
+                .is_some()
+                .then(|| (dir.to_path_buf(), manifest))
+        })
+    }
+
+    fn expand_workspace_members(workspace_root: &Path, workspace: &CargoManifest) -> Vec<PathBuf> {
+        let Some(workspace) = &workspace.workspace else {
+            return vec![];
+        };
+
+        let mut members = Vec::new();
+        for member in &workspace.members {
+            // only trailing `/*` globs are supported (e.g. `crates/*`)
+            if let Some(parent) = member.strip_suffix("/*") {
+                if let Ok(entries) = fs::read_dir(workspace_root.join(parent)) {
+                    for entry in entries.flatten() {
+                        let path = entry.path();
+                        if path.join("Cargo.toml").exists() {
+                            members.push(path);
+                        }
+                    }
+                }
+            } else {
+                members.push(workspace_root.join(member));
+            }
+        }
+
+        members.retain(|m| {
+            !workspace
+                .exclude
+                .iter()
+                .any(|e| workspace_root.join(e) == *m)
+        });
+
+        members
+    }
+
+    fn read_package_name(crate_dir: &Path) -> Option<String> {
+        CargoManifest::from_path(crate_dir.join("Cargo.toml"))
+            .ok()?
+            .package
+            .map(|p| p.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Manifest;
+    use crate::utils::fixtures::{package_manifest, CrateFixture};
+
+    #[test]
+    fn finds_workspace_members_by_package_name() {
+        let workspace =
+            CrateFixture::new("[workspace]\nmembers = [\"crates/*\", \"services/orders\"]\n");
+        let package = |dir: &str, name: &str| {
+            workspace.write(format!("{dir}/Cargo.toml"), &package_manifest(name));
+        };
+        package("services/orders", "orders-api");
+        package("crates/auth", "auth");
+        package("crates/db", "db");
+        let root = workspace.root();
+
+        let manifest = Manifest::try_new(&root).unwrap();
+        assert_eq!(
+            manifest.member_dir("orders-api").unwrap(),
+            root.join("services/orders")
+        );
+        assert_eq!(
+            manifest.member_dir("billing").unwrap_err().to_string(),
+            "no package `billing` in the workspace, available packages: auth, db, orders-api"
+        );
+
+        // members see the rest of the workspace too
+        let member = Manifest::try_new(&root.join("crates/db")).unwrap();
+        assert_eq!(member.member_dir("auth").unwrap(), root.join("crates/auth"));
+    }
 }
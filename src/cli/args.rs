@@ -1,12 +1,76 @@
+use anyhow::bail;
 use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 use url::Url;
 
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, PartialEq, ValueEnum)]
 pub enum Framework {
     RustAxum,
 }
 
+/// Web framework crates recognised in a crate's dependencies, and the generator for each
+/// one that is supported
+const KNOWN_FRAMEWORKS: [(&str, Option<Framework>); 6] = [
+    ("axum", Some(Framework::RustAxum)),
+    ("actix-web", None),
+    ("rocket", None),
+    ("warp", None),
+    ("poem", None),
+    ("tide", None),
+];
+
+impl Framework {
+    /// Picks the framework from the names of a crate's dependencies, failing when none or
+    /// more than one known framework is among them
+    pub fn detect(dependencies: &[String]) -> anyhow::Result<Self> {
+        let found = KNOWN_FRAMEWORKS
+            .iter()
+            .filter(|(name, _)| dependencies.iter().any(|dep| dep == name))
+            .collect::<Vec<_>>();
+
+        match found.as_slice() {
+            [] => bail!(
+                "couldn't detect the web framework from the crate's dependencies, pass `--framework`"
+            ),
+            [(_, Some(framework))] => Ok(framework.clone()),
+            [(name, None)] => bail!("{name} is not supported yet"),
+            found => bail!(
+                "found more than one web framework ({}), pass `--framework` to pick one",
+                found
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// Accepts `--contact-email` values shaped like `name@example.com`
+fn parse_email(email: &str) -> Result<String, String> {
+    match email.split_once('@') {
+        Some((local, domain))
+            if !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains('@')
+                && !email.contains(char::is_whitespace) =>
+        {
+            Ok(email.to_owned())
+        }
+        _ => Err(format!("`{email}` is not an email address")),
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum AuthScheme {
+    Bearer,
+    Basic,
+}
+
+// parsed once at startup, boxing the generate options wouldn't buy anything
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     #[command(about = "Generate document for a given codebase")]
@@ -15,8 +79,283 @@ pub enum Commands {
         url: Option<Url>,
         #[arg(short, long)]
         dir: Option<PathBuf>,
-        #[arg(short, long, value_enum)]
-        framework: Framework,
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with = "from_ir",
+            help = "Workspace member to document, when `--dir` or `--url` is a workspace"
+        )]
+        package: Option<String>,
+        #[arg(
+            short,
+            long,
+            value_enum,
+            help = "Framework the codebase uses [default: detected from its Cargo.toml dependencies]"
+        )]
+        framework: Option<Framework>,
+        #[arg(long, value_enum, default_value = "bearer")]
+        auth_scheme: AuthScheme,
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with = "api_key_query",
+            help = "Require an API key sent in the NAME header on every operation"
+        )]
+        api_key_header: Option<String>,
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Require an API key sent in the NAME query parameter on every operation"
+        )]
+        api_key_query: Option<String>,
+        #[arg(
+            long,
+            alias = "fail-on-warning",
+            help = "Fail if any route couldn't be fully resolved"
+        )]
+        strict: bool,
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            help = "Stop generating after the given time"
+        )]
+        timeout: Option<u64>,
+        #[arg(
+            long,
+            default_value_t = 4,
+            help = "Maximum number of concurrent LLM requests"
+        )]
+        concurrency: usize,
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Write the intermediate representation to a JSON file"
+        )]
+        emit_ir: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Write each request body DTO as a standalone JSON Schema file"
+        )]
+        emit_schemas: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Write a JSON report of the run: routes, LLM calls, tokens, warnings and time taken"
+        )]
+        report: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "DIR",
+            conflicts_with_all = ["emit_schemas", "report"],
+            help = "Write openapi.yaml, a schemas/ folder and report.json to DIR instead of output.yaml"
+        )]
+        output_dir: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with = "compact",
+            help = "Indent the JSON files written [default: when stdout is a terminal]"
+        )]
+        pretty: bool,
+        #[arg(
+            long,
+            help = "Write the JSON files on a single line [default: when stdout is piped]"
+        )]
+        compact: bool,
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = ["url", "dir"],
+            help = "Build the spec from a saved IR JSON file instead of analysing code"
+        )]
+        from_ir: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "PREFIX",
+            help = "Prefix every path with the given base path, e.g. /api/v1"
+        )]
+        base_path: Option<String>,
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Function to start route discovery from, e.g. app or Application::routes"
+        )]
+        entry_fn: Option<String>,
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with = "entry_fn",
+            help = "File the router is built in, relative to the codebase root, to skip locating it"
+        )]
+        routes_file: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with = "from_ir",
+            help = "Only discover routes and print what would be analysed, without writing a spec"
+        )]
+        dry_run: bool,
+        #[arg(
+            long,
+            help = "Locate the router again instead of reusing the one cached in .docgen-cache"
+        )]
+        no_cache: bool,
+        #[arg(
+            long,
+            value_name = "PATH",
+            requires = "url",
+            help = "Directory `--url` downloads are placed in [default: <temp dir>/docgen/code]"
+        )]
+        work_dir: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Document Authorization, Content-Type and Accept header parameters too"
+        )]
+        include_standard_headers: bool,
+        #[arg(
+            long,
+            value_name = "GLOB",
+            help = "Only document routes whose path matches the glob, e.g. /api/** (repeatable)"
+        )]
+        include: Vec<String>,
+        #[arg(
+            long,
+            value_name = "GLOB",
+            help = "Leave out routes whose path matches the glob, wins over --include (repeatable)"
+        )]
+        exclude: Vec<String>,
+        #[arg(
+            long,
+            value_name = "PATTERN",
+            help = "Replace what matches the regex in the spec's examples, defaults and descriptions with *** (repeatable)"
+        )]
+        redact: Vec<String>,
+        #[arg(
+            long,
+            help = "Fail instead of replacing output.yaml when it already exists"
+        )]
+        no_overwrite: bool,
+        #[arg(
+            long,
+            conflicts_with = "dry_run",
+            help = "Rewrite output.yaml as routes are generated, so a crash still leaves the routes done so far"
+        )]
+        stream: bool,
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Load route_extract.txt, body_extract.txt and body_outer_extract.txt from DIR in place of the built-in prompts"
+        )]
+        prompt_dir: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Stop following calls more than N levels below the entry function [default: 100]"
+        )]
+        max_depth: Option<usize>,
+        #[arg(
+            long,
+            help = "Ask the LLM for example values of parameters and body fields, at the cost of extra tokens"
+        )]
+        examples: bool,
+        #[arg(
+            long,
+            help = "Leave services mounted with route_service or nest_service, e.g. static files, out of the spec"
+        )]
+        exclude_services: bool,
+        #[arg(
+            long,
+            help = "Leave routes whose handlers aren't `pub` out of the spec, treating them as internal"
+        )]
+        exclude_private: bool,
+        #[arg(
+            long,
+            help = "Describe handlers without a doc comment with a HuggingFace summary of their source (needs HF_TOKEN)"
+        )]
+        handler_summaries: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 8000,
+            help = "Estimated tokens a single LLM call may read, larger router and handler files are analyzed in parts"
+        )]
+        max_tokens_per_call: usize,
+        #[arg(long, help = "Title of the API [default: the crate's package name]")]
+        title: Option<String>,
+        #[arg(
+            long,
+            value_name = "VERSION",
+            help = "Version of the API [default: the crate's package version]"
+        )]
+        api_version: Option<String>,
+        #[arg(
+            long,
+            help = "Description of the API [default: the crate's package description]"
+        )]
+        description: Option<String>,
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Name of the API's contact person or team"
+        )]
+        contact_name: Option<String>,
+        #[arg(
+            long,
+            value_name = "EMAIL",
+            value_parser = parse_email,
+            help = "Email address of the API's contact"
+        )]
+        contact_email: Option<String>,
+        #[arg(long, value_name = "URL", help = "Web page of the API's contact")]
+        contact_url: Option<Url>,
+        #[arg(
+            long,
+            value_name = "SPDX",
+            help = "License the API is published under, e.g. Apache-2.0"
+        )]
+        license: Option<String>,
+        #[arg(
+            long,
+            value_name = "URL",
+            requires = "license",
+            help = "Link to the full text of the license"
+        )]
+        license_url: Option<Url>,
+        #[arg(
+            long,
+            conflicts_with = "from_ir",
+            help = "List the servers of each environment from docgen.toml's [servers], or [package.metadata.docgen.servers] in Cargo.toml"
+        )]
+        server_profiles: bool,
+    },
+    #[command(about = "List the supported frameworks and their conventions")]
+    Frameworks,
+    #[command(about = "Validate a generated spec")]
+    Validate {
+        #[arg(short, long, default_value = "output.yaml")]
+        file: PathBuf,
+    },
+    #[command(about = "Check the codebase and the LLM backend are ready for a run")]
+    Doctor {
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Workspace member to check, when `--dir` is a workspace"
+        )]
+        package: Option<String>,
+        #[arg(
+            long,
+            help = "Web framework of the codebase [default: detected from Cargo.toml]"
+        )]
+        framework: Option<Framework>,
+    },
+    #[command(about = "Compare two generated specs, failing if the new one removes anything")]
+    Diff {
+        #[arg(long, value_name = "FILE")]
+        old: PathBuf,
+        #[arg(long, value_name = "FILE")]
+        new: PathBuf,
     },
 }
 
@@ -28,4 +367,62 @@ pub struct Args {
 
     #[arg(short, long)]
     pub verbose: bool,
+
+    #[arg(
+        short,
+        long,
+        conflicts_with = "verbose",
+        help = "Hide all progress and log output"
+    )]
+    pub quiet: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        help = "Send LLM requests through the proxy at URL, overriding HTTPS_PROXY and HTTP_PROXY"
+    )]
+    pub proxy: Option<Url>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_email, Framework};
+
+    #[test]
+    fn detects_the_framework_from_dependencies() {
+        let deps = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            Framework::detect(&deps(&["serde", "axum", "tokio"])).unwrap(),
+            Framework::RustAxum
+        );
+        assert!(Framework::detect(&deps(&["serde"])).is_err());
+        assert!(Framework::detect(&deps(&["actix-web"]))
+            .unwrap_err()
+            .to_string()
+            .contains("not supported"));
+        assert!(Framework::detect(&deps(&["axum", "rocket"]))
+            .unwrap_err()
+            .to_string()
+            .contains("axum, rocket"));
+    }
+
+    #[test]
+    fn accepts_only_email_addresses() {
+        assert_eq!(
+            parse_email("api@example.com"),
+            Ok("api@example.com".to_owned())
+        );
+        for invalid in [
+            "api",
+            "@example.com",
+            "api@localhost",
+            "api@example.",
+            "api@@example.com",
+            "api team@example.com",
+        ] {
+            assert!(parse_email(invalid).is_err(), "{invalid}");
+        }
+    }
 }
@@ -0,0 +1,256 @@
+use oas3::{
+    spec::{ObjectOrReference, Operation, Parameter, PathItem},
+    OpenApiV3Spec,
+};
+use std::fmt::Display;
+
+/// One difference between two versions of a spec
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Change {
+    /// Removals break clients written against the old spec
+    pub fn is_breaking(&self) -> bool {
+        self.kind == ChangeKind::Removed
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let marker = match self.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        };
+        write!(f, "{marker} {}", self.description)
+    }
+}
+
+/// Compares the paths, operations and parameters of two specs, in path order
+pub fn diff_specs(old: &OpenApiV3Spec, new: &OpenApiV3Spec) -> Vec<Change> {
+    let empty = Default::default();
+    let old_paths = old.paths.as_ref().unwrap_or(&empty);
+    let new_paths = new.paths.as_ref().unwrap_or(&empty);
+    let mut changes = Vec::new();
+
+    for (path, old_item) in old_paths {
+        let Some(new_item) = new_paths.get(path) else {
+            changes.push(change(ChangeKind::Removed, format!("path {path}")));
+            continue;
+        };
+
+        let old_ops = operations(old_item);
+        let new_ops = operations(new_item);
+        for (method, old_op) in &old_ops {
+            let Some((_, new_op)) = new_ops.iter().find(|(m, _)| m == method) else {
+                changes.push(change(ChangeKind::Removed, format!("{method} {path}")));
+                continue;
+            };
+
+            let context = format!("{method} {path}");
+            diff_parameters(
+                &context,
+                &parameters(old, old_item, old_op),
+                &parameters(new, new_item, new_op),
+                &mut changes,
+            );
+        }
+        for (method, _) in &new_ops {
+            if !old_ops.iter().any(|(m, _)| m == method) {
+                changes.push(change(ChangeKind::Added, format!("{method} {path}")));
+            }
+        }
+    }
+
+    for path in new_paths.keys() {
+        if !old_paths.contains_key(path) {
+            changes.push(change(ChangeKind::Added, format!("path {path}")));
+        }
+    }
+
+    changes
+}
+
+fn change(kind: ChangeKind, description: String) -> Change {
+    Change { kind, description }
+}
+
+fn diff_parameters(context: &str, old: &[Parameter], new: &[Parameter], changes: &mut Vec<Change>) {
+    let same = |a: &Parameter, b: &Parameter| a.name == b.name && a.location == b.location;
+
+    for old_param in old {
+        let name = &old_param.name;
+        let Some(new_param) = new.iter().find(|p| same(old_param, p)) else {
+            changes.push(change(
+                ChangeKind::Removed,
+                format!("{context}: parameter `{name}`"),
+            ));
+            continue;
+        };
+
+        let (old_type, new_type) = (param_type(old_param), param_type(new_param));
+        if old_type != new_type {
+            changes.push(change(
+                ChangeKind::Changed,
+                format!("{context}: parameter `{name}` type {old_type} -> {new_type}"),
+            ));
+        }
+
+        let (old_required, new_required) = (
+            old_param.required.unwrap_or_default(),
+            new_param.required.unwrap_or_default(),
+        );
+        if old_required != new_required {
+            changes.push(change(
+                ChangeKind::Changed,
+                format!(
+                    "{context}: parameter `{name}` is now {}",
+                    if new_required { "required" } else { "optional" }
+                ),
+            ));
+        }
+    }
+
+    for new_param in new {
+        if !old.iter().any(|p| same(p, new_param)) {
+            changes.push(change(
+                ChangeKind::Added,
+                format!("{context}: parameter `{}`", new_param.name),
+            ));
+        }
+    }
+}
+
+/// The operations of a path, each method once
+fn operations(item: &PathItem) -> Vec<(String, &Operation)> {
+    let mut ops: Vec<(String, &Operation)> = Vec::new();
+    for (method, op) in item.methods() {
+        let method = method.to_string();
+        if !ops.iter().any(|(m, _)| *m == method) {
+            ops.push((method, op));
+        }
+    }
+    ops
+}
+
+/// The parameters of an operation, including those shared by its path, with references resolved
+fn parameters(spec: &OpenApiV3Spec, item: &PathItem, op: &Operation) -> Vec<Parameter> {
+    item.parameters
+        .iter()
+        .chain(op.parameters.iter())
+        .filter_map(|p| match p {
+            ObjectOrReference::Object(p) => Some(p.clone()),
+            ObjectOrReference::Ref { .. } => p.resolve(spec).ok(),
+        })
+        .collect()
+}
+
+/// The schema type of a parameter as written in the spec, e.g. `string` or `["integer","null"]`
+fn param_type(param: &Parameter) -> String {
+    match &param.schema {
+        Some(ObjectOrReference::Object(schema)) => schema
+            .schema_type
+            .as_ref()
+            .and_then(|t| serde_json::to_value(t).ok())
+            .map(|t| match t {
+                serde_json::Value::String(t) => t,
+                t => t.to_string(),
+            })
+            .unwrap_or_else(|| "any".to_owned()),
+        Some(ObjectOrReference::Ref { ref_path, .. }) => ref_path.clone(),
+        None => "any".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_specs, ChangeKind};
+    use indoc::indoc;
+    use oas3::OpenApiV3Spec;
+
+    fn parse(yaml: &str) -> OpenApiV3Spec {
+        serde_yaml::from_str(yaml).expect("invalid test spec")
+    }
+
+    #[test]
+    fn reports_path_operation_and_parameter_changes() {
+        let old = parse(indoc! {"
+            openapi: 3.0.3
+            info:
+              title: Generated API
+              version: 1.0.0
+            paths:
+              /health:
+                get:
+                  responses: {}
+              /users/{id}:
+                get:
+                  parameters:
+                  - name: id
+                    in: path
+                    required: true
+                    schema:
+                      type: string
+                  - name: verbose
+                    in: query
+                    schema:
+                      type: boolean
+                  responses: {}
+                delete:
+                  responses: {}
+        "});
+        let new = parse(indoc! {"
+            openapi: 3.0.3
+            info:
+              title: Generated API
+              version: 1.0.0
+            paths:
+              /users/{id}:
+                get:
+                  parameters:
+                  - name: id
+                    in: path
+                    required: true
+                    schema:
+                      type: integer
+                  - name: fields
+                    in: query
+                    schema:
+                      type: string
+                  responses: {}
+                put:
+                  responses: {}
+              /users:
+                post:
+                  responses: {}
+        "});
+
+        let changes = diff_specs(&old, &new);
+        let lines = changes.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "- path /health",
+                "~ GET /users/{id}: parameter `id` type string -> integer",
+                "- GET /users/{id}: parameter `verbose`",
+                "+ GET /users/{id}: parameter `fields`",
+                "- DELETE /users/{id}",
+                "+ PUT /users/{id}",
+                "+ path /users",
+            ]
+        );
+        assert_eq!(changes.iter().filter(|c| c.is_breaking()).count(), 3);
+        assert!(diff_specs(&old, &old).is_empty());
+        assert_eq!(changes[0].kind, ChangeKind::Removed);
+    }
+}
@@ -0,0 +1,232 @@
+use super::args::Framework;
+use crate::{
+    call_graph::manifest::Manifest,
+    generators::rust_axum::RustAxumGenerator,
+    llm::{
+        openai::deepseek::{DeepseekBackend, API_KEY_VAR},
+        retry::RetryPolicy,
+        LLMQueryRequest, LLM,
+    },
+};
+use std::{fmt::Display, path::Path};
+
+/// One item of the `doctor` checklist, with what was found or why it failed
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Result<String, String>,
+}
+
+impl Check {
+    fn new(name: &'static str, outcome: Result<String, String>) -> Self {
+        Self { name, outcome }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.outcome {
+            Ok(found) => write!(f, "[pass] {}: {found}", self.name),
+            Err(reason) => write!(f, "[fail] {}: {reason}", self.name),
+        }
+    }
+}
+
+/// Checks the codebase at `dir` can be analysed, in order, up to the first that fails since
+/// the rest depend on it: `dir` exists, `package` is a member of its workspace when given,
+/// its `Cargo.toml` has a package, its framework is known and the entry file is there
+pub fn check_codebase(
+    dir: &Path,
+    package: Option<&str>,
+    framework: Option<Framework>,
+) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let fail = |mut checks: Vec<Check>, name, reason| {
+        checks.push(Check::new(name, Err(reason)));
+        checks
+    };
+
+    if !dir.is_dir() {
+        return fail(
+            checks,
+            "codebase directory",
+            format!("{} is not a directory", dir.display()),
+        );
+    }
+    checks.push(Check::new(
+        "codebase directory",
+        Ok(dir.display().to_string()),
+    ));
+
+    // the workspace member is checked like `generate --package` would analyse it
+    let dir = match package {
+        Some(package) => {
+            match Manifest::try_new(&dir.to_path_buf()).and_then(|m| m.member_dir(package)) {
+                Ok(member_dir) => {
+                    checks.push(Check::new(
+                        "workspace member",
+                        Ok(member_dir.display().to_string()),
+                    ));
+                    member_dir
+                }
+                Err(e) => return fail(checks, "workspace member", format!("{e:#}")),
+            }
+        }
+        None => dir.to_path_buf(),
+    };
+
+    let manifest = match Manifest::try_new(&dir) {
+        Ok(manifest) => manifest,
+        Err(e) => return fail(checks, "Cargo.toml package", format!("{e:#}")),
+    };
+    let Some(package) = manifest.package_name() else {
+        return fail(
+            checks,
+            "Cargo.toml package",
+            "Cargo.toml has no [package], pick a workspace member with --package".to_owned(),
+        );
+    };
+    checks.push(Check::new("Cargo.toml package", Ok(package)));
+
+    let framework = match framework {
+        Some(framework) => framework,
+        None => match Framework::detect(&manifest.dependency_names()) {
+            Ok(framework) => framework,
+            Err(e) => return fail(checks, "framework", e.to_string()),
+        },
+    };
+    let (info, entry_file) = match framework {
+        Framework::RustAxum => (
+            RustAxumGenerator::info(),
+            RustAxumGenerator::entry_file(&dir),
+        ),
+    };
+    checks.push(Check::new("framework", Ok(info.name.to_owned())));
+
+    if !entry_file.is_file() {
+        return fail(
+            checks,
+            "entry file",
+            format!("{} doesn't exist", entry_file.display()),
+        );
+    }
+    checks.push(Check::new(
+        "entry file",
+        Ok(entry_file.display().to_string()),
+    ));
+
+    checks
+}
+
+/// Checks the LLM backend's API key is set and that it answers a tiny query
+pub async fn check_llm() -> Vec<Check> {
+    let backend = match DeepseekBackend::try_new() {
+        Ok(backend) => backend,
+        Err(e) => return vec![Check::new("LLM API key", Err(e.to_string()))],
+    };
+    let mut checks = vec![Check::new(
+        "LLM API key",
+        Ok(format!("{API_KEY_VAR} is set")),
+    )];
+
+    // a failing key or endpoint should fail the check rather than be retried
+    let mut llm = backend
+        .with_retry_policy(RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        })
+        .conversation("Answer with the JSON object {\"ok\": true}.");
+    let query = LLMQueryRequest {
+        history: vec![],
+        query: "Are you there? Answer in json.".to_owned(),
+    };
+    checks.push(Check::new(
+        "LLM test query",
+        match llm.execute_query(query).await {
+            Ok(_) => Ok("the backend answered".to_owned()),
+            Err(e) => Err(e.to_string()),
+        },
+    ));
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_codebase;
+    use crate::utils::fixtures::{package_manifest, CrateFixture};
+
+    #[test]
+    fn stops_at_the_first_failing_codebase_check() {
+        let krate = CrateFixture::new(
+            "[package]\nname = \"api\"\nversion = \"0.1.0\"\n\n[dependencies]\naxum = \"0.7\"\n",
+        );
+        let outcomes = |dir: &std::path::Path| {
+            check_codebase(dir, None, None)
+                .iter()
+                .map(|check| (check.name, check.passed()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            outcomes(&krate.root().join("missing")),
+            vec![("codebase directory", false)]
+        );
+        assert_eq!(
+            outcomes(&krate.root()),
+            vec![
+                ("codebase directory", true),
+                ("Cargo.toml package", true),
+                ("framework", true),
+                ("entry file", false),
+            ]
+        );
+
+        krate.write("src/lib.rs", "pub fn app() {}\n");
+        assert!(check_codebase(&krate.root(), None, None)
+            .iter()
+            .all(|check| check.passed()));
+    }
+
+    #[test]
+    fn checks_the_workspace_member_picked_with_package() {
+        let workspace = CrateFixture::new("[workspace]\nmembers = [\"services/*\"]\n");
+        workspace.write(
+            "services/orders/Cargo.toml",
+            &format!(
+                "{}\n[dependencies]\naxum = \"0.7\"\n",
+                package_manifest("orders")
+            ),
+        );
+        workspace.write("services/orders/src/main.rs", "fn main() {}\n");
+        let outcomes = |package| {
+            check_codebase(&workspace.root(), package, None)
+                .iter()
+                .map(|check| (check.name, check.passed()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            outcomes(None),
+            vec![("codebase directory", true), ("Cargo.toml package", false)]
+        );
+        assert_eq!(
+            outcomes(Some("billing")),
+            vec![("codebase directory", true), ("workspace member", false)]
+        );
+        assert_eq!(
+            outcomes(Some("orders")),
+            vec![
+                ("codebase directory", true),
+                ("workspace member", true),
+                ("Cargo.toml package", true),
+                ("framework", true),
+                ("entry file", true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::Context;
+use regex::Regex;
+
+/// Keeps routes whose path matches an `--include` glob (all of them when there are none)
+/// and no `--exclude` glob. Excludes win over includes.
+///
+/// `*` matches within a path segment, `**` across segments, so `/api/**` matches `/api`
+/// and everything below it.
+pub struct RouteFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl RouteFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> anyhow::Result<Self> {
+        let compile = |globs: &[String]| {
+            globs
+                .iter()
+                .map(|glob| glob_to_regex(glob))
+                .collect::<anyhow::Result<Vec<Regex>>>()
+        };
+
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub fn keeps(&self, path: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|r| r.is_match(path));
+        included && !self.exclude.iter().any(|r| r.is_match(path))
+    }
+}
+
+fn glob_to_regex(glob: &str) -> anyhow::Result<Regex> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            // a trailing `/**` also matches the directory itself
+            '/' if chars.clone().collect::<String>() == "**" => {
+                chars.nth(1);
+                pattern.push_str("(/.*)?");
+            }
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    Regex::new(&pattern).context(format!("invalid path glob {glob}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RouteFilter;
+
+    fn route_filter(include: &[&str], exclude: &[&str]) -> RouteFilter {
+        let owned = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        RouteFilter::new(&owned(include), &owned(exclude)).unwrap()
+    }
+
+    #[test]
+    fn keeps_everything_without_patterns() {
+        let filter = route_filter(&[], &[]);
+        assert!(filter.keeps("/health"));
+        assert!(filter.keeps("/api/users/:id"));
+    }
+
+    #[test]
+    fn matches_single_and_multi_segment_globs() {
+        let filter = route_filter(&["/api/**", "/v?/*"], &[]);
+        assert!(filter.keeps("/api"));
+        assert!(filter.keeps("/api/users/:id"));
+        assert!(filter.keeps("/v1/status"));
+        assert!(!filter.keeps("/v1/status/detail"));
+        assert!(!filter.keeps("/apiary"));
+        assert!(!filter.keeps("/health"));
+    }
+
+    #[test]
+    fn excludes_win_over_overlapping_includes() {
+        let filter = route_filter(&["/api/**"], &["/api/admin/**", "/api/*/internal"]);
+        assert!(filter.keeps("/api/users"));
+        assert!(!filter.keeps("/api/admin"));
+        assert!(!filter.keeps("/api/admin/users/:id"));
+        assert!(!filter.keeps("/api/users/internal"));
+
+        let filter = route_filter(&[], &["/health"]);
+        assert!(filter.keeps("/api/users"));
+        assert!(!filter.keeps("/health"));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::domain::ir::{BodyType, IR};
+use crate::openapi::object_schema;
+use anyhow::Context;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, path::Path};
+
+const DRAFT_7: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Builds a standalone Draft 7 JSON Schema for every named JSON request or response body
+/// in the IR, keyed by DTO name. Bodies shared by several routes are only emitted once.
+pub fn json_schemas(ir: &IR) -> anyhow::Result<BTreeMap<String, Value>> {
+    let mut schemas = BTreeMap::new();
+
+    let bodies = ir
+        .routes
+        .iter()
+        .flat_map(|route| route.body.iter().chain(route.response.iter()));
+    for body in bodies {
+        let (BodyType::Json, Some(identifier)) = (&body.body_type, &body.identifier) else {
+            continue;
+        };
+        if schemas.contains_key(identifier) {
+            continue;
+        }
+
+        let mut schema =
+            serde_json::to_value(object_schema(&body.properties, &body.required_fields))
+                .context(format!("failed to serialize schema for {identifier}"))?;
+
+        if let Value::Object(fields) = &mut schema {
+            fields.insert("$schema".to_owned(), json!(DRAFT_7));
+            fields.insert("$id".to_owned(), json!(format!("{identifier}.json")));
+            fields.insert("title".to_owned(), json!(identifier));
+        }
+
+        schemas.insert(identifier.to_owned(), schema);
+    }
+
+    Ok(schemas)
+}
+
+/// Serializes `value` indented for people to read, or on a single line for tools
+pub fn to_json<T: Serialize + ?Sized>(value: &T, pretty: bool) -> serde_json::Result<String> {
+    match pretty {
+        true => serde_json::to_string_pretty(value),
+        false => serde_json::to_string(value),
+    }
+}
+
+/// Writes each schema from `json_schemas` to `<dir>/<DTO>.json`, see `to_json` for `pretty`
+pub fn write_json_schemas(ir: &IR, dir: &Path, pretty: bool) -> anyhow::Result<usize> {
+    let schemas = json_schemas(ir)?;
+    std::fs::create_dir_all(dir).context(format!("failed to create {:?}", dir))?;
+
+    for (identifier, schema) in &schemas {
+        let file = dir.join(format!("{identifier}.json"));
+        let json = to_json(schema, pretty).context("failed to serialize schema")?;
+        std::fs::write(&file, json).context(format!("failed to write schema to {:?}", file))?;
+    }
+
+    Ok(schemas.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_schemas, to_json};
+    use crate::domain::ir::{
+        Body, BodyProperty, BodyShape, BodyType, HTTPMethod, OperationDocs, PropType, Route, IR,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn wraps_named_json_bodies_in_a_draft_7_envelope() {
+        let route = |path: &str, body_type, identifier: Option<&str>| Route {
+            path: path.to_owned(),
+            method: HTTPMethod::POST,
+            parameters: vec![],
+            body: Some(Body {
+                body_type,
+                identifier: identifier.map(str::to_owned),
+                shape: BodyShape::Single,
+                required_fields: vec!["name".to_owned()],
+                properties: HashMap::from([(
+                    "name".to_owned(),
+                    BodyProperty {
+                        prop_type: PropType::String,
+                        properties: HashMap::new(),
+                        required_fields: vec![],
+                        example: None,
+                    },
+                )]),
+                alternatives: vec![],
+            }),
+            response: None,
+            response_status: None,
+            error_responses: vec![],
+            security: None,
+            docs: OperationDocs::default(),
+            source: None,
+            handler: None,
+        };
+
+        let ir = IR {
+            routes: vec![
+                route("/users", BodyType::Json, Some("CreateUserDto")),
+                route("/users/bulk", BodyType::Json, Some("CreateUserDto")),
+                route("/upload", BodyType::Multipart, Some("Multipart")),
+                route("/anonymous", BodyType::Json, None),
+            ],
+            warnings: vec![],
+            tags: vec![],
+        };
+
+        let schemas = json_schemas(&ir).unwrap();
+        assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["CreateUserDto"]);
+
+        let schema = &schemas["CreateUserDto"];
+        assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(schema["$id"], "CreateUserDto.json");
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"][0], "name");
+        assert_eq!(schema["properties"]["name"]["type"], "string");
+
+        let pretty = to_json(&schemas, true).unwrap();
+        let compact = to_json(&schemas, false).unwrap();
+        assert!(pretty.contains('\n'));
+        assert!(!compact.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&compact).unwrap()
+        );
+    }
+}
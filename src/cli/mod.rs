@@ -1,248 +1,514 @@
 use crate::{
+    call_graph::manifest::Manifest,
     code::downloader,
-    domain::ir::{self, BodyType, HTTPMethod, PropType},
+    domain::ir,
     generators::{
-        rust_axum::{RustAxumGenerator, RustAxumGeneratorArgsBuilder},
+        rust_axum::{RustAxumGenerator, RustAxumGeneratorArgsBuilder, Verbosity},
         Generator,
     },
+    llm::LLMUsage,
+    openapi::{self, SpecOptions},
 };
 use anyhow::{bail, Context};
-use args::{Args, Commands, Framework};
-use clap::Parser;
-use indoc::formatdoc;
-use oas3::{
-    spec::{
-        Info, MediaType, ObjectOrReference, ObjectSchema, Operation, Parameter, PathItem,
-        RequestBody, Response, SchemaType, SchemaTypeSet,
-    },
-    OpenApiV3Spec,
+use args::{Args, AuthScheme, Commands, Framework};
+use clap::{Parser, ValueEnum};
+use oas3::spec::{Contact, License, SecurityScheme};
+use redact::Redactor;
+use report::RunReport;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
-use regex::Regex;
-use std::{collections::BTreeMap, fs::File, io::Write, path::PathBuf};
 
 pub mod args;
+pub mod diff;
+pub mod doctor;
+pub mod filter;
+pub mod json_schema;
+pub mod redact;
+pub mod report;
+pub mod servers;
+pub mod validate;
+
+const OUTPUT_FILE: &str = "output.yaml";
+
+/// Where a `generate` run writes the spec, the JSON schemas and the run report
+#[derive(Debug, PartialEq)]
+struct OutputPaths {
+    spec: PathBuf,
+    schemas: Option<PathBuf>,
+    report: Option<PathBuf>,
+}
+
+impl OutputPaths {
+    /// With `--output-dir` everything goes under conventional names in that directory,
+    /// otherwise the spec is `output.yaml` and the rest goes where `--emit-schemas` and
+    /// `--report` say, if anywhere
+    fn new(output_dir: Option<&Path>, schemas: Option<PathBuf>, report: Option<PathBuf>) -> Self {
+        match output_dir {
+            Some(dir) => Self {
+                spec: dir.join("openapi.yaml"),
+                schemas: Some(dir.join("schemas")),
+                report: Some(dir.join("report.json")),
+            },
+            None => Self {
+                spec: PathBuf::from(OUTPUT_FILE),
+                schemas,
+                report,
+            },
+        }
+    }
+}
+
 pub struct Cli;
 
 impl Cli {
-    pub async fn init() -> anyhow::Result<()> {
+    pub fn init() -> anyhow::Result<()> {
         let args = Args::parse();
 
+        // the LLM clients are built inside their crates and only read the proxy from the
+        // environment, so the override goes through it while this is still the only thread
+        if let Some(proxy) = &args.proxy {
+            std::env::set_var("HTTPS_PROXY", proxy.as_str());
+            std::env::set_var("HTTP_PROXY", proxy.as_str());
+        }
+
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("failed to start the tokio runtime")?
+            .block_on(Self::run(args))
+    }
+
+    async fn run(args: Args) -> anyhow::Result<()> {
+        let verbosity = match (args.quiet, args.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (_, true) => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        };
+
         if let Some(command) = args.command {
             match command {
                 Commands::Generate {
                     url,
                     dir,
+                    package: member,
                     framework,
+                    auth_scheme,
+                    api_key_header,
+                    api_key_query,
+                    strict,
+                    timeout,
+                    concurrency,
+                    emit_ir,
+                    emit_schemas,
+                    report,
+                    output_dir,
+                    pretty,
+                    compact,
+                    from_ir,
+                    base_path,
+                    entry_fn,
+                    routes_file,
+                    dry_run,
+                    no_cache,
+                    work_dir,
+                    include_standard_headers,
+                    include,
+                    exclude,
+                    redact,
+                    no_overwrite,
+                    stream,
+                    prompt_dir,
+                    max_depth,
+                    examples,
+                    exclude_services,
+                    exclude_private,
+                    handler_summaries,
+                    max_tokens_per_call,
+                    title,
+                    api_version,
+                    description,
+                    contact_name,
+                    contact_email,
+                    contact_url,
+                    license,
+                    license_url,
+                    server_profiles,
                 } => {
-                    let dir = match (dir, url) {
-                        (Some(dir), None) => dir,
-                        (None, Some(url)) => {
-                            let download_dir = PathBuf::from("/temp/docgen/code");
-                            downloader::download_from_url(&url, &download_dir)?;
-                            download_dir
-                        }
-                        _ => bail!("either `--dir` or `--url` must be provided. Run docgen -h to check usage")
+                    let started = Instant::now();
+                    let (scheme_name, security_scheme) = match auth_scheme {
+                        AuthScheme::Bearer => (
+                            "bearerAuth",
+                            SecurityScheme::Http {
+                                description: None,
+                                scheme: "bearer".to_owned(),
+                                bearer_format: Some("JWT".to_owned()),
+                            },
+                        ),
+                        AuthScheme::Basic => (
+                            "basicAuth",
+                            SecurityScheme::Http {
+                                description: None,
+                                scheme: "basic".to_owned(),
+                                bearer_format: None,
+                            },
+                        ),
                     };
 
-                    let generator = match framework {
-                        Framework::RustAxum => {
-                            let args = RustAxumGeneratorArgsBuilder::default()
-                                .code_dir(dir)
-                                .build()
-                                .context("failed to build rust-axum args")?;
-                            RustAxumGenerator::new(args)
+                    let global_security = match (api_key_header, api_key_query) {
+                        (Some(name), _) => Some((name, "header")),
+                        (_, Some(name)) => Some((name, "query")),
+                        _ => None,
+                    }
+                    .map(|(name, location)| {
+                        (
+                            "apiKeyAuth".to_owned(),
+                            SecurityScheme::ApiKey {
+                                description: None,
+                                name,
+                                location: location.to_owned(),
+                            },
+                        )
+                    });
+
+                    let route_filter = filter::RouteFilter::new(&include, &exclude)?;
+                    let redactor = Redactor::new(&redact)?;
+
+                    let output = OutputPaths::new(output_dir.as_deref(), emit_schemas, report);
+                    if no_overwrite && !dry_run && output.spec.exists() {
+                        bail!(
+                            "{} already exists and --no-overwrite was given",
+                            output.spec.display()
+                        );
+                    }
+                    if let (Some(dir), false) = (&output_dir, dry_run) {
+                        std::fs::create_dir_all(dir)
+                            .context(format!("failed to create {:?}", dir))?;
+                    }
+
+                    let mut spec_options = SpecOptions {
+                        base_path,
+                        security_scheme_name: scheme_name.to_owned(),
+                        security_scheme,
+                        include_standard_headers,
+                        title,
+                        version: api_version,
+                        description,
+                        contact: (contact_name.is_some()
+                            || contact_email.is_some()
+                            || contact_url.is_some())
+                        .then(|| Contact {
+                            name: contact_name,
+                            url: contact_url,
+                            email: contact_email,
+                            extensions: BTreeMap::new(),
+                        }),
+                        license: license.map(|name| License {
+                            name,
+                            identifier: None,
+                            url: license_url,
+                            extensions: BTreeMap::new(),
+                        }),
+                        servers: vec![],
+                        global_security,
+                    };
+
+                    let (mut ir, interruption, usage) = match from_ir {
+                        Some(ir_file) => {
+                            let json = std::fs::read_to_string(&ir_file)
+                                .context(format!("failed to read IR from {:?}", ir_file))?;
+                            let ir = serde_json::from_str::<ir::IR>(&json)
+                                .context(format!("failed to parse IR from {:?}", ir_file))?;
+                            (ir, None, LLMUsage::default())
+                        }
+                        None => {
+                            let dir = match (dir, url) {
+                                (Some(dir), None) => dir,
+                                (None, Some(url)) => {
+                                    let work_dir =
+                                        work_dir.unwrap_or_else(downloader::default_work_dir);
+                                    downloader::download_to_work_dir(&url, &work_dir)?
+                                }
+                                _ => bail!("either `--dir` or `--url` must be provided. Run docgen -h to check usage")
+                            };
+                            let dir = match member {
+                                Some(member) => Manifest::try_new(&dir)?.member_dir(&member)?,
+                                None => dir,
+                            };
+                            // the analysed crate's Cargo.toml, for the spec's info defaults
+                            let package = Manifest::try_new(&dir).ok();
+                            if let Some(package) = &package {
+                                spec_options.title =
+                                    spec_options.title.take().or_else(|| package.package_name());
+                                spec_options.version = spec_options
+                                    .version
+                                    .take()
+                                    .or_else(|| package.package_version());
+                                spec_options.description = spec_options
+                                    .description
+                                    .take()
+                                    .or_else(|| package.package_description());
+                            }
+                            if server_profiles {
+                                spec_options.servers =
+                                    servers::server_profiles(&dir, package.as_ref())?;
+                                if spec_options.servers.is_empty() {
+                                    bail!(
+                                        "--server-profiles was given but neither {} nor Cargo.toml lists any servers",
+                                        servers::PROFILES_FILE
+                                    );
+                                }
+                            }
+                            let framework = match (framework, &package) {
+                                (Some(framework), _) => framework,
+                                (None, Some(package)) => {
+                                    Framework::detect(&package.dependency_names())?
+                                }
+                                (None, None) => bail!(
+                                    "couldn't read {} to detect the framework, pass `--framework`",
+                                    dir.join("Cargo.toml").display()
+                                ),
+                            };
+
+                            let generator = match framework {
+                                Framework::RustAxum => {
+                                    let args = RustAxumGeneratorArgsBuilder::default()
+                                        .code_dir(dir)
+                                        .security_scheme(scheme_name)
+                                        .concurrency(concurrency)
+                                        .verbosity(verbosity)
+                                        .entry_fn(entry_fn)
+                                        .routes_file(routes_file)
+                                        .dry_run(dry_run)
+                                        .cache(!no_cache)
+                                        .prompt_dir(prompt_dir)
+                                        .max_depth(max_depth)
+                                        .examples(examples)
+                                        .services(!exclude_services)
+                                        .private_handlers(!exclude_private)
+                                        .handler_summaries(handler_summaries)
+                                        .max_tokens_per_call(max_tokens_per_call)
+                                        .proxy(args.proxy.clone())
+                                        .build()
+                                        .context("failed to build rust-axum args")?;
+                                    RustAxumGenerator::new(args)
+                                }
+                            };
+
+                            let generation = async {
+                                match timeout {
+                                    Some(secs) => tokio::time::timeout(
+                                        Duration::from_secs(secs),
+                                        generator.generate_ir(),
+                                    )
+                                    .await
+                                    .map_err(|_| {
+                                        format!("generation timed out after {secs} seconds")
+                                    }),
+                                    None => Ok(generator.generate_ir().await),
+                                }
+                            };
+
+                            let stream_spec = async {
+                                let mut partial_count = generator.watch_partial_ir();
+                                while partial_count.changed().await.is_ok() {
+                                    let mut partial = generator.partial_ir();
+                                    partial
+                                        .routes
+                                        .retain(|route| route_filter.keeps(&route.path));
+                                    let mut spec = openapi::build_spec(&partial, &spec_options);
+                                    redactor.redact(&mut spec.spec)?;
+                                    let serialized = spec.to_yaml()?;
+                                    write_atomically(&output.spec, &serialized)?;
+                                }
+                                Ok::<_, anyhow::Error>(())
+                            };
+
+                            // on timeout or ctrl-c the routes generated so far are still written out
+                            let (ir, interruption) = tokio::select! {
+                                result = generation => match result {
+                                    Ok(ir) => (ir?, None),
+                                    Err(reason) => (generator.partial_ir(), Some(reason)),
+                                },
+                                _ = tokio::signal::ctrl_c() => {
+                                    (generator.partial_ir(), Some("generation interrupted".to_owned()))
+                                }
+                                Err(e) = stream_spec, if stream => {
+                                    return Err(e.context("failed to write the partial spec"));
+                                }
+                            };
+                            (ir, interruption, generator.usage())
                         }
                     };
 
-                    let ir = generator.generate_ir().await?;
+                    if dry_run {
+                        return Ok(());
+                    }
+
+                    // every output below, down to the IR printed at the end, reads the redacted IR
+                    let mut redacted = redactor.redact(&mut ir)?;
 
-                    let mut paths: BTreeMap<String, PathItem> = BTreeMap::new();
+                    // people read what's written from a terminal, tools what's written in a pipe
+                    let pretty_json = match (pretty, compact) {
+                        (true, _) => true,
+                        (_, true) => false,
+                        _ => std::io::stdout().is_terminal(),
+                    };
 
-                    fn to_route_path(s: &str) -> String {
-                        let r = Regex::new("/:(\\w+)").unwrap();
-                        r.replace_all(s, "/{$1}").to_string()
+                    if let Some(ir_file) = emit_ir {
+                        let json = json_schema::to_json(&ir, pretty_json)
+                            .context("failed to serialize IR")?;
+                        std::fs::write(&ir_file, json)
+                            .context(format!("failed to write IR to {:?}", ir_file))?;
                     }
 
-                    fn get_param_type(param: &ir::Parameter) -> String {
-                        match param.param_type {
-                            ir::ParamType::Path => "path",
-                            ir::ParamType::Query => "query",
-                            ir::ParamType::Unknown => "path", // TODO: fix this horror
+                    if !ir.warnings.is_empty() {
+                        println!("generated with {} warning(s):", ir.warnings.len());
+                        for warning in &ir.warnings {
+                            println!("  - {warning}");
                         }
-                        .to_owned()
-                    }
 
-                    fn get_param_schema_type(param: &ir::Parameter) -> String {
-                        match param.data_type {
-                            ir::ParamDataType::String => "string",
-                            ir::ParamDataType::Integer => "integer",
-                            ir::ParamDataType::Float => "float",
-                            ir::ParamDataType::Unknown => "string",
+                        if strict {
+                            bail!(
+                                "{} item(s) could not be fully resolved in strict mode",
+                                ir.warnings.len()
+                            );
                         }
-                        .to_owned()
                     }
 
-                    for route in &ir.routes {
-                        let mut response = BTreeMap::new();
+                    ir.routes.retain(|route| route_filter.keeps(&route.path));
 
-                        response.insert(
-                            "200".to_owned(),
-                            ObjectOrReference::Object(Response {
-                                description: Some("Successful operation".to_owned()),
-                                ..Default::default()
-                            }),
-                        );
+                    let mut generated = openapi::build_spec(&ir, &spec_options);
+                    redacted += redactor.redact(&mut generated.spec)?;
+                    if redacted > 0 {
+                        println!("redacted {redacted} value(s) matching --redact");
+                    }
 
-                        let mut parameters = Vec::new();
-
-                        for param in &route.parameters {
-                            println!("param={:#?}", param);
-                            let spec = formatdoc! {"
-                                    name: {name}
-                                    in: {param_type}
-                                    description: {description}
-                                    required: true
-                                    schema:
-                                        type: {schema_type}
-                                ", name = param.name, param_type = get_param_type(&param), description = param.description, schema_type = get_param_schema_type(&param)};
-
-                            println!("spec={:#?}", spec);
-                            let parameter = serde_yaml::from_str::<Parameter>(&spec).unwrap();
-                            parameters.push(ObjectOrReference::Object(parameter));
+                    if !generated.collisions.is_empty() {
+                        for collision in &generated.collisions {
+                            println!("warning: {collision}");
                         }
 
-                        let request_body = if let Some(body) = &route.body {
-                            Some(ObjectOrReference::Object(RequestBody {
-                                content: {
-                                    let mut content = BTreeMap::new();
-
-                                    match body.body_type {
-                                        BodyType::Json => {
-                                            let media_type = MediaType {
-                                                schema: Some(ObjectOrReference::Object(
-                                                    ObjectSchema {
-                                                        schema_type: Some(SchemaTypeSet::Single(
-                                                            oas3::spec::SchemaType::Object,
-                                                        )),
-                                                        properties: {
-                                                            let mut props = BTreeMap::new();
-
-                                                            for (prop, prop_body) in
-                                                                &body.properties
-                                                            {
-                                                                props.insert(
-                                                                    prop.clone(),
-                                                                    ObjectOrReference::Object(
-                                                                        ObjectSchema {
-                                                                            schema_type: Some(SchemaTypeSet::Single(match prop_body.prop_type {
-                                                                                PropType::Number => SchemaType::Number,
-                                                                                PropType::Boolean => SchemaType::Boolean,
-                                                                                PropType::String => SchemaType::String,
-                                                                                PropType::Object => SchemaType::Object,
-                                                                            })),
-                                                                            ..Default::default()
-                                                                        },
-                                                                    ),
-                                                                );
-                                                            }
-
-                                                            props
-                                                        },
-                                                        ..Default::default()
-                                                    },
-                                                )),
-                                                ..Default::default()
-                                            };
-
-                                            content
-                                                .insert("application/json".to_string(), media_type);
-                                        }
-                                    }
-
-                                    content
-                                },
-                                required: Some(true),
-                                ..Default::default()
-                            }))
-                        } else {
-                            None
-                        };
+                        if strict {
+                            bail!(
+                                "{} duplicate route(s) found in strict mode",
+                                generated.collisions.len()
+                            );
+                        }
+                    }
 
-                        let op = Operation {
-                            parameters,
-                            request_body,
-                            responses: Some(response),
-                            ..Default::default()
-                        };
+                    for ambiguous in &generated.ambiguous_operations {
+                        println!("warning: {ambiguous}");
+                    }
 
-                        let route_path = to_route_path(&route.path);
+                    let serialized = generated.to_yaml()?;
 
-                        if let Some(existing_path) = paths.get_mut(&route_path) {
-                            match route.method {
-                                HTTPMethod::GET => existing_path.get = Some(op),
-                                HTTPMethod::POST => existing_path.post = Some(op),
-                                HTTPMethod::PUT => existing_path.put = Some(op),
-                                HTTPMethod::PATCH => existing_path.patch = Some(op),
-                                HTTPMethod::DELETE => existing_path.delete = Some(op),
-                            };
+                    if stream {
+                        // the partial spec written while streaming is replaced, even with --no-overwrite
+                        write_atomically(&output.spec, &serialized)?;
+                    } else {
+                        let mut x = if no_overwrite {
+                            File::create_new(&output.spec)
                         } else {
-                            let path_item = match route.method {
-                                HTTPMethod::GET => PathItem {
-                                    get: Some(op),
-                                    ..Default::default()
-                                },
-                                HTTPMethod::POST => PathItem {
-                                    post: Some(op),
-                                    ..Default::default()
-                                },
-                                HTTPMethod::PUT => PathItem {
-                                    put: Some(op),
-                                    ..Default::default()
-                                },
-                                HTTPMethod::PATCH => PathItem {
-                                    patch: Some(op),
-                                    ..Default::default()
-                                },
-                                HTTPMethod::DELETE => PathItem {
-                                    delete: Some(op),
-                                    ..Default::default()
-                                },
-                            };
+                            File::create(&output.spec)
+                        }
+                        .context(format!("failed to create {}", output.spec.display()))?;
+
+                        x.write_all(serialized.as_bytes())
+                            .context("failed to write to file")?;
+                    }
+
+                    if let Some(schema_dir) = output.schemas {
+                        let written =
+                            json_schema::write_json_schemas(&ir, &schema_dir, pretty_json)?;
+                        println!("wrote {written} JSON schema(s) to {}", schema_dir.display());
+                    }
 
-                            paths.insert(route_path, path_item);
+                    if let Some(report_file) = output.report {
+                        RunReport::new(
+                            &ir,
+                            &generated,
+                            usage,
+                            started.elapsed(),
+                            interruption.clone(),
+                        )
+                        .write(&report_file, pretty_json)?;
+                    }
+
+                    println!("IR: {:#?}", ir);
+
+                    if let Some(reason) = interruption {
+                        bail!(
+                            "{reason}, wrote {} partially generated route(s)",
+                            ir.routes.len()
+                        );
+                    }
+                }
+                Commands::Frameworks => {
+                    for framework in Framework::value_variants() {
+                        let info = match framework {
+                            Framework::RustAxum => RustAxumGenerator::info(),
                         };
+
+                        println!("{}", info.name);
+                        println!("  entry file: {}", info.default_entry.display());
+                        println!("  router signatures: {}", info.router_signatures.join(", "));
                     }
+                }
+                Commands::Validate { file } => {
+                    let issues = validate::validate_spec_file(&file)?;
 
-                    let spec = OpenApiV3Spec {
-                        openapi: "3.0.3".to_owned(),
-                        info: Info {
-                            title: "Generated API".to_owned(),
-                            summary: None,
-                            description: Some("A description of the generated API".to_owned()),
-                            terms_of_service: None,
-                            contact: None,
-                            license: None,
-                            version: "1.0.0".to_string(),
-                            extensions: BTreeMap::new(),
-                        },
-                        servers: vec![],
-                        paths: Some(paths),
-                        webhooks: BTreeMap::new(),
-                        components: None,
-                        extensions: BTreeMap::new(),
-                        tags: vec![],
-                        external_docs: None,
-                    };
+                    for issue in &issues {
+                        println!("{issue}");
+                    }
+
+                    if !issues.is_empty() {
+                        bail!("{} found {} issue(s)", file.display(), issues.len());
+                    }
 
-                    let serialized =
-                        serde_yaml::to_string(&spec).context("failed to serialize spec")?;
+                    println!("{} is valid", file.display());
+                }
+                Commands::Doctor {
+                    dir,
+                    package,
+                    framework,
+                } => {
+                    let mut checks = doctor::check_codebase(&dir, package.as_deref(), framework);
+                    checks.extend(doctor::check_llm().await);
 
-                    let mut x = File::create("output.yaml").context("failed to create file")?;
+                    for check in &checks {
+                        println!("{check}");
+                    }
 
-                    x.write(serialized.as_bytes())
-                        .context("failed to write to file")?;
+                    let failed = checks.iter().filter(|c| !c.passed()).count();
+                    if failed > 0 {
+                        bail!("{failed} check(s) failed");
+                    }
+                }
+                Commands::Diff { old, new } => {
+                    let changes =
+                        diff::diff_specs(&validate::read_spec(&old)?, &validate::read_spec(&new)?);
 
-                    println!("IR: {:#?}", ir);
+                    for change in &changes {
+                        println!("{change}");
+                    }
+
+                    let breaking = changes.iter().filter(|c| c.is_breaking()).count();
+                    if breaking > 0 {
+                        bail!(
+                            "{} removes {breaking} item(s) from {}",
+                            new.display(),
+                            old.display()
+                        );
+                    }
+
+                    if changes.is_empty() {
+                        println!("no changes");
+                    }
                 }
             };
         }
@@ -251,39 +517,50 @@ impl Cli {
     }
 }
 
+/// Replaces `path` with `contents` through a temporary file renamed over it, so a crash
+/// mid-write leaves the previous contents rather than a truncated file
+fn write_atomically(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents).context(format!("failed to write {:?}", tmp))?;
+    std::fs::rename(&tmp, path).context(format!("failed to replace {:?}", path))
+}
+
 #[cfg(test)]
 mod tests {
-    use indoc::{formatdoc, indoc};
-    use oas3::spec::{ObjectOrReference, Parameter};
-    use regex::Regex;
+    use super::{write_atomically, OutputPaths, OUTPUT_FILE};
+    use std::{fs, path::PathBuf};
 
     #[test]
-    fn matching_params() {
-        let mut parameters = Vec::new();
-
-        let r = Regex::new("/:(\\w+)").unwrap();
-
-        for captures in r.captures_iter("/messages/:message_id/conversation/:conversation_id") {
-            let (_, matches) = captures.extract::<1>();
-
-            if let Some(cap_match) = matches.first() {
-                println!("match {:#?}", cap_match);
-                let spec = formatdoc! {"
-                        name: {name}
-                        in: path
-                        description: some description
-                        required: true
-                        schema:
-                            type: string
-                    ", name = cap_match};
-
-                let parameter = serde_yaml::from_str::<Parameter>(&spec).unwrap();
-                parameters.push(ObjectOrReference::Object(parameter));
+    fn output_dir_collects_every_artifact() {
+        let dir = PathBuf::from("docs/api");
+        assert_eq!(
+            OutputPaths::new(Some(&dir), None, None),
+            OutputPaths {
+                spec: dir.join("openapi.yaml"),
+                schemas: Some(dir.join("schemas")),
+                report: Some(dir.join("report.json")),
             }
-        }
+        );
+        assert_eq!(
+            OutputPaths::new(None, None, Some(PathBuf::from("run.json"))),
+            OutputPaths {
+                spec: PathBuf::from(OUTPUT_FILE),
+                schemas: None,
+                report: Some(PathBuf::from("run.json")),
+            }
+        );
+    }
+
+    #[test]
+    fn replaces_the_file_without_leaving_the_temporary_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("output.yaml");
 
-        println!("{:#?}", parameters);
+        write_atomically(&file, "paths: {}\n").unwrap();
+        write_atomically(&file, "paths:\n  /users: {}\n").unwrap();
 
-        assert!(true)
+        assert_eq!(fs::read_to_string(&file).unwrap(), "paths:\n  /users: {}\n");
+        assert!(!dir.path().join("output.yaml.tmp").exists());
     }
 }
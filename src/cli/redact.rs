@@ -0,0 +1,213 @@
+use anyhow::Context;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Fields of a spec or IR whose values come from the analysed code and could carry its
+/// secrets, `message` being the IR's warnings
+const SCRUBBED_FIELDS: [&str; 5] = ["example", "examples", "default", "description", "message"];
+
+const REPLACEMENT: &str = "***";
+
+/// Replaces what matches a `--redact` pattern in the examples, defaults and descriptions of
+/// a spec or IR with `***`, for specs published outside the team
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(patterns: &[String]) -> anyhow::Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| Regex::new(p).context(format!("invalid redact pattern {p}")))
+            .collect::<anyhow::Result<Vec<Regex>>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Scrubs a spec or IR in place, returning the number of values changed
+    pub fn redact<T: Serialize + DeserializeOwned>(&self, target: &mut T) -> anyhow::Result<usize> {
+        if self.patterns.is_empty() {
+            return Ok(0);
+        }
+
+        let mut value =
+            serde_json::to_value(&*target).context("failed to serialize for redaction")?;
+        let redacted = self.scrub(&mut value, false, false);
+        *target = serde_json::from_value(value).context("failed to read back redacted value")?;
+        Ok(redacted)
+    }
+
+    /// `sensitive` is set below a scrubbed field, `names` on maps keyed by name, e.g.
+    /// `properties`, where a property called `description` is no description
+    fn scrub(&self, value: &mut Value, sensitive: bool, names: bool) -> usize {
+        match value {
+            Value::String(s) if sensitive => {
+                let mut scrubbed = s.clone();
+                for pattern in &self.patterns {
+                    scrubbed = pattern.replace_all(&scrubbed, REPLACEMENT).into_owned();
+                }
+                let changed = scrubbed != *s;
+                *s = scrubbed;
+                changed as usize
+            }
+            Value::Number(_) | Value::Bool(_) if sensitive => {
+                let text = value.to_string();
+                match self.patterns.iter().any(|p| p.is_match(&text)) {
+                    true => {
+                        *value = Value::String(REPLACEMENT.to_owned());
+                        1
+                    }
+                    false => 0,
+                }
+            }
+            Value::Array(items) => items
+                .iter_mut()
+                .map(|item| self.scrub(item, sensitive, false))
+                .sum(),
+            Value::Object(fields) => fields
+                .iter_mut()
+                .map(|(key, field)| {
+                    let scrubbed = !names && SCRUBBED_FIELDS.contains(&key.as_str());
+                    self.scrub(field, sensitive || scrubbed, key == "properties")
+                })
+                .sum(),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Redactor;
+    use crate::{
+        cli::{
+            json_schema::{json_schemas, to_json},
+            report::RunReport,
+        },
+        domain::ir::{
+            Body, BodyProperty, BodyShape, BodyType, HTTPMethod, OperationDocs, ParamDataType,
+            ParamType, Parameter, PropType, Route, Warning, IR,
+        },
+        llm::LLMUsage,
+        openapi::{build_spec, SpecOptions},
+    };
+    use indoc::indoc;
+    use oas3::OpenApiV3Spec;
+    use std::{collections::HashMap, time::Duration};
+
+    #[test]
+    fn scrubs_examples_defaults_and_descriptions() {
+        let mut spec: OpenApiV3Spec = serde_yaml::from_str(indoc! {"
+            openapi: 3.1.0
+            info:
+              title: Internal API
+              version: 1.0.0
+            paths:
+              /webhooks:
+                post:
+                  description: Forwards events to https://hooks.internal.example.com/events
+                  parameters:
+                  - name: token
+                    in: query
+                    schema:
+                      type: string
+                      default: sk_live_4f9a8b7c
+                    example: sk_live_4f9a8b7c
+                  responses:
+                    '200':
+                      description: Successful operation
+            components:
+              schemas:
+                Hook:
+                  type: object
+                  properties:
+                    description:
+                      type: string
+                      description: what the hook is for
+        "})
+        .unwrap();
+
+        let redactor = Redactor::new(&[
+            "sk_live_\\w+".to_owned(),
+            "[\\w.]+\\.internal\\.example\\.com".to_owned(),
+        ])
+        .unwrap();
+        assert_eq!(redactor.redact(&mut spec).unwrap(), 3);
+
+        let yaml = serde_yaml::to_value(&spec).unwrap();
+        let op = &yaml["paths"]["/webhooks"]["post"];
+        assert_eq!(op["description"], "Forwards events to https://***/events");
+        assert_eq!(op["parameters"][0]["schema"]["default"], "***");
+        assert_eq!(op["parameters"][0]["example"], "***");
+        assert_eq!(op["parameters"][0]["name"], "token");
+        assert_eq!(
+            yaml["components"]["schemas"]["Hook"]["properties"]["description"]["type"],
+            "string"
+        );
+
+        assert!(Redactor::new(&["(".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn nothing_written_from_a_redacted_ir_leaks() {
+        const SECRET: &str = "sk_live_4f9a8b7c";
+        let mut ir = IR {
+            routes: vec![Route {
+                path: "/webhooks".to_owned(),
+                method: HTTPMethod::POST,
+                parameters: vec![Parameter {
+                    name: "token".to_owned(),
+                    param_type: ParamType::Query,
+                    data_type: ParamDataType::String,
+                    description: format!("e.g. {SECRET}"),
+                    format: None,
+                    enum_values: vec![],
+                    example: Some(SECRET.into()),
+                    default: Some(SECRET.to_owned()),
+                }],
+                body: Some(Body {
+                    body_type: BodyType::Json,
+                    identifier: Some("Hook".to_owned()),
+                    shape: BodyShape::Single,
+                    required_fields: vec![],
+                    properties: HashMap::from([(
+                        "secret".to_owned(),
+                        BodyProperty {
+                            prop_type: PropType::String,
+                            properties: HashMap::new(),
+                            required_fields: vec![],
+                            example: Some(SECRET.into()),
+                        },
+                    )]),
+                    alternatives: vec![],
+                }),
+                response: None,
+                response_status: None,
+                error_responses: vec![],
+                security: None,
+                docs: OperationDocs::default(),
+                source: None,
+                handler: None,
+            }],
+            warnings: vec![Warning {
+                route: Some("/webhooks".to_owned()),
+                message: format!("couldn't read the default {SECRET}"),
+            }],
+            tags: vec![],
+        };
+
+        let redactor = Redactor::new(&["sk_live_\\w+".to_owned()]).unwrap();
+        assert_eq!(redactor.redact(&mut ir).unwrap(), 5);
+
+        let schemas = to_json(&json_schemas(&ir).unwrap(), false).unwrap();
+        let ir_json = to_json(&ir, false).unwrap();
+        let generated = build_spec(&ir, &SpecOptions::default());
+        let report = RunReport::new(&ir, &generated, LLMUsage::default(), Duration::ZERO, None);
+        let report = to_json(&report, false).unwrap();
+        for written in [&schemas, &ir_json, &report] {
+            assert!(!written.contains(SECRET), "{written}");
+        }
+        assert!(schemas.contains("\"secret\""));
+        assert_eq!(ir.warnings[0].message, "couldn't read the default ***");
+    }
+}
@@ -0,0 +1,138 @@
+use super::json_schema::to_json;
+use crate::{
+    domain::ir::{Warning, IR},
+    llm::LLMUsage,
+    openapi::GeneratedSpec,
+};
+use anyhow::Context;
+use serde::Serialize;
+use std::{path::Path, time::Duration};
+
+/// Summary of a `generate` run, written with `--report` for CI to track over time
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    /// routes written to the spec
+    pub routes: usize,
+    pub llm_calls: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub warnings: Vec<Warning>,
+    pub elapsed_secs: f64,
+    /// why the run stopped early, if it timed out or was interrupted
+    pub interrupted: Option<String>,
+}
+
+impl RunReport {
+    /// The report of the run that generated `ir`, then built `generated` from it. Routes
+    /// dropped as duplicates aren't counted, and are reported as warnings along with the
+    /// ambiguous operations.
+    pub fn new(
+        ir: &IR,
+        generated: &GeneratedSpec,
+        usage: LLMUsage,
+        elapsed: Duration,
+        interrupted: Option<String>,
+    ) -> Self {
+        let routes = generated
+            .spec
+            .paths
+            .iter()
+            .flatten()
+            .map(|(_, path_item)| path_item.methods().into_iter().count())
+            .sum();
+        let spec_warnings = generated
+            .collisions
+            .iter()
+            .chain(&generated.ambiguous_operations)
+            .map(|message| Warning {
+                route: None,
+                message: message.clone(),
+            });
+
+        Self {
+            routes,
+            llm_calls: usage.calls,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens(),
+            warnings: ir.warnings.iter().cloned().chain(spec_warnings).collect(),
+            elapsed_secs: elapsed.as_secs_f64(),
+            interrupted,
+        }
+    }
+
+    pub fn write(&self, path: &Path, pretty: bool) -> anyhow::Result<()> {
+        let json = to_json(self, pretty).context("failed to serialize run report")?;
+        std::fs::write(path, json).context(format!("failed to write run report to {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RunReport;
+    use crate::{
+        domain::ir::{HTTPMethod, OperationDocs, Route, Warning, IR},
+        llm::LLMUsage,
+        openapi::{build_spec, SpecOptions},
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn reports_usage_and_warnings() {
+        let route = |path: &str| Route {
+            path: path.to_owned(),
+            method: HTTPMethod::GET,
+            parameters: vec![],
+            body: None,
+            response: None,
+            response_status: None,
+            error_responses: vec![],
+            security: None,
+            docs: OperationDocs::default(),
+            source: None,
+            handler: None,
+        };
+        let ir = IR {
+            routes: vec![route("/users"), route("/users/"), route("/health")],
+            warnings: vec![Warning {
+                route: Some("/users".to_owned()),
+                message: "could not resolve import `crate::dto::User`".to_owned(),
+            }],
+            tags: vec![],
+        };
+        let usage = LLMUsage {
+            calls: 3,
+            prompt_tokens: 1200,
+            completion_tokens: 300,
+        };
+
+        let generated = build_spec(&ir, &SpecOptions::default());
+
+        let report = RunReport::new(&ir, &generated, usage, Duration::from_millis(2500), None);
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                // the duplicate of `GET /users` isn't in the spec
+                "routes": 2,
+                "llm_calls": 3,
+                "prompt_tokens": 1200,
+                "completion_tokens": 300,
+                "total_tokens": 1500,
+                "warnings": [
+                    {
+                        "route": "/users",
+                        "message": "could not resolve import `crate::dto::User`",
+                    },
+                    {
+                        "route": null,
+                        "message": "GET /users is defined more than once, ignoring the duplicate",
+                    },
+                ],
+                "elapsed_secs": 2.5,
+                "interrupted": null,
+            })
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::call_graph::manifest::Manifest;
+use anyhow::{bail, Context};
+use cargo_toml::Value;
+use oas3::spec::Server;
+use std::{collections::BTreeMap, path::Path};
+
+/// File in the analysed crate's root listing its servers, read before `Cargo.toml`
+pub const PROFILES_FILE: &str = "docgen.toml";
+
+/// The servers the analysed crate is deployed to, one per environment, from the `[servers]`
+/// table of `docgen.toml` or else the `[package.metadata.docgen.servers]` table of its
+/// `Cargo.toml`. Each environment names either its URL or a table with a `url` and an
+/// optional `description`, which defaults to the environment's name:
+///
+/// ```toml
+/// [package.metadata.docgen.servers]
+/// dev = "http://localhost:3000"
+/// prod = { url = "https://api.example.com", description = "Production" }
+/// ```
+///
+/// Servers are listed by environment name.
+pub fn server_profiles(dir: &Path, manifest: Option<&Manifest>) -> anyhow::Result<Vec<Server>> {
+    let profiles_file = dir.join(PROFILES_FILE);
+    let profiles = match profiles_file.exists() {
+        true => {
+            let content = std::fs::read_to_string(&profiles_file)
+                .context(format!("failed to read {:?}", profiles_file))?;
+            let value = content
+                .parse::<Value>()
+                .context(format!("failed to parse {:?}", profiles_file))?;
+            value.get("servers").cloned()
+        }
+        false => manifest
+            .and_then(|m| m.package_metadata())
+            .and_then(|metadata| metadata.get("docgen")?.get("servers"))
+            .cloned(),
+    };
+
+    match profiles {
+        Some(profiles) => parse_servers(&profiles),
+        None => Ok(vec![]),
+    }
+}
+
+fn parse_servers(profiles: &Value) -> anyhow::Result<Vec<Server>> {
+    let Some(profiles) = profiles.as_table() else {
+        bail!("`servers` must be a table of environment names to URLs");
+    };
+
+    profiles
+        .iter()
+        .map(|(name, profile)| {
+            let (url, description) = match profile {
+                Value::String(url) => (url.to_owned(), None),
+                Value::Table(profile) => {
+                    let Some(url) = profile.get("url").and_then(Value::as_str) else {
+                        bail!("server `{name}` has no `url`");
+                    };
+                    let description = profile.get("description").and_then(Value::as_str);
+                    (url.to_owned(), description.map(str::to_owned))
+                }
+                _ => bail!("server `{name}` must be a URL or a table with a `url`"),
+            };
+
+            Ok(Server {
+                url,
+                description: Some(description.unwrap_or_else(|| name.to_owned())),
+                variables: BTreeMap::new(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{server_profiles, PROFILES_FILE};
+    use crate::{call_graph::manifest::Manifest, utils::fixtures::CrateFixture};
+    use indoc::indoc;
+
+    #[test]
+    fn reads_servers_from_docgen_toml_or_package_metadata() {
+        let krate = CrateFixture::new(indoc! {r#"
+            [package]
+            name = "api"
+            version = "0.1.0"
+
+            [package.metadata.docgen.servers]
+            staging = { url = "https://staging.example.com", description = "Staging" }
+            dev = "http://localhost:3000"
+        "#});
+        let dir = krate.root();
+        let manifest = Manifest::try_new(&dir).unwrap();
+
+        let servers = server_profiles(&dir, Some(&manifest)).unwrap();
+        let servers = servers
+            .iter()
+            .map(|s| (s.url.as_str(), s.description.as_deref().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            servers,
+            vec![
+                ("http://localhost:3000", "dev"),
+                ("https://staging.example.com", "Staging"),
+            ]
+        );
+
+        krate.write(
+            PROFILES_FILE,
+            "[servers]\nprod = { url = \"https://api.example.com\" }\n",
+        );
+        let servers = server_profiles(&dir, Some(&manifest)).unwrap();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].url, "https://api.example.com");
+
+        krate.write(PROFILES_FILE, "[servers]\nprod = 1\n");
+        assert!(server_profiles(&dir, Some(&manifest)).is_err());
+    }
+}
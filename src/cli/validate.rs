@@ -0,0 +1,131 @@
+use anyhow::{anyhow, Context};
+use oas3::{
+    spec::{ObjectOrReference, Operation, ParameterIn, PathItem},
+    OpenApiV3Spec,
+};
+use regex::Regex;
+use std::{fs::read_to_string, path::Path};
+
+/// Reads and parses a generated spec, returning the sanity check issues found in it.
+/// Deserialization failures are reported as errors with the offending line attached.
+pub fn validate_spec_file(file: &Path) -> anyhow::Result<Vec<String>> {
+    Ok(validate_spec(&read_spec(file)?))
+}
+
+/// Reads and parses a spec, pointing at the offending line when it can't be deserialized
+pub fn read_spec(file: &Path) -> anyhow::Result<OpenApiV3Spec> {
+    let content = read_to_string(file).context(format!("failed to read spec at {:?}", file))?;
+
+    serde_yaml::from_str::<OpenApiV3Spec>(&content).map_err(|e| {
+        let context = e
+            .location()
+            .and_then(|loc| {
+                let line = content.lines().nth(loc.line().saturating_sub(1))?;
+                Some(format!(
+                    "\n  --> {}:{}:{}\n   | {}",
+                    file.display(),
+                    loc.line(),
+                    loc.column(),
+                    line
+                ))
+            })
+            .unwrap_or_default();
+
+        anyhow!("failed to parse spec: {e}{context}")
+    })
+}
+
+/// Checks that every templated path parameter is declared with `in: path`
+/// and that every operation documents at least one response.
+pub fn validate_spec(spec: &OpenApiV3Spec) -> Vec<String> {
+    let template_param = Regex::new("\\{(\\w+)\\}").unwrap();
+    let mut issues = Vec::new();
+
+    for (path, item) in spec.paths.iter().flatten() {
+        for (method, op) in item.methods() {
+            for captures in template_param.captures_iter(path) {
+                let (_, [name]) = captures.extract::<1>();
+                if !declares_path_param(spec, item, op, name) {
+                    issues.push(format!(
+                        "{method} {path}: path parameter `{name}` has no matching `in: path` parameter"
+                    ));
+                }
+            }
+
+            if op.responses.as_ref().is_none_or(|r| r.is_empty()) {
+                issues.push(format!("{method} {path}: operation has no responses"));
+            }
+        }
+    }
+
+    issues
+}
+
+fn declares_path_param(spec: &OpenApiV3Spec, item: &PathItem, op: &Operation, name: &str) -> bool {
+    item.parameters
+        .iter()
+        .chain(op.parameters.iter())
+        .filter_map(|p| match p {
+            ObjectOrReference::Object(p) => Some(p.clone()),
+            ObjectOrReference::Ref { .. } => p.resolve(spec).ok(),
+        })
+        .any(|p| p.name == name && p.location == ParameterIn::Path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_spec;
+    use indoc::indoc;
+    use oas3::OpenApiV3Spec;
+
+    fn parse(yaml: &str) -> OpenApiV3Spec {
+        serde_yaml::from_str(yaml).expect("invalid test spec")
+    }
+
+    #[test]
+    fn accepts_well_formed_spec() {
+        let spec = parse(indoc! {"
+            openapi: 3.0.3
+            info:
+              title: Generated API
+              version: 1.0.0
+            paths:
+              /channels/{channel_id}:
+                get:
+                  parameters:
+                  - name: channel_id
+                    in: path
+                    required: true
+                    schema:
+                      type: string
+                  responses:
+                    '200':
+                      description: Successful operation
+        "});
+
+        assert!(validate_spec(&spec).is_empty());
+    }
+
+    #[test]
+    fn reports_mislabeled_path_params_and_missing_responses() {
+        let spec = parse(indoc! {"
+            openapi: 3.0.3
+            info:
+              title: Generated API
+              version: 1.0.0
+            paths:
+              /channels/{channel_id}:
+                delete:
+                  parameters:
+                  - name: channel_id
+                    in: query
+                    schema:
+                      type: string
+        "});
+
+        let issues = validate_spec(&spec);
+        assert_eq!(issues.len(), 2, "{issues:#?}");
+        assert!(issues[0].contains("`channel_id`"));
+        assert!(issues[1].contains("no responses"));
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{call_graph::manifest::Manifest, error::DocgenError};
+use serde::Deserialize;
+use std::{
+    fmt::Display,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+/// What an import of the analysed crate resolves to
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum ImportKind {
+    /// the file defining the symbol, in the crate itself, a sibling workspace member or a
+    /// path dependency
+    Local(PathBuf),
+    /// a dependency from a registry, with the import as written
+    External(String),
+    /// the standard library, `std`, `core` or `alloc`
+    Std,
+    /// an import that couldn't be resolved, for callers carrying on without it
+    Unknown,
+}
+
+impl Display for ImportKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Local(path) => path.to_str().unwrap_or("invalid path"),
+            Self::External(s) => s.as_str(),
+            Self::Std => "std",
+            Self::Unknown => "unknown",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+/// Files a module directory's own items can live in, in lookup order
+const ROOT_MODULE_FILES: [&str; 3] = ["main.rs", "lib.rs", "mod.rs"];
+
+/// The file of the module `segments` names, e.g. `src/a/b.rs` or `src/a/b/mod.rs` for
+/// `crate::a::b`
+pub fn resolve_module_path(
+    segments: &[&str],
+    base_dir: &Path,
+    manifest: &Manifest,
+) -> Option<PathBuf> {
+    let first = segments.first()?;
+
+    let crate_name = manifest.crate_name();
+
+    let (mut module_dir, skip_segment) = match *first {
+        first if Some(first) == crate_name.as_deref() || first == "crate" => {
+            // src directory
+            let dir = base_dir
+                .ancestors()
+                .find(|d| d.join("src").exists())
+                .map(|d| d.join("src"))?;
+            (dir, 1)
+        }
+        "self" => (base_dir.to_path_buf(), 1),
+        "super" => {
+            // one directory up per leading `super`
+            let supers = segments.iter().take_while(|s| **s == "super").count();
+            let dir = base_dir.ancestors().nth(supers)?.to_path_buf();
+            (dir, supers)
+        }
+        // a sibling workspace member or path dependency
+        first => (manifest.local_crate_dir(first)?.join("src"), 1),
+    };
+
+    // `crate` alone (from `crate::handler`) names the root module of the directory itself
+    let Some((module, parents)) = segments[skip_segment..].split_last() else {
+        return ROOT_MODULE_FILES
+            .iter()
+            .map(|file| module_dir.join(file))
+            .find(|file| file.exists());
+    };
+
+    for seg in parents {
+        module_dir = module_dir.join(seg);
+    }
+
+    let file_rs = module_dir.join(format!("{}.rs", module));
+    let mod_rs = module_dir.join(module).join("mod.rs");
+
+    if file_rs.exists() {
+        Some(file_rs)
+    } else if mod_rs.exists() {
+        Some(mod_rs)
+    } else {
+        None
+    }
+}
+
+/// How many `pub use` hops are followed before giving up, guards against re-export cycles
+const MAX_REEXPORT_DEPTH: usize = 8;
+
+/// Directory that `self::` resolves against for items declared in `file`
+pub fn module_dir_of(file: &Path) -> Option<PathBuf> {
+    let parent = file.parent()?;
+    match file.file_name()?.to_str()? {
+        "mod.rs" | "lib.rs" | "main.rs" => Some(parent.to_path_buf()),
+        _ => Some(parent.join(file.file_stem()?)),
+    }
+}
+
+pub fn defines_symbol(file: &syn::File, symbol: &str) -> bool {
+    file.items.iter().any(|item| match item {
+        syn::Item::Fn(f) => f.sig.ident == symbol,
+        syn::Item::Struct(s) => s.ident == symbol,
+        syn::Item::Enum(e) => e.ident == symbol,
+        syn::Item::Type(t) => t.ident == symbol,
+        syn::Item::Impl(i) => i.items.iter().any(|item| match item {
+            syn::ImplItem::Fn(f) => f.sig.ident == symbol,
+            _ => false,
+        }),
+        _ => false,
+    })
+}
+
+/// Flattens a `use` tree into the module path and the re-exported name (`None` for globs)
+fn collect_use_paths(
+    tree: &syn::UseTree,
+    prefix: &mut Vec<String>,
+    paths: &mut Vec<(Vec<String>, Option<String>)>,
+) {
+    match tree {
+        syn::UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            collect_use_paths(&path.tree, prefix, paths);
+            prefix.pop();
+        }
+        syn::UseTree::Group(group) => {
+            for tree in &group.items {
+                collect_use_paths(tree, prefix, paths);
+            }
+        }
+        syn::UseTree::Name(name) => paths.push((prefix.clone(), Some(name.ident.to_string()))),
+        syn::UseTree::Rename(rename) => {
+            paths.push((prefix.clone(), Some(rename.ident.to_string())))
+        }
+        syn::UseTree::Glob(_) => paths.push((prefix.clone(), None)),
+    }
+}
+
+/// Returns the file that actually defines `symbol`, starting at `file` and following its
+/// `pub use` re-exports when the symbol isn't declared there directly
+pub fn follow_reexports(
+    file: &Path,
+    symbol: &str,
+    manifest: &Manifest,
+    depth: usize,
+) -> Option<PathBuf> {
+    let parsed = syn::parse_file(&read_to_string(file).ok()?).ok()?;
+    if defines_symbol(&parsed, symbol) {
+        return Some(file.to_path_buf());
+    }
+
+    if depth >= MAX_REEXPORT_DEPTH {
+        return None;
+    }
+
+    let module_dir = module_dir_of(file)?;
+    for item in &parsed.items {
+        let syn::Item::Use(item_use) = item else {
+            continue;
+        };
+        if matches!(item_use.vis, syn::Visibility::Inherited) {
+            continue;
+        }
+
+        let mut paths = Vec::new();
+        collect_use_paths(&item_use.tree, &mut Vec::new(), &mut paths);
+
+        for (mut module, name) in paths {
+            if name.as_deref().is_some_and(|n| n != symbol) || module.is_empty() {
+                continue;
+            }
+
+            // 2018 style relative paths start at the current module
+            let first = module[0].as_str();
+            if first != "crate"
+                && first != "self"
+                && first != "super"
+                && Some(first) != manifest.crate_name().as_deref()
+                && manifest.local_crate_dir(first).is_none()
+            {
+                module.insert(0, "self".to_owned());
+            }
+
+            let segments = module.iter().map(String::as_str).collect::<Vec<&str>>();
+            let Some(target) = resolve_module_path(&segments, &module_dir, manifest) else {
+                continue;
+            };
+
+            if let Some(found) = follow_reexports(&target, symbol, manifest, depth + 1) {
+                return Some(found);
+            }
+        }
+    }
+
+    None
+}
+
+/// Resolves an import as written in a file of the analysed crate, e.g.
+/// `crate::handlers::users::create` or `super::dto::CreateUser`, relative to `base_dir`,
+/// the directory `self::` stands for in that file. Local imports point at the file
+/// defining the symbol, following `pub use` re-exports.
+pub fn resolve(
+    import: &str,
+    manifest: &Manifest,
+    base_dir: &Path,
+) -> Result<ImportKind, DocgenError> {
+    let path_segments = import.split("::").collect::<Vec<&str>>();
+    let Some(first) = path_segments.first() else {
+        return Ok(ImportKind::External(import.to_owned()));
+    };
+
+    let local = match *first {
+        "std" | "core" | "alloc" => return Ok(ImportKind::Std),
+        "crate" | "self" | "super" => true,
+        // the crate itself by name, a sibling workspace member or a path dependency
+        first => {
+            Some(first) == manifest.crate_name().as_deref()
+                || manifest.local_crate_dir(first).is_some()
+        }
+    };
+    if !local {
+        return Ok(ImportKind::External(import.to_owned()));
+    }
+
+    let path = resolve_module_path(
+        &path_segments[..path_segments.len() - 1],
+        base_dir,
+        manifest,
+    )
+    .ok_or(DocgenError::UnresolvedImport(import.to_owned()))?;
+    Ok(ImportKind::Local(resolve_reexported_symbol(
+        path,
+        &path_segments,
+        manifest,
+    )))
+}
+
+/// Points `path` at the file that really defines the imported symbol when its module
+/// only re-exports it. Falls back to `path` if the definition can't be found.
+fn resolve_reexported_symbol(
+    path: PathBuf,
+    path_segments: &[&str],
+    manifest: &Manifest,
+) -> PathBuf {
+    let Some(symbol) = path_segments.last() else {
+        return path;
+    };
+
+    follow_reexports(&path, symbol, manifest, 0).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{module_dir_of, resolve, resolve_module_path, ImportKind};
+    use crate::{
+        call_graph::manifest::Manifest,
+        utils::fixtures::{package_manifest, CrateFixture},
+    };
+    use std::path::Path;
+
+    #[test]
+    fn resolves_every_import_form() {
+        let krate = CrateFixture::new(indoc::indoc! {r#"
+            [package]
+            name = "api-server"
+            version = "0.1.0"
+
+            [dependencies]
+            serde = "1"
+            shared = { path = "shared" }
+        "#});
+        krate.write("shared/Cargo.toml", &package_manifest("shared"));
+        let page = krate.write("shared/src/lib.rs", "pub struct Page {}\n");
+        krate.write("src/main.rs", "mod dto;\nmod handlers;\nfn main() {}\n");
+        krate.write("src/dto.rs", "pub struct CreateUser {}\n");
+        krate.write("src/handlers/mod.rs", "pub mod users;\n");
+        krate.write("src/handlers/users.rs", "pub async fn create() {}\n");
+        krate.write("src/handlers/auth.rs", "pub async fn login() {}\n");
+        krate.write("src/handlers/users/validation.rs", "pub fn check() {}\n");
+
+        let src = krate.root().join("src");
+        let manifest = Manifest::try_new(&krate.root()).unwrap();
+        let handlers = src.join("handlers");
+        let resolve = |import: &str, base_dir: &std::path::Path| {
+            resolve(import, &manifest, base_dir).unwrap()
+        };
+        let local = |file: &str| ImportKind::Local(src.join(file));
+
+        for std in [
+            "std::collections::HashMap",
+            "core::fmt::Display",
+            "alloc::vec::Vec",
+        ] {
+            assert_eq!(resolve(std, &src), ImportKind::Std);
+        }
+        for external in ["serde::Deserialize", "axum::extract::Path"] {
+            assert_eq!(
+                resolve(external, &src),
+                ImportKind::External(external.to_owned())
+            );
+        }
+
+        assert_eq!(resolve("crate::dto::CreateUser", &src), local("dto.rs"));
+        // the crate by name, written `api_server` in code
+        assert_eq!(
+            resolve("api_server::handlers::users::create", &src),
+            local("handlers/users.rs")
+        );
+        assert_eq!(
+            resolve("self::users::create", &handlers),
+            local("handlers/users.rs")
+        );
+        assert_eq!(
+            resolve("super::dto::CreateUser", &handlers),
+            local("dto.rs")
+        );
+        // from a file that isn't a mod.rs, self:: is the file's own module
+        let users = module_dir_of(&handlers.join("users.rs")).unwrap();
+        assert_eq!(
+            resolve("self::validation::check", &users),
+            local("handlers/users/validation.rs")
+        );
+        assert_eq!(
+            resolve("super::auth::login", &users),
+            local("handlers/auth.rs")
+        );
+        assert_eq!(resolve("shared::Page", &src), ImportKind::Local(page));
+
+        assert!(super::resolve("crate::missing::Thing", &manifest, &src).is_err());
+    }
+
+    #[test]
+    fn resolves_module_paths() {
+        let krate = CrateFixture::package("paths");
+        for file in [
+            "main.rs",
+            "x.rs",
+            "a/mod.rs",
+            "a/y.rs",
+            "a/b/mod.rs",
+            "a/b/z.rs",
+        ] {
+            krate.write(Path::new("src").join(file), "");
+        }
+
+        let src = krate.root().join("src");
+        let manifest = Manifest::try_new(&krate.root()).unwrap();
+        let resolve = |path: &str, base_dir: &Path| {
+            let segments = path.split("::").collect::<Vec<&str>>();
+            resolve_module_path(&segments, base_dir, &manifest)
+        };
+
+        // a single segment is the root module of the directory it names
+        assert_eq!(resolve("crate", &src), Some(src.join("main.rs")));
+        assert_eq!(resolve("paths", &src), Some(src.join("main.rs")));
+        assert_eq!(resolve("self", &src.join("a")), Some(src.join("a/mod.rs")));
+
+        assert_eq!(resolve("crate::x", &src), Some(src.join("x.rs")));
+        assert_eq!(resolve("crate::a", &src), Some(src.join("a/mod.rs")));
+        assert_eq!(resolve("crate::a::y", &src), Some(src.join("a/y.rs")));
+        assert_eq!(resolve("crate::a::b::z", &src), Some(src.join("a/b/z.rs")));
+        assert_eq!(resolve("self::y", &src.join("a")), Some(src.join("a/y.rs")));
+        assert_eq!(
+            resolve("super::y", &src.join("a/b")),
+            Some(src.join("a/y.rs"))
+        );
+
+        // `src/a/b/c.rs` importing `super::super::x` reaches into `src/a`
+        let c_dir = module_dir_of(&src.join("a/b/c.rs")).unwrap();
+        assert_eq!(resolve("super::super::y", &c_dir), Some(src.join("a/y.rs")));
+        assert_eq!(
+            resolve("super::super::b", &c_dir),
+            Some(src.join("a/b/mod.rs"))
+        );
+        assert_eq!(resolve("super::super", &c_dir), Some(src.join("a/mod.rs")));
+        assert_eq!(
+            resolve("super::super::super::x", &c_dir),
+            Some(src.join("x.rs"))
+        );
+
+        assert_eq!(resolve("crate::missing", &src), None);
+        assert_eq!(resolve("", &src), None);
+
+        // `crate::handler` points at the crate root rather than panicking
+        assert!(matches!(
+            super::resolve("crate::handler", &manifest, &src).unwrap(),
+            ImportKind::Local(path) if path == src.join("main.rs")
+        ));
+    }
+
+    #[test]
+    fn follows_pub_use_reexports_to_the_defining_file() {
+        let krate = CrateFixture::package("facade");
+        krate.write("src/main.rs", "mod handlers;\nfn main() {}\n");
+        krate.write(
+            "src/handlers/mod.rs",
+            "mod users;\npub use users::*;\npub use crate::handlers::health::ping;\nmod health;\n",
+        );
+        krate.write(
+            "src/handlers/users/mod.rs",
+            "mod create;\npub use self::create::create_user;\n",
+        );
+        krate.write(
+            "src/handlers/users/create.rs",
+            "pub async fn create_user() {}\n",
+        );
+        krate.write("src/handlers/health.rs", "pub async fn ping() {}\n");
+
+        let manifest = Manifest::try_new(&krate.root()).unwrap();
+        let src = krate.root().join("src");
+        let handlers = src.join("handlers");
+        let resolve = |import: &str| match super::resolve(import, &manifest, &src).unwrap() {
+            ImportKind::Local(path) => path,
+            other => panic!("{import} resolved to {other}"),
+        };
+
+        assert_eq!(
+            resolve("crate::handlers::create_user"),
+            handlers.join("users/create.rs")
+        );
+        assert_eq!(
+            resolve("facade::handlers::ping"),
+            handlers.join("health.rs")
+        );
+        // symbols that can't be found anywhere keep pointing at the imported module
+        assert_eq!(resolve("crate::handlers::missing"), handlers.join("mod.rs"));
+    }
+}
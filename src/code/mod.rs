@@ -1,11 +1,66 @@
+pub mod imports;
+
 pub mod downloader {
-    use std::path::PathBuf;
+    use anyhow::Context;
+    use std::path::{Path, PathBuf};
     use url::Url;
 
+    /// Where `--url` downloads land when no `--work-dir` is given
+    pub fn default_work_dir() -> PathBuf {
+        std::env::temp_dir().join("docgen/code")
+    }
+
+    /// Directory a repository is downloaded to, namespaced by host and repository path
+    /// (e.g. `<work_dir>/github.com/owner/repo`) so concurrent runs on different repositories
+    /// don't collide
+    pub fn download_dir_for(work_dir: &Path, url: &Url) -> PathBuf {
+        let mut dir = work_dir.join(url.host_str().unwrap_or("unknown-host"));
+        for segment in url.path_segments().into_iter().flatten() {
+            let segment = segment.trim_end_matches(".git");
+            if !segment.is_empty() && segment != "." && segment != ".." {
+                dir = dir.join(segment);
+            }
+        }
+        dir
+    }
+
+    /// Downloads the repository at `url` into its directory under `work_dir`, replacing
+    /// whatever a previous run left there, and returns that directory
+    pub fn download_to_work_dir(url: &Url, work_dir: &Path) -> anyhow::Result<PathBuf> {
+        let download_dir = download_dir_for(work_dir, url);
+        if download_dir.exists() {
+            std::fs::remove_dir_all(&download_dir)
+                .context(format!("failed to clean up {:?}", download_dir))?;
+        }
+
+        download_from_url(url, &download_dir)?;
+        Ok(download_dir)
+    }
+
     pub fn download_from_url(url: &Url, download_dir: &PathBuf) -> anyhow::Result<()> {
         unimplemented!(
             "yet to implement download_from_url {url} {:?}",
             download_dir
         )
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::download_dir_for;
+        use std::path::Path;
+        use url::Url;
+
+        #[test]
+        fn namespaces_downloads_by_repository() {
+            let work_dir = Path::new("/work");
+            let url = Url::parse("https://github.com/AdeThorMiwa/docgen.git").unwrap();
+            assert_eq!(
+                download_dir_for(work_dir, &url),
+                work_dir.join("github.com/AdeThorMiwa/docgen")
+            );
+
+            let url = Url::parse("https://example.com/../../etc").unwrap();
+            assert!(download_dir_for(work_dir, &url).starts_with(work_dir));
+        }
+    }
 }
@@ -1,14 +1,60 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    path::PathBuf,
+};
 
 use anyhow::bail;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum HTTPMethod {
     GET,
     POST,
     PUT,
     PATCH,
     DELETE,
+    HEAD,
+    OPTIONS,
+    TRACE,
+}
+
+impl HTTPMethod {
+    /// Every method, in the order OpenAPI lists them on a path item
+    pub const ALL: [HTTPMethod; 8] = [
+        Self::GET,
+        Self::PUT,
+        Self::POST,
+        Self::DELETE,
+        Self::OPTIONS,
+        Self::HEAD,
+        Self::PATCH,
+        Self::TRACE,
+    ];
+
+    pub fn all() -> impl Iterator<Item = HTTPMethod> {
+        Self::ALL.into_iter()
+    }
+
+    /// The uppercase method name, e.g. `GET`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GET => "GET",
+            Self::POST => "POST",
+            Self::PUT => "PUT",
+            Self::PATCH => "PATCH",
+            Self::DELETE => "DELETE",
+            Self::HEAD => "HEAD",
+            Self::OPTIONS => "OPTIONS",
+            Self::TRACE => "TRACE",
+        }
+    }
+}
+
+impl Display for HTTPMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl TryFrom<&str> for HTTPMethod {
@@ -20,40 +66,66 @@ impl TryFrom<&str> for HTTPMethod {
             "put" => Self::PUT,
             "patch" => Self::PATCH,
             "delete" => Self::DELETE,
+            "head" => Self::HEAD,
+            "options" => Self::OPTIONS,
+            "trace" => Self::TRACE,
             method => bail!(format!("invalid http method: {}", method)),
         })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ParamType {
     Query,
     Path,
+    Header,
     Unknown,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ParamDataType {
     String,
     Integer,
+    /// integers that can't be negative, documented with `minimum: 0`
+    UnsignedInteger,
     Float,
     Unknown,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Parameter {
     pub name: String,
     pub param_type: ParamType,
     pub data_type: ParamDataType,
     pub description: String,
+    /// OpenAPI `format` hint, e.g. `uuid` or `date-time`
+    #[serde(default)]
+    pub format: Option<String>,
+    /// the only values accepted, for parameters parsed into a unit enum
+    #[serde(default)]
+    pub enum_values: Vec<String>,
+    /// a representative value, when examples were requested and the LLM gave one
+    #[serde(default)]
+    pub example: Option<serde_json::Value>,
+    /// value used when the parameter is left out, where it could be read from the code
+    #[serde(default)]
+    pub default: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BodyType {
     Json,
+    /// `multipart/form-data`, e.g. from the `Multipart` extractor
+    Multipart,
+    /// `application/x-www-form-urlencoded`, e.g. from the `Form` extractor
+    Form,
+    /// raw `application/octet-stream` bytes, e.g. from `Bytes` or `Body`
+    Binary,
+    /// `application/xml`, e.g. a response negotiated on the `Accept` header
+    Xml,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PropType {
     String,
     Number,
@@ -61,27 +133,160 @@ pub enum PropType {
     Object,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BodyProperty {
     pub prop_type: PropType,
+    /// fields of a nested struct, for `PropType::Object`
+    #[serde(default)]
+    pub properties: HashMap<String, BodyProperty>,
+    #[serde(default)]
+    pub required_fields: Vec<String>,
+    /// a representative value, when examples were requested and the LLM gave one
+    #[serde(default)]
+    pub example: Option<serde_json::Value>,
+}
+
+/// How the DTO is carried in a body, e.g. `Json<Vec<CreateItemDto>>` is an array of them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyShape {
+    #[default]
+    Single,
+    Array,
+    /// `Json<Option<T>>`, which also accepts `null`
+    Nullable,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Body {
     pub body_type: BodyType,
+    /// name of the DTO the body is read into, e.g. `CreateUserDto`
+    #[serde(default)]
+    pub identifier: Option<String>,
+    #[serde(default)]
+    pub shape: BodyShape,
     pub required_fields: Vec<String>,
     pub properties: HashMap<String, BodyProperty>,
+    /// the same body accepted in other content types, each with its own schema, e.g. an
+    /// extractor reading either JSON or a form
+    #[serde(default)]
+    pub alternatives: Vec<Body>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Route {
     pub path: String,
     pub method: HTTPMethod,
     pub parameters: Vec<Parameter>,
     pub body: Option<Body>,
+    /// body of the successful response, when the handler's return type tells
+    #[serde(default)]
+    pub response: Option<Body>,
+    /// status of the successful response when the handler sets one, e.g. 201 for
+    /// `(StatusCode::CREATED, Json(user))`
+    #[serde(default)]
+    pub response_status: Option<u16>,
+    /// responses of the handler's error type, e.g. `ApiError` of `Result<Json<T>, ApiError>`
+    #[serde(default)]
+    pub error_responses: Vec<ErrorResponse>,
+    /// name of the security scheme protecting this route, if any
+    pub security: Option<String>,
+    #[serde(default)]
+    pub docs: OperationDocs,
+    /// where the handler is defined
+    #[serde(default)]
+    pub source: Option<SourceLocation>,
+    /// the named function handling the route, `None` for closures
+    #[serde(default)]
+    pub handler: Option<Handler>,
+}
+
+/// A status a handler's error type responds with and the variants responding with it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub status: u16,
+    /// e.g. `ApiError::NotFound`, empty when the status is set by a catch-all arm
+    pub variants: Vec<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Handler {
+    pub name: String,
+    /// module the handler is defined in, e.g. `handlers::channels`
+    pub module: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    /// 1-based line of the handler's `fn`
+    pub line: usize,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)
+    }
+}
+
+/// Operation metadata written in comments on the handler, e.g. `/// @openapi.summary Create a channel`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationDocs {
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    /// vendor extensions keyed by their full `x-` name
+    pub extensions: BTreeMap<String, String>,
+    #[serde(default)]
+    pub operation_id: Option<String>,
+}
+
+/// Something the generator couldn't fully resolve, e.g. an unresolved import or a missing body schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Warning {
+    /// path of the affected route, if the warning can be tied to one
+    pub route: Option<String>,
+    pub message: String,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.route {
+            Some(route) => write!(f, "{}: {}", route, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A tag used by the routes, described by the doc comment of the module it's named after
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct IR {
     pub routes: Vec<Route>,
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HTTPMethod;
+
+    #[test]
+    fn http_methods_round_trip() {
+        for method in HTTPMethod::all() {
+            assert_eq!(HTTPMethod::try_from(method.as_str()).unwrap(), method);
+            assert_eq!(
+                HTTPMethod::try_from(method.to_string().to_lowercase().as_str()).unwrap(),
+                method
+            );
+        }
+        assert_eq!(HTTPMethod::all().count(), 8);
+        assert!(HTTPMethod::try_from("CONNECT").is_err());
+    }
 }
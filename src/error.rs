@@ -0,0 +1,56 @@
+use thiserror::Error;
+
+/// Errors a generation run can fail with, for library consumers that need to tell
+/// them apart. Anything else is carried as `Other`.
+#[derive(Debug, Error)]
+pub enum DocgenError {
+    #[error("llm request failed: {0}")]
+    LlmRequestFailed(String),
+    #[error("llm returned unserializable string {message}\n\n{raw}")]
+    InvalidLlmJson { message: String, raw: String },
+    #[error("unable to resolve import module path for {0}")]
+    UnresolvedImport(String),
+    #[error("couldn't locate the router from the entry function")]
+    RouterNotFound,
+    /// the environment variable the LLM client reads its API key from isn't set
+    #[error("{0} is not set, set it to your API key to call the LLM")]
+    MissingApiKey(&'static str),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// Most of the generator is written against `anyhow`, so typed errors raised deep inside
+/// it travel as `anyhow::Error` and are recovered here at the public boundary
+impl From<anyhow::Error> for DocgenError {
+    fn from(e: anyhow::Error) -> Self {
+        e.downcast::<DocgenError>().unwrap_or_else(Self::Other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DocgenError;
+    use anyhow::Context;
+
+    #[test]
+    fn recovers_typed_errors_from_anyhow() {
+        let err = anyhow::Error::new(DocgenError::RouterNotFound);
+        assert!(matches!(
+            DocgenError::from(err),
+            DocgenError::RouterNotFound
+        ));
+
+        let err = Err::<(), _>(DocgenError::UnresolvedImport("crate::x".to_owned()))
+            .context("while reading routes")
+            .unwrap_err();
+        assert!(matches!(
+            DocgenError::from(err),
+            DocgenError::UnresolvedImport(import) if import == "crate::x"
+        ));
+
+        let err = anyhow::anyhow!("something else");
+        assert!(matches!(DocgenError::from(err), DocgenError::Other(_)));
+    }
+}
@@ -1,15 +1,42 @@
 use std::path::PathBuf;
 
 use async_trait::async_trait;
+use tokio::sync::watch;
 
-use crate::domain::ir::{Route, IR};
+use crate::{
+    domain::ir::{Route, IR},
+    error::DocgenError,
+    llm::LLMUsage,
+};
 
 pub mod rust_axum;
 
+/// Conventions of the framework a generator targets
+#[derive(Debug, Clone)]
+pub struct FrameworkInfo {
+    pub name: &'static str,
+    /// file route discovery starts from, relative to the codebase root
+    pub default_entry: PathBuf,
+    /// code patterns that construct or extend a router
+    pub router_signatures: Vec<&'static str>,
+}
+
 #[async_trait]
 pub trait Generator {
+    /// Describes the framework this generator documents
+    fn framework_info(&self) -> FrameworkInfo;
+
     /// Generates an intermediate representation (`IR`) of our eventual documentation spec
-    async fn generate_ir(&self) -> anyhow::Result<IR>;
+    async fn generate_ir(&self) -> Result<IR, DocgenError>;
+
+    /// Returns the routes generated so far, for flushing a run that timed out or was interrupted
+    fn partial_ir(&self) -> IR;
+
+    /// Notified with the number of partial routes whenever `partial_ir` grows
+    fn watch_partial_ir(&self) -> watch::Receiver<usize>;
+
+    /// The LLM requests made so far and the tokens they used
+    fn usage(&self) -> LLMUsage;
 }
 
 pub struct GeneratorBaseInfo {}
@@ -0,0 +1,134 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Directory, relative to the codebase, cached discovery results are kept in
+pub const CACHE_DIR: &str = ".docgen-cache";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct FileStamp {
+    path: PathBuf,
+    modified: SystemTime,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            modified,
+        })
+    }
+
+    fn is_current(&self) -> bool {
+        Self::of(&self.path).as_ref() == Some(self)
+    }
+}
+
+/// The router files found from an entry function, and every file read to find them
+#[derive(Serialize, Deserialize, Debug)]
+struct RouterDiscovery {
+    traversed: Vec<FileStamp>,
+    route_files: Vec<PathBuf>,
+}
+
+/// Caches where the router of a codebase lives, so repeated runs skip the LLM calls
+/// spent walking to it from the entry function. An entry is only used while none of
+/// the files traversed to find the router have changed.
+pub struct RouterCache {
+    dir: PathBuf,
+}
+
+impl RouterCache {
+    pub fn new(code_dir: &Path) -> Self {
+        Self {
+            dir: code_dir.join(CACHE_DIR),
+        }
+    }
+
+    fn entry_file_for(&self, entry_file: &Path, entry_fn: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        (entry_file, entry_fn).hash(&mut hasher);
+        self.dir
+            .join(format!("router-{:016x}.json", hasher.finish()))
+    }
+
+    /// The cached router files for the entry function, if still valid
+    pub fn load(&self, entry_file: &Path, entry_fn: &str) -> Option<Vec<PathBuf>> {
+        let json = fs::read_to_string(self.entry_file_for(entry_file, entry_fn)).ok()?;
+        let discovery = serde_json::from_str::<RouterDiscovery>(&json).ok()?;
+
+        discovery
+            .traversed
+            .iter()
+            .all(FileStamp::is_current)
+            .then_some(discovery.route_files)
+    }
+
+    pub fn store(
+        &self,
+        entry_file: &Path,
+        entry_fn: &str,
+        traversed: &[PathBuf],
+        route_files: &[PathBuf],
+    ) -> anyhow::Result<()> {
+        let discovery = RouterDiscovery {
+            traversed: traversed
+                .iter()
+                .map(|path| FileStamp::of(path).context(format!("failed to stat {:?}", path)))
+                .collect::<anyhow::Result<_>>()?,
+            route_files: route_files.to_vec(),
+        };
+
+        fs::create_dir_all(&self.dir).context(format!("failed to create {:?}", self.dir))?;
+        let file = self.entry_file_for(entry_file, entry_fn);
+        let json = serde_json::to_string(&discovery).context("failed to serialize cache entry")?;
+        fs::write(&file, json).context(format!("failed to write {:?}", file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RouterCache;
+    use crate::utils::fixtures::CrateFixture;
+    use std::{
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    #[test]
+    fn invalidates_entries_when_a_traversed_file_changes() {
+        let krate = CrateFixture::package("api");
+        let main_rs = krate.write("src/main.rs", "fn main() { app::run() }");
+        let app_rs = krate.write("src/app.rs", "pub fn run() { Router::new(); }");
+
+        let cache = RouterCache::new(&krate.root());
+        assert_eq!(cache.load(&main_rs, "main"), None);
+
+        cache
+            .store(
+                &main_rs,
+                "main",
+                &[main_rs.clone(), app_rs.clone()],
+                std::slice::from_ref(&app_rs),
+            )
+            .unwrap();
+        assert_eq!(cache.load(&main_rs, "main"), Some(vec![app_rs.clone()]));
+        // entries are per entry function
+        assert_eq!(cache.load(&main_rs, "app"), None);
+
+        fs::File::options()
+            .write(true)
+            .open(&app_rs)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(cache.load(&main_rs, "main"), None);
+    }
+}
@@ -0,0 +1,307 @@
+use syn::{spanned::Spanned, visit::Visit, Expr, ExprMethodCall, Item, ItemFn};
+
+/// Rough number of tokens the LLM reads for `text`, at about four characters a token
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Splits a router file too large for one LLM call into files holding a segment of its
+/// longest `Router::new()` chain each, along with the rest of the file so imports can still
+/// be resolved. Files that fit in `max_tokens`, or whose chain can't be split, are returned
+/// whole.
+pub fn router_chunks(file_content: &str, max_tokens: usize) -> Vec<String> {
+    if estimate_tokens(file_content) <= max_tokens {
+        return vec![file_content.to_owned()];
+    }
+    let Ok(file) = syn::parse_file(file_content) else {
+        return vec![file_content.to_owned()];
+    };
+
+    // the top level function holding the longest chain, and the calls chained on `Router::new()`
+    let Some((router_fn, calls)) = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(f) => {
+                let mut visitor = RouterChainVisitor { calls: vec![] };
+                visitor.visit_item_fn(f);
+                Some((f, visitor.calls))
+            }
+            _ => None,
+        })
+        .max_by_key(|(_, calls)| calls.len())
+    else {
+        return vec![file_content.to_owned()];
+    };
+    let Some(signature) = fn_signature(router_fn) else {
+        return vec![file_content.to_owned()];
+    };
+    if calls.len() < 2 {
+        return vec![file_content.to_owned()];
+    }
+
+    let rest = file
+        .items
+        .iter()
+        .filter(|item| !matches!(item, Item::Fn(f) if std::ptr::eq(f, router_fn)))
+        .filter_map(|item| item.span().source_text())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let budget = max_tokens.saturating_sub(estimate_tokens(&rest) + estimate_tokens(&signature));
+
+    group_within_budget(calls, budget)
+        .into_iter()
+        .map(|segment| {
+            format!(
+                "{rest}\n\n{signature} {{\n    Router::new()\n        {}\n}}\n",
+                segment.join("\n        ")
+            )
+        })
+        .collect()
+}
+
+/// Splits a handler file too large for one LLM call into files each holding some of
+/// `handlers`, paired with the names of those handlers. Every file keeps the items that
+/// aren't functions, e.g. imports and the types bodies are built from, and a handler too
+/// large on its own is sent as its signature alone. Files that fit in `max_tokens`, or
+/// can't be parsed, are returned whole.
+pub fn handler_chunks(
+    file_content: &str,
+    handlers: &[&str],
+    max_tokens: usize,
+) -> Vec<(Vec<String>, String)> {
+    let whole = || {
+        vec![(
+            handlers.iter().map(|h| h.to_string()).collect(),
+            file_content.to_owned(),
+        )]
+    };
+    if estimate_tokens(file_content) <= max_tokens {
+        return whole();
+    }
+    let Ok(file) = syn::parse_file(file_content) else {
+        return whole();
+    };
+
+    let rest = file
+        .items
+        .iter()
+        .filter(|item| !matches!(item, Item::Fn(_)))
+        .filter_map(|item| item.span().source_text())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let budget = max_tokens.saturating_sub(estimate_tokens(&rest));
+
+    // handlers defined on a struct live in the `impl` blocks kept with the rest of the file
+    let (functions, methods): (Vec<&str>, Vec<&str>) =
+        handlers.iter().partition(|h| !h.contains("::"));
+    let mut sources = Vec::new();
+    for item in &file.items {
+        let Item::Fn(f) = item else {
+            continue;
+        };
+        let name = f.sig.ident.to_string();
+        if !functions.contains(&name.as_str()) {
+            continue;
+        }
+        let Some(source) = f.span().source_text() else {
+            continue;
+        };
+        let source = match estimate_tokens(&source) > budget {
+            true => match fn_signature(f) {
+                Some(signature) => format!("{signature} {{ /* body left out */ }}"),
+                None => source,
+            },
+            false => source,
+        };
+        sources.push((name, source));
+    }
+
+    let mut chunks = Vec::new();
+    let mut names = methods.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+    let mut chunk = Vec::new();
+    let mut used = 0;
+    for (name, source) in sources {
+        let tokens = estimate_tokens(&source);
+        if !chunk.is_empty() && used + tokens > budget {
+            chunks.push((std::mem::take(&mut names), join_chunk(&rest, &chunk)));
+            chunk.clear();
+            used = 0;
+        }
+        names.push(name);
+        chunk.push(source);
+        used += tokens;
+    }
+    if !chunk.is_empty() || !names.is_empty() {
+        chunks.push((names, join_chunk(&rest, &chunk)));
+    }
+
+    chunks
+}
+
+/// The function's source up to its body, with its attributes and visibility
+fn fn_signature(f: &ItemFn) -> Option<String> {
+    let source = f.span().source_text()?;
+    let body = f.block.span().source_text()?;
+    Some(source.strip_suffix(&body)?.trim_end().to_owned())
+}
+
+fn join_chunk(rest: &str, functions: &[String]) -> String {
+    format!("{rest}\n\n{}\n", functions.join("\n\n"))
+}
+
+/// Groups `items` in order so each group stays within `budget` tokens, with at least one
+/// item a group
+fn group_within_budget(items: Vec<String>, budget: usize) -> Vec<Vec<String>> {
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    let mut used = 0;
+    for item in items {
+        let tokens = estimate_tokens(&item);
+        match groups.last_mut() {
+            Some(group) if used + tokens <= budget => group.push(item),
+            _ => {
+                groups.push(vec![item]);
+                used = 0;
+            }
+        }
+        used += tokens;
+    }
+    groups
+}
+
+/// Collects the source of every call chained on `Router::new()`, e.g. `.route("/", get(list))`,
+/// from the longest such chain
+struct RouterChainVisitor {
+    calls: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for RouterChainVisitor {
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        let mut calls = Vec::new();
+        let mut expr = node;
+        let root = loop {
+            calls.push(expr);
+            match expr.receiver.as_ref() {
+                Expr::MethodCall(receiver) => expr = receiver,
+                root => break root,
+            }
+        };
+
+        let is_router_new = match root {
+            Expr::Call(call) => match call.func.as_ref() {
+                Expr::Path(func) => {
+                    let segments = func.path.segments.iter().collect::<Vec<_>>();
+                    matches!(segments.as_slice(), [.., ty, new] if ty.ident == "Router" && new.ident == "new")
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if !is_router_new {
+            syn::visit::visit_expr_method_call(self, node);
+            return;
+        }
+
+        let calls = calls
+            .iter()
+            .rev()
+            .filter_map(|call| {
+                let span = call.method.span().join(call.paren_token.span.close())?;
+                Some(format!(".{}", span.source_text()?))
+            })
+            .collect::<Vec<_>>();
+        if calls.len() > self.calls.len() {
+            self.calls = calls;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate_tokens, handler_chunks, router_chunks};
+    use indoc::indoc;
+
+    #[test]
+    fn splits_the_router_chain_into_segments() {
+        let file = indoc! {r#"
+            use crate::handlers::{channels, users};
+
+            pub fn app() -> Router {
+                Router::new()
+                    .route("/users", get(users::list).post(users::create))
+                    .route("/users/:id", get(users::get))
+                    .route("/channels", get(channels::list))
+                    .route("/channels/:id", delete(channels::delete))
+                    .with_state(AppState::default())
+            }
+        "#};
+
+        assert_eq!(router_chunks(file, 1000), vec![file.to_owned()]);
+
+        let chunks = router_chunks(file, estimate_tokens(file) / 2);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.starts_with("use crate::handlers::{channels, users};"));
+            assert!(chunk.contains("pub fn app() -> Router {\n    Router::new()\n        ."));
+        }
+        let joined = chunks.concat();
+        for call in [
+            r#".route("/users", get(users::list).post(users::create))"#,
+            r#".route("/users/:id", get(users::get))"#,
+            r#".route("/channels", get(channels::list))"#,
+            r#".route("/channels/:id", delete(channels::delete))"#,
+            ".with_state(AppState::default())",
+        ] {
+            assert_eq!(joined.matches(call).count(), 1, "{call}");
+        }
+    }
+
+    #[test]
+    fn splits_handler_files_by_handler() {
+        let file = indoc! {r#"
+            use axum::Json;
+
+            #[derive(Deserialize)]
+            pub struct CreateUser {
+                name: String,
+            }
+
+            pub async fn create(Json(payload): Json<CreateUser>) -> StatusCode {
+                let user = User::new(payload.name);
+                StatusCode::CREATED
+            }
+
+            pub async fn list() -> Json<Vec<User>> {
+                Json(User::all().await)
+            }
+
+            fn helper() {}
+        "#};
+
+        let chunks = handler_chunks(file, &["create", "list"], 1000);
+        assert_eq!(
+            chunks,
+            vec![(
+                vec!["create".to_owned(), "list".to_owned()],
+                file.to_owned()
+            )]
+        );
+
+        let chunks = handler_chunks(file, &["create", "list"], 60);
+        let names = chunks
+            .iter()
+            .map(|(names, _)| names.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![vec!["create".to_owned()], vec!["list".to_owned()]]
+        );
+        for (_, chunk) in &chunks {
+            assert!(chunk.contains("pub struct CreateUser"));
+            assert!(!chunk.contains("fn helper"));
+        }
+        assert!(chunks[0]
+            .1
+            .contains("pub async fn create(Json(payload): Json<CreateUser>)"));
+    }
+}
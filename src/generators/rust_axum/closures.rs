@@ -0,0 +1,152 @@
+use crate::domain::ir::HTTPMethod;
+use syn::{spanned::Spanned, visit::Visit, Expr, ExprClosure, ExprMethodCall, Lit, Pat, Stmt};
+
+/// A handler written inline in a `.route()` call, e.g.
+///
+/// ```text
+/// .route("/ping", get(|| async { "pong" }))
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteClosure {
+    pub path: String,
+    pub method: HTTPMethod,
+    /// `(pattern, type)` of every typed closure argument, as written, e.g. `("Path(id)", "Path<u32>")`
+    pub arguments: Vec<(String, String)>,
+    /// 1-based line the closure starts on
+    pub line: usize,
+}
+
+/// Every closure handler registered with `.route()` in `file_content`, in the order they appear.
+/// Files that can't be parsed have none.
+pub fn route_closures(file_content: &str) -> Vec<RouteClosure> {
+    let Ok(file) = syn::parse_file(file_content) else {
+        return vec![];
+    };
+
+    let mut visitor = RouteClosureVisitor { closures: vec![] };
+    visitor.visit_file(&file);
+    visitor.closures
+}
+
+struct RouteClosureVisitor {
+    closures: Vec<RouteClosure>,
+}
+
+impl<'ast> Visit<'ast> for RouteClosureVisitor {
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        // the receiver holds the routes registered before this one
+        syn::visit::visit_expr_method_call(self, node);
+
+        if node.method != "route" || node.args.len() != 2 {
+            return;
+        }
+        let Expr::Lit(path) = &node.args[0] else {
+            return;
+        };
+        let Lit::Str(path) = &path.lit else {
+            return;
+        };
+
+        let mut handlers = Vec::new();
+        collect_closure_handlers(&node.args[1], &mut handlers);
+        for (method, closure) in handlers {
+            self.closures.push(RouteClosure {
+                path: path.value(),
+                method,
+                arguments: closure
+                    .inputs
+                    .iter()
+                    .filter_map(|input| match input {
+                        Pat::Type(typed) => Some((
+                            typed.pat.span().source_text()?,
+                            typed.ty.span().source_text()?,
+                        )),
+                        _ => None,
+                    })
+                    .collect(),
+                line: closure.span().start().line,
+            });
+        }
+    }
+}
+
+/// Walks a `get(|| ..).post(handler)` style chain for the methods handled by closures
+fn collect_closure_handlers<'a>(expr: &'a Expr, handlers: &mut Vec<(HTTPMethod, &'a ExprClosure)>) {
+    match expr {
+        Expr::Call(call) => {
+            let Expr::Path(func) = call.func.as_ref() else {
+                return;
+            };
+            let Some(method) = func.path.segments.last() else {
+                return;
+            };
+            if let (Ok(method), Some(Expr::Closure(closure))) = (
+                HTTPMethod::try_from(method.ident.to_string().as_str()),
+                call.args.first(),
+            ) {
+                handlers.push((method, closure));
+            }
+        }
+        Expr::MethodCall(call) => {
+            collect_closure_handlers(&call.receiver, handlers);
+            if let (Ok(method), Some(Expr::Closure(closure))) = (
+                HTTPMethod::try_from(call.method.to_string().as_str()),
+                call.args.first(),
+            ) {
+                handlers.push((method, closure));
+            }
+        }
+        Expr::Paren(paren) => collect_closure_handlers(&paren.expr, handlers),
+        Expr::Block(block) => {
+            if let Some(Stmt::Expr(expr, None)) = block.block.stmts.last() {
+                collect_closure_handlers(expr, handlers);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{route_closures, RouteClosure};
+    use crate::domain::ir::HTTPMethod;
+    use indoc::indoc;
+
+    #[test]
+    fn finds_closure_handlers_in_route_calls() {
+        let file = indoc! {r#"
+            pub fn app() -> Router {
+                Router::new()
+                    .route("/ping", get(|| async { "pong" }))
+                    .route("/users", get(users::list))
+                    .route(
+                        "/items/:id",
+                        get(users::get).delete(|Path(id): Path<u32>, State(state): State<AppState>| async move {
+                            state.delete(id).await
+                        }),
+                    )
+            }
+        "#};
+
+        assert_eq!(
+            route_closures(file),
+            vec![
+                RouteClosure {
+                    path: "/ping".to_owned(),
+                    method: HTTPMethod::GET,
+                    arguments: vec![],
+                    line: 3,
+                },
+                RouteClosure {
+                    path: "/items/:id".to_owned(),
+                    method: HTTPMethod::DELETE,
+                    arguments: vec![
+                        ("Path(id)".to_owned(), "Path<u32>".to_owned()),
+                        ("State(state)".to_owned(), "State<AppState>".to_owned()),
+                    ],
+                    line: 7,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use super::{docs::handler_source, enums::serde_attr};
+use crate::utils::apply_serde_rename_rule;
+use std::collections::HashMap;
+use syn::{
+    visit::Visit, Expr, ExprMethodCall, Field, Fields, FnArg, GenericArgument, Item, ItemFn,
+    ItemStruct, Lit, Member, PathArguments, Stmt, Type, UnOp,
+};
+
+/// Default values of the query parameters `handler` reads from a `.unwrap_or(..)` in its
+/// body, e.g. `params.page.unwrap_or(1)`, keyed by parameter name. The fields read are
+/// looked up in `field_names`, from field names to serialized ones, since parameters are
+/// named after the latter. Only literal defaults are determinable. `handler` may be
+/// prefixed with the struct it's defined on.
+pub fn unwrap_or_defaults(
+    file_content: &str,
+    handler: &str,
+    field_names: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let Some(handler) = parse_handler(file_content, handler) else {
+        return HashMap::new();
+    };
+
+    let mut visitor = UnwrapOrVisitor {
+        defaults: HashMap::new(),
+    };
+    visitor.visit_block(&handler.block);
+    visitor
+        .defaults
+        .into_iter()
+        .map(|(field, value)| match field_names.get(&field) {
+            Some(name) => (name.clone(), value),
+            None => (field, value),
+        })
+        .collect()
+}
+
+/// Names of the structs `handler` extracts with `Query<T>`
+pub fn query_struct_names(file_content: &str, handler: &str) -> Vec<String> {
+    let Some(handler) = parse_handler(file_content, handler) else {
+        return vec![];
+    };
+
+    handler
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(typed) => query_type(&typed.ty),
+            FnArg::Receiver(_) => None,
+        })
+        .collect()
+}
+
+/// Serialized names of the fields of `struct_name`, keyed by field name. `None` when the
+/// struct isn't defined in `file_content`.
+pub fn serde_field_names(file_content: &str, struct_name: &str) -> Option<HashMap<String, String>> {
+    let file = syn::parse_file(file_content).ok()?;
+    let item = find_struct(&file, struct_name)?;
+    let Fields::Named(fields) = &item.fields else {
+        return Some(HashMap::new());
+    };
+
+    let rename_all = serde_attr(&item.attrs, "rename_all");
+    let names = fields
+        .named
+        .iter()
+        .filter_map(|field| {
+            let ident = field.ident.as_ref()?.to_string();
+            Some((ident, serialized_name(field, rename_all.as_deref())?))
+        })
+        .collect();
+
+    Some(names)
+}
+
+/// Default values of the fields of `struct_name` set with `#[serde(default = "path")]`,
+/// keyed by their serialized names, where `path` is a function of the same file returning
+/// a literal. `None` when the struct isn't defined in `file_content`.
+pub fn serde_field_defaults(
+    file_content: &str,
+    struct_name: &str,
+) -> Option<HashMap<String, String>> {
+    let file = syn::parse_file(file_content).ok()?;
+    let item = find_struct(&file, struct_name)?;
+    let Fields::Named(fields) = &item.fields else {
+        return Some(HashMap::new());
+    };
+
+    let rename_all = serde_attr(&item.attrs, "rename_all");
+    let defaults = fields
+        .named
+        .iter()
+        .filter_map(|field| {
+            let default_fn = serde_attr(&field.attrs, "default")?;
+            let default_fn = default_fn.rsplit("::").next().unwrap_or(&default_fn);
+            let value = file.items.iter().find_map(|item| match item {
+                Item::Fn(f) if f.sig.ident == default_fn => returned_literal(f),
+                _ => None,
+            })?;
+
+            Some((serialized_name(field, rename_all.as_deref())?, value))
+        })
+        .collect();
+
+    Some(defaults)
+}
+
+fn find_struct<'a>(file: &'a syn::File, struct_name: &str) -> Option<&'a ItemStruct> {
+    file.items.iter().find_map(|item| match item {
+        Item::Struct(s) if s.ident == struct_name => Some(s),
+        _ => None,
+    })
+}
+
+/// The name serde (de)serializes a named field with, given its struct's `rename_all` rule
+fn serialized_name(field: &Field, rename_all: Option<&str>) -> Option<String> {
+    let ident = field.ident.as_ref()?.to_string();
+    Some(match (serde_attr(&field.attrs, "rename"), rename_all) {
+        (Some(rename), _) => rename,
+        (None, Some(rule)) => apply_serde_rename_rule(&ident, rule),
+        (None, None) => ident,
+    })
+}
+
+fn parse_handler(file_content: &str, handler: &str) -> Option<ItemFn> {
+    // methods parse as free functions too, receiver included
+    syn::parse_str(&handler_source(file_content, handler)?).ok()
+}
+
+/// `T` for `Query<T>`, also when wrapped in `Option`
+fn query_type(ty: &Type) -> Option<String> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    let Some(GenericArgument::Type(inner)) = args.args.first() else {
+        return None;
+    };
+
+    match segment.ident.to_string().as_str() {
+        "Query" => match inner {
+            Type::Path(inner) => Some(inner.path.segments.last()?.ident.to_string()),
+            _ => None,
+        },
+        "Option" => query_type(inner),
+        _ => None,
+    }
+}
+
+/// The literal a function returns as its only expression, e.g. `20` for
+/// `fn default_limit() -> u32 { 20 }`
+fn returned_literal(f: &ItemFn) -> Option<String> {
+    match f.block.stmts.as_slice() {
+        [Stmt::Expr(expr, None)] => literal_value(expr),
+        _ => None,
+    }
+}
+
+/// The value of a literal expression as a string, e.g. `-1`, `true` or `asc` for
+/// `"asc".to_owned()`
+fn literal_value(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Str(s) => Some(s.value()),
+            Lit::Int(i) => Some(i.base10_digits().to_owned()),
+            Lit::Float(f) => Some(f.base10_digits().to_owned()),
+            Lit::Bool(b) => Some(b.value.to_string()),
+            Lit::Char(c) => Some(c.value().to_string()),
+            _ => None,
+        },
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => {
+            Some(format!("-{}", literal_value(&unary.expr)?))
+        }
+        Expr::Paren(paren) => literal_value(&paren.expr),
+        Expr::MethodCall(call)
+            if call.args.is_empty()
+                && ["to_owned", "to_string", "into"]
+                    .contains(&call.method.to_string().as_str()) =>
+        {
+            literal_value(&call.receiver)
+        }
+        _ => None,
+    }
+}
+
+struct UnwrapOrVisitor {
+    defaults: HashMap<String, String>,
+}
+
+impl<'ast> Visit<'ast> for UnwrapOrVisitor {
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        syn::visit::visit_expr_method_call(self, node);
+
+        if node.method != "unwrap_or" || node.args.len() != 1 {
+            return;
+        }
+        // `params.page` or a destructured `page`
+        let name = match node.receiver.as_ref() {
+            Expr::Field(field) => match &field.member {
+                Member::Named(ident) => ident.to_string(),
+                Member::Unnamed(_) => return,
+            },
+            Expr::Path(path) => match path.path.get_ident() {
+                Some(ident) => ident.to_string(),
+                None => return,
+            },
+            _ => return,
+        };
+        if let Some(value) = literal_value(&node.args[0]) {
+            self.defaults.entry(name).or_insert(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{query_struct_names, serde_field_defaults, serde_field_names, unwrap_or_defaults};
+    use indoc::indoc;
+    use std::collections::HashMap;
+
+    const FILE: &str = indoc! {r#"
+        fn default_limit() -> u32 {
+            20
+        }
+
+        fn default_order() -> String {
+            "desc".to_owned()
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        pub struct Pagination {
+            page: Option<u32>,
+            #[serde(default = "default_limit")]
+            page_size: u32,
+            #[serde(default = "self::default_order", rename = "sort")]
+            order: String,
+            #[serde(default)]
+            archived: bool,
+            per_page: Option<u32>,
+        }
+
+        pub async fn list(Query(params): Query<Pagination>, State(db): State<Db>) -> Json<Vec<Item>> {
+            let page = params.page.unwrap_or(1);
+            let offset = params.offset.unwrap_or(-1).max(0);
+            let per_page = params.per_page.unwrap_or(50);
+            let name = params.name.unwrap_or_default();
+            Json(db.items(page, offset, name).await)
+        }
+    "#};
+
+    #[test]
+    fn reads_serde_field_defaults() {
+        assert_eq!(
+            serde_field_defaults(FILE, "Pagination"),
+            Some(HashMap::from([
+                ("pageSize".to_owned(), "20".to_owned()),
+                ("sort".to_owned(), "desc".to_owned()),
+            ]))
+        );
+        assert_eq!(serde_field_defaults(FILE, "Missing"), None);
+    }
+
+    #[test]
+    fn reads_unwrap_or_defaults_in_the_handler() {
+        assert_eq!(query_struct_names(FILE, "list"), vec!["Pagination"]);
+        assert_eq!(
+            unwrap_or_defaults(FILE, "list", &HashMap::new()),
+            HashMap::from([
+                ("page".to_owned(), "1".to_owned()),
+                ("offset".to_owned(), "-1".to_owned()),
+                ("per_page".to_owned(), "50".to_owned()),
+            ])
+        );
+
+        // fields are read by their Rust name, parameters named as `rename_all` serializes them
+        let field_names = serde_field_names(FILE, "Pagination").unwrap();
+        assert_eq!(field_names["per_page"], "perPage");
+        assert_eq!(field_names["order"], "sort");
+        assert_eq!(
+            unwrap_or_defaults(FILE, "list", &field_names),
+            HashMap::from([
+                ("page".to_owned(), "1".to_owned()),
+                ("offset".to_owned(), "-1".to_owned()),
+                ("perPage".to_owned(), "50".to_owned()),
+            ])
+        );
+    }
+}
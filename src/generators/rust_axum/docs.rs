@@ -0,0 +1,352 @@
+use crate::domain::ir::OperationDocs;
+use proc_macro2::Span;
+use syn::{
+    spanned::Spanned, AttrStyle, Attribute, Expr, ImplItem, Item, Lit, Meta, Signature, Type,
+    Visibility,
+};
+
+/// Reads the `@` directives from the comments directly above a handler, e.g.
+///
+/// ```text
+/// /// @openapi.summary Create a channel
+/// // @tag admin
+/// // @x-rate-limit 10/min
+/// pub async fn create(..) {}
+/// ```
+///
+/// Without summary or description directives, the first line of the handler's doc
+/// comment is its summary and the rest of the doc comment its description.
+///
+/// `function_name` may be prefixed with the struct it's defined on (`Struct::method`).
+/// Handlers that can't be found or parsed get empty docs.
+pub fn extract_handler_docs(file_content: &str, function_name: &str) -> OperationDocs {
+    let Ok(file) = syn::parse_file(file_content) else {
+        return OperationDocs::default();
+    };
+    let Some(handler) = find_handler(&file, function_name) else {
+        return OperationDocs::default();
+    };
+
+    // plain `//` comments aren't in the syntax tree, they're read from the lines directly
+    // above the handler and from between its attributes, up to the `fn`
+    let lines = file_content.lines().collect::<Vec<&str>>();
+    let start = handler.span.start().line.saturating_sub(1).min(lines.len());
+    let fn_line = handler.sig.fn_token.span().start().line.saturating_sub(1);
+    let mut above = lines[..start]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with("//") && !line.starts_with("///"))
+        .collect::<Vec<&str>>();
+    above.reverse();
+    let between = lines[start..fn_line.clamp(start, lines.len())]
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| line.starts_with("//") && !line.starts_with("///"));
+    let comments = above
+        .into_iter()
+        .chain(between)
+        .map(|line| (false, line.trim_start_matches('/').trim().to_owned()));
+
+    // `///` doc comments are `#[doc]` attributes, whatever attributes sit around them
+    let doc_lines = handler
+        .attrs
+        .iter()
+        .filter(|attr| matches!(attr.style, AttrStyle::Outer))
+        .filter_map(doc_attr_value)
+        .flat_map(|doc| {
+            doc.lines()
+                .map(|line| (true, line.trim().to_owned()))
+                .collect::<Vec<_>>()
+        });
+
+    let mut docs = OperationDocs::default();
+    let mut prose = Vec::new();
+    let comments = comments.chain(doc_lines).collect::<Vec<(bool, String)>>();
+    for (is_doc, comment) in comments.iter().map(|(d, c)| (*d, c.as_str())) {
+        let Some(directive) = comment.strip_prefix('@') else {
+            if is_doc {
+                prose.push(comment);
+            }
+            continue;
+        };
+        let (key, value) = directive
+            .split_once(char::is_whitespace)
+            .map(|(k, v)| (k, v.trim()))
+            .unwrap_or((directive, ""));
+
+        match key {
+            "openapi.summary" | "summary" => docs.summary = Some(value.to_owned()),
+            "openapi.description" | "description" => docs.description = Some(value.to_owned()),
+            "openapi.operation_id" | "operation_id" => docs.operation_id = Some(value.to_owned()),
+            "openapi.tag" | "openapi.tags" | "tag" | "tags" => docs.tags.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_owned),
+            ),
+            key if key.starts_with("x-") => {
+                docs.extensions.insert(key.to_owned(), value.to_owned());
+            }
+            _ => {}
+        }
+    }
+
+    let mut prose = prose.into_iter().skip_while(|line| line.is_empty());
+    if docs.summary.is_none() {
+        docs.summary = prose.next().map(str::to_owned);
+    } else {
+        prose.next();
+    }
+    if docs.description.is_none() {
+        let description = prose.collect::<Vec<&str>>().join("\n");
+        let description = description.trim();
+        docs.description = (!description.is_empty()).then(|| description.to_owned());
+    }
+
+    docs
+}
+
+/// The module's own doc comment, written with `//!` or `#![doc = ".."]`
+pub fn module_docs(file_content: &str) -> Option<String> {
+    let file = syn::parse_file(file_content).ok()?;
+    let lines = file
+        .attrs
+        .iter()
+        .filter(|attr| matches!(attr.style, AttrStyle::Inner(_)))
+        .filter_map(doc_attr_value)
+        .collect::<Vec<_>>();
+
+    let docs = lines
+        .iter()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let docs = docs.trim();
+    (!docs.is_empty()).then(|| docs.to_owned())
+}
+
+/// The text of a `#[doc = ".."]` attribute, which is what doc comments are parsed into
+fn doc_attr_value(attr: &Attribute) -> Option<String> {
+    match &attr.meta {
+        Meta::NameValue(doc) if doc.path.is_ident("doc") => match &doc.value {
+            Expr::Lit(lit) => match &lit.lit {
+                Lit::Str(line) => Some(line.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 1-based line of the handler's `fn` keyword
+pub fn find_handler_line(file_content: &str, function_name: &str) -> Option<usize> {
+    let file = syn::parse_file(file_content).ok()?;
+    let handler = find_handler(&file, function_name)?;
+    Some(handler.sig.fn_token.span().start().line)
+}
+
+/// The source of the handler, from its attributes and doc comments to its closing brace
+pub fn handler_source(file_content: &str, function_name: &str) -> Option<String> {
+    let file = syn::parse_file(file_content).ok()?;
+    find_handler(&file, function_name)?.span.source_text()
+}
+
+/// Whether the handler is declared plain `pub`, rather than private or restricted with
+/// `pub(crate)` or `pub(super)`. `None` when the handler can't be found.
+pub fn handler_is_public(file_content: &str, function_name: &str) -> Option<bool> {
+    let file = syn::parse_file(file_content).ok()?;
+    let handler = find_handler(&file, function_name)?;
+    Some(matches!(handler.vis, Visibility::Public(_)))
+}
+
+/// A handler function found in a parsed file
+struct Handler<'a> {
+    sig: &'a Signature,
+    vis: &'a Visibility,
+    attrs: &'a [Attribute],
+    /// the whole definition, from its first attribute to its closing brace
+    span: Span,
+}
+
+fn find_handler<'a>(file: &'a syn::File, function_name: &str) -> Option<Handler<'a>> {
+    let (struct_name, fn_name) = match function_name.rsplit_once("::") {
+        Some((struct_name, fn_name)) => (Some(struct_name), fn_name),
+        None => (None, function_name),
+    };
+
+    file.items
+        .iter()
+        .find_map(|item| match (item, struct_name) {
+            (Item::Fn(f), None) if f.sig.ident == fn_name => Some(Handler {
+                sig: &f.sig,
+                vis: &f.vis,
+                attrs: &f.attrs,
+                span: f.span(),
+            }),
+            (Item::Impl(i), Some(struct_name)) => {
+                let Type::Path(self_ty) = i.self_ty.as_ref() else {
+                    return None;
+                };
+                if self_ty.path.segments.last()?.ident != struct_name {
+                    return None;
+                }
+
+                i.items.iter().find_map(|item| match item {
+                    ImplItem::Fn(f) if f.sig.ident == fn_name => Some(Handler {
+                        sig: &f.sig,
+                        vis: &f.vis,
+                        attrs: &f.attrs,
+                        span: f.span(),
+                    }),
+                    _ => None,
+                })
+            }
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_handler_docs, handler_is_public, handler_source, module_docs};
+    use indoc::indoc;
+
+    const FILE: &str = indoc! {r#"
+        use axum::Json;
+
+        /// Creates a channel
+        /// @openapi.summary Create a channel
+        /// @openapi.description Creates a channel owned by the caller
+        // @tag admin
+        // @tags channels, write
+        // @x-rate-limit 10/min
+        #[tracing::instrument]
+        pub async fn create(Json(payload): Json<Payload>) {}
+
+        // @tag unrelated
+        fn helper() {}
+
+        pub async fn get() {}
+
+        pub struct Channels;
+
+        impl Channels {
+            /// @openapi.summary Delete a channel
+            pub async fn delete() {}
+        }
+
+        /// Lists the channels the caller can see
+        ///
+        /// Archived channels are left out
+        /// unless `archived` is set.
+        // not part of the docs
+        pub async fn list() {}
+    "#};
+
+    #[test]
+    fn reads_directives_above_the_handler() {
+        let docs = extract_handler_docs(FILE, "create");
+
+        assert_eq!(docs.summary.as_deref(), Some("Create a channel"));
+        assert_eq!(
+            docs.description.as_deref(),
+            Some("Creates a channel owned by the caller")
+        );
+        assert_eq!(docs.tags, vec!["admin", "channels", "write"]);
+        assert_eq!(
+            docs.extensions.get("x-rate-limit").map(String::as_str),
+            Some("10/min")
+        );
+    }
+
+    #[test]
+    fn tells_public_handlers_apart() {
+        let file = indoc! {r#"
+            pub async fn list() {}
+            pub(crate) async fn export() {}
+            async fn purge() {}
+        "#};
+
+        assert_eq!(handler_is_public(file, "list"), Some(true));
+        assert_eq!(handler_is_public(file, "export"), Some(false));
+        assert_eq!(handler_is_public(file, "purge"), Some(false));
+        assert_eq!(handler_is_public(FILE, "Channels::delete"), Some(true));
+        assert_eq!(handler_is_public(file, "missing"), None);
+    }
+
+    #[test]
+    fn reads_the_module_doc_comment() {
+        let file = indoc! {r#"
+            //! Channels a user can post messages to
+            //!
+            #![doc = "Only members can read them."]
+
+            /// Creates a channel
+            pub async fn create() {}
+        "#};
+
+        assert_eq!(
+            module_docs(file).as_deref(),
+            Some("Channels a user can post messages to\n\nOnly members can read them.")
+        );
+        assert_eq!(module_docs(FILE), None);
+    }
+
+    #[test]
+    fn ignores_comments_that_belong_to_other_items() {
+        let docs = extract_handler_docs(FILE, "get");
+        assert!(docs.summary.is_none());
+        assert!(docs.tags.is_empty());
+    }
+
+    #[test]
+    fn falls_back_to_the_doc_comment() {
+        let docs = extract_handler_docs(FILE, "list");
+        assert_eq!(
+            docs.summary.as_deref(),
+            Some("Lists the channels the caller can see")
+        );
+        assert_eq!(
+            docs.description.as_deref(),
+            Some("Archived channels are left out\nunless `archived` is set.")
+        );
+    }
+
+    #[test]
+    fn reads_docs_above_multi_line_attributes() {
+        let file = indoc! {r#"
+            /// Archives a channel
+            // @tag admin
+            #[instrument(
+                skip(state)
+            )]
+            // @x-audit true
+            pub async fn archive(state: State) {}
+        "#};
+
+        let docs = extract_handler_docs(file, "archive");
+        assert_eq!(docs.summary.as_deref(), Some("Archives a channel"));
+        assert_eq!(docs.tags, vec!["admin"]);
+        assert_eq!(
+            docs.extensions.get("x-audit").map(String::as_str),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn finds_methods_on_structs() {
+        let docs = extract_handler_docs(FILE, "Channels::delete");
+        assert_eq!(docs.summary.as_deref(), Some("Delete a channel"));
+    }
+
+    #[test]
+    fn reads_the_handler_source() {
+        assert_eq!(
+            handler_source(FILE, "Channels::delete").as_deref(),
+            Some("/// @openapi.summary Delete a channel\n    pub async fn delete() {}")
+        );
+        assert_eq!(handler_source(FILE, "missing"), None);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::utils::{apply_serde_rename_rule, to_snake_case};
+use syn::{Attribute, Fields, Item, LitStr};
+
+/// Serialized names of the variants of the unit enum `name` defined in `file_content`,
+/// honouring `#[serde(rename_all = "..")]` and per-variant `#[serde(rename = "..")]`.
+/// Returns `None` when the enum isn't defined there or any variant carries data.
+pub fn unit_enum_variants(file_content: &str, name: &str) -> Option<Vec<String>> {
+    let file = syn::parse_file(file_content).ok()?;
+    let item = file.items.iter().find_map(|item| match item {
+        Item::Enum(e) if e.ident == name => Some(e),
+        _ => None,
+    })?;
+
+    let rename_all = serde_attr(&item.attrs, "rename_all");
+    item.variants
+        .iter()
+        .map(|variant| {
+            if !matches!(variant.fields, Fields::Unit) {
+                return None;
+            }
+
+            let ident = variant.ident.to_string();
+            Some(match (serde_attr(&variant.attrs, "rename"), &rename_all) {
+                (Some(rename), _) => rename,
+                (None, Some(rule)) => variant_wire_name(&ident, rule),
+                (None, None) => ident,
+            })
+        })
+        .collect()
+}
+
+/// Applies a `rename_all` rule to a PascalCase variant name the way serde does
+fn variant_wire_name(variant: &str, rule: &str) -> String {
+    match rule {
+        "lowercase" => variant.to_lowercase(),
+        "UPPERCASE" => variant.to_uppercase(),
+        "PascalCase" => variant.to_owned(),
+        rule => apply_serde_rename_rule(&to_snake_case(variant), rule),
+    }
+}
+
+/// The string value of `#[serde(<key> = "..")]`, if present
+pub fn serde_attr(attrs: &[Attribute], key: &str) -> Option<String> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                value = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.input.peek(syn::Token![=]) {
+                // skip the values of other keys, e.g. `default = "path"`
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        });
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unit_enum_variants;
+    use indoc::indoc;
+
+    const FILE: &str = indoc! {r#"
+        #[derive(Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        pub enum OrderStatus {
+            Pending,
+            InProgress,
+            #[serde(rename = "done")]
+            Completed,
+        }
+
+        #[derive(Deserialize)]
+        pub enum Sort {
+            Asc,
+            Desc,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(tag = "type")]
+        pub enum Filter {
+            All,
+            ByName(String),
+        }
+    "#};
+
+    #[test]
+    fn reads_serialized_variant_names() {
+        assert_eq!(
+            unit_enum_variants(FILE, "OrderStatus"),
+            Some(vec![
+                "pending".to_owned(),
+                "in_progress".to_owned(),
+                "done".to_owned()
+            ])
+        );
+        assert_eq!(
+            unit_enum_variants(FILE, "Sort"),
+            Some(vec!["Asc".to_owned(), "Desc".to_owned()])
+        );
+    }
+
+    #[test]
+    fn skips_enums_with_data_and_missing_enums() {
+        assert_eq!(unit_enum_variants(FILE, "Filter"), None);
+        assert_eq!(unit_enum_variants(FILE, "Missing"), None);
+    }
+}
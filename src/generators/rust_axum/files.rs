@@ -0,0 +1,54 @@
+use std::{
+    collections::HashMap,
+    fs::read_to_string,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Contents of the source files read during a run, so a file holding several handlers or
+/// the types of several bodies is only read once
+#[derive(Debug, Default)]
+pub struct FileCache {
+    contents: Mutex<HashMap<PathBuf, Arc<str>>>,
+}
+
+impl FileCache {
+    /// The contents of `path`, read from disk the first time it's asked for.
+    /// Files that can't be read aren't cached, so a later read tries again.
+    pub fn read(&self, path: &Path) -> io::Result<Arc<str>> {
+        if let Some(content) = self.lock().get(path) {
+            return Ok(Arc::clone(content));
+        }
+
+        let content: Arc<str> = read_to_string(path)?.into();
+        self.lock().insert(path.to_path_buf(), Arc::clone(&content));
+        Ok(content)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Arc<str>>> {
+        self.contents.lock().expect("file cache lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileCache;
+    use crate::utils::fixtures::CrateFixture;
+    use std::{fs, sync::Arc};
+
+    #[test]
+    fn reads_each_file_once() {
+        let krate = CrateFixture::package("api");
+        let file = krate.write("src/handlers.rs", "pub async fn list() {}");
+
+        let cache = FileCache::default();
+        let first = cache.read(&file).unwrap();
+        fs::write(&file, "pub async fn changed() {}").unwrap();
+        let second = cache.read(&file).unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(&*second, "pub async fn list() {}");
+        assert!(cache.read(&krate.root().join("src/missing.rs")).is_err());
+    }
+}
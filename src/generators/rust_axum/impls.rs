@@ -0,0 +1,273 @@
+use super::files::FileCache;
+use std::{
+    collections::HashSet,
+    fs::read_dir,
+    path::{Path, PathBuf},
+};
+use syn::{ImplItem, Item, Type};
+
+/// Whether `file_content` has an `impl` block of `struct_name`, inherent or of a trait,
+/// defining `function`
+pub fn impl_defines_fn(file_content: &str, struct_name: &str, function: &str) -> bool {
+    let Ok(file) = syn::parse_file(file_content) else {
+        return false;
+    };
+
+    file.items.iter().any(|item| match item {
+        Item::Impl(block) => {
+            let Type::Path(self_ty) = block.self_ty.as_ref() else {
+                return false;
+            };
+            self_ty
+                .path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == struct_name)
+                && block.items.iter().any(|item| match item {
+                    ImplItem::Fn(f) => f.sig.ident == function,
+                    _ => false,
+                })
+        }
+        _ => false,
+    })
+}
+
+/// An `impl` of a trait for a type, e.g. `impl Routable for UsersModule`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitImpl {
+    pub trait_name: String,
+    /// name of the implementing type, without its path or generics
+    pub self_ty: String,
+    pub file: PathBuf,
+    /// functions the `impl` defines, not counting the trait's default ones
+    pub functions: Vec<String>,
+}
+
+/// The traits declared in a crate and their impls, scanned once a run so a call made
+/// through a trait can be followed into each impl without walking the crate again
+#[derive(Debug, Default)]
+pub struct TraitImpls {
+    traits: HashSet<String>,
+    impls: Vec<TraitImpl>,
+}
+
+impl TraitImpls {
+    /// Scans every rust file under `dir`, through `files` so they're read once a run
+    pub fn scan(dir: &Path, files: &FileCache) -> Self {
+        let mut paths = Vec::new();
+        collect_rust_files(dir, &mut paths);
+
+        let mut trait_impls = Self::default();
+        for path in paths {
+            let Ok(content) = files.read(&path) else {
+                continue;
+            };
+            trait_impls.add_file(&path, &content);
+        }
+        trait_impls
+    }
+
+    /// Adds the traits declared and implemented in the file at `path`
+    pub fn add_file(&mut self, path: &Path, file_content: &str) {
+        let Ok(file) = syn::parse_file(file_content) else {
+            return;
+        };
+
+        for item in &file.items {
+            match item {
+                Item::Trait(declared) => {
+                    self.traits.insert(declared.ident.to_string());
+                }
+                Item::Impl(block) => {
+                    let Some((_, trait_path, _)) = &block.trait_ else {
+                        continue;
+                    };
+                    let Type::Path(self_ty) = block.self_ty.as_ref() else {
+                        continue;
+                    };
+                    let (Some(trait_name), Some(self_ty)) =
+                        (trait_path.segments.last(), self_ty.path.segments.last())
+                    else {
+                        continue;
+                    };
+                    self.impls.push(TraitImpl {
+                        trait_name: trait_name.ident.to_string(),
+                        self_ty: self_ty.ident.to_string(),
+                        file: path.to_path_buf(),
+                        functions: block
+                            .items
+                            .iter()
+                            .filter_map(|item| match item {
+                                ImplItem::Fn(f) => Some(f.sig.ident.to_string()),
+                                _ => None,
+                            })
+                            .collect(),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The impls of `trait_name` defining `function`, in file order. Traits that aren't
+    /// declared in the crate, e.g. `Default` or `From`, have none.
+    pub fn defining(&self, trait_name: &str, function: &str) -> Vec<&TraitImpl> {
+        if !self.traits.contains(trait_name) {
+            return vec![];
+        }
+        self.impls
+            .iter()
+            .filter(|i| i.trait_name == trait_name && i.functions.iter().any(|f| f == function))
+            .collect()
+    }
+}
+
+/// The file whose `impl` of `struct_name` defines `function`, looking in `preferred` first
+/// and then through every rust file under `dir`
+pub fn find_impl_file(
+    dir: &Path,
+    struct_name: &str,
+    function: &str,
+    preferred: &[&Path],
+    files: &FileCache,
+) -> Option<PathBuf> {
+    let defines = |file: &Path| {
+        files
+            .read(file)
+            .is_ok_and(|content| impl_defines_fn(&content, struct_name, function))
+    };
+
+    if let Some(file) = preferred.iter().find(|file| defines(file)) {
+        return Some(file.to_path_buf());
+    }
+
+    let mut paths = Vec::new();
+    collect_rust_files(dir, &mut paths);
+    paths.into_iter().find(|file| defines(file))
+}
+
+fn collect_rust_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = read_dir(dir) else {
+        return;
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect::<Vec<_>>();
+    // walk in a stable order so the same file wins on every run
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_rust_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_impl_file, impl_defines_fn, FileCache, TraitImpls};
+    use crate::utils::fixtures::CrateFixture;
+    use indoc::indoc;
+    use std::path::Path;
+
+    #[test]
+    fn finds_the_impl_defining_an_associated_function() {
+        let krate = CrateFixture::package("api");
+        let app = indoc! {"
+            pub struct Application;
+
+            impl Application {
+                pub async fn build() -> Self {
+                    let router = Self::routes(AppState::default());
+                    Self
+                }
+            }
+        "};
+        let routes = indoc! {"
+            use super::Application;
+
+            impl Application {
+                pub fn routes(state: AppState) -> Router {
+                    Router::new().with_state(state)
+                }
+            }
+        "};
+        let app_rs = krate.write("src/app.rs", app);
+        let routes_rs = krate.write("src/startup/routes.rs", routes);
+
+        assert!(impl_defines_fn(app, "Application", "build"));
+        assert!(!impl_defines_fn(app, "Application", "routes"));
+        assert!(impl_defines_fn(routes, "Application", "routes"));
+
+        let src = krate.root().join("src");
+        let files = FileCache::default();
+        assert_eq!(
+            find_impl_file(&src, "Application", "routes", &[&app_rs], &files),
+            Some(routes_rs)
+        );
+        assert_eq!(
+            find_impl_file(&src, "Application", "missing", &[], &files),
+            None
+        );
+    }
+
+    #[test]
+    fn indexes_the_impls_of_the_crates_traits() {
+        let mut trait_impls = TraitImpls::default();
+        trait_impls.add_file(
+            Path::new("src/lib.rs"),
+            indoc! {"
+                pub trait Routable {
+                    fn routes() -> Router;
+                }
+            "},
+        );
+        trait_impls.add_file(
+            Path::new("src/features/channels.rs"),
+            indoc! {r#"
+                pub struct Channels;
+
+                impl Channels {
+                    fn routes() -> Vec<String> { vec![] }
+                }
+
+                impl crate::Routable for Channels {
+                    fn routes() -> Router {
+                        Router::new().route("/channels", get(list))
+                    }
+                }
+
+                impl Default for Channels {
+                    fn default() -> Self { Self }
+                }
+            "#},
+        );
+        trait_impls.add_file(
+            Path::new("src/features/users.rs"),
+            indoc! {r#"
+                impl<S> Routable for Users<S> {
+                    fn routes() -> Router {
+                        Router::new().route("/users", get(list))
+                    }
+                }
+            "#},
+        );
+
+        let impls = trait_impls.defining("Routable", "routes");
+        assert_eq!(
+            impls
+                .iter()
+                .map(|i| (i.self_ty.as_str(), i.file.as_path()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Channels", Path::new("src/features/channels.rs")),
+                ("Users", Path::new("src/features/users.rs")),
+            ]
+        );
+        assert!(trait_impls.defining("Routable", "layer").is_empty());
+        // only traits declared in the crate are dispatched through
+        assert!(trait_impls.defining("Default", "default").is_empty());
+    }
+}
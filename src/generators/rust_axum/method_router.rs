@@ -0,0 +1,119 @@
+use crate::domain::ir::HTTPMethod;
+use syn::{Expr, Item, Stmt};
+
+/// The `(method, handler path)` pairs registered by the method router that the function or
+/// const `name` builds, e.g.
+///
+/// ```text
+/// fn user_routes() -> MethodRouter {
+///     get(users::list).post(users::create)
+/// }
+/// ```
+///
+/// Returns `None` when `name` isn't defined in `file_content` or doesn't build a method router.
+pub fn method_router_handlers(file_content: &str, name: &str) -> Option<Vec<(HTTPMethod, String)>> {
+    let file = syn::parse_file(file_content).ok()?;
+    let expr = file.items.iter().find_map(|item| match item {
+        Item::Fn(f) if f.sig.ident == name => match f.block.stmts.last()? {
+            Stmt::Expr(expr, None) => Some(expr),
+            _ => None,
+        },
+        Item::Const(c) if c.ident == name => Some(c.expr.as_ref()),
+        Item::Static(s) if s.ident == name => Some(s.expr.as_ref()),
+        _ => None,
+    })?;
+
+    let mut handlers = Vec::new();
+    collect_method_handlers(expr, &mut handlers);
+    (!handlers.is_empty()).then_some(handlers)
+}
+
+/// Walks a `get(a).post(b)` style chain, in registration order
+fn collect_method_handlers(expr: &Expr, handlers: &mut Vec<(HTTPMethod, String)>) {
+    match expr {
+        Expr::Call(call) => {
+            let Expr::Path(func) = call.func.as_ref() else {
+                return;
+            };
+            let Some(method) = func.path.segments.last() else {
+                return;
+            };
+            if let (Ok(method), Some(handler)) = (
+                HTTPMethod::try_from(method.ident.to_string().as_str()),
+                call.args.first().and_then(handler_path),
+            ) {
+                handlers.push((method, handler));
+            }
+        }
+        Expr::MethodCall(call) => {
+            collect_method_handlers(&call.receiver, handlers);
+            if let (Ok(method), Some(handler)) = (
+                HTTPMethod::try_from(call.method.to_string().as_str()),
+                call.args.first().and_then(handler_path),
+            ) {
+                handlers.push((method, handler));
+            }
+        }
+        Expr::Paren(paren) => collect_method_handlers(&paren.expr, handlers),
+        Expr::Block(block) => {
+            if let Some(Stmt::Expr(expr, None)) = block.block.stmts.last() {
+                collect_method_handlers(expr, handlers);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The path of a handler given by name, e.g. `users::list`. Closures have none.
+fn handler_path(handler: &Expr) -> Option<String> {
+    let Expr::Path(path) = handler else {
+        return None;
+    };
+
+    Some(
+        path.path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<String>>()
+            .join("::"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::method_router_handlers;
+    use crate::domain::ir::HTTPMethod;
+    use indoc::indoc;
+
+    const FILE: &str = indoc! {r#"
+        use axum::routing::{get, post, MethodRouter};
+
+        pub fn user_routes() -> MethodRouter<AppState> {
+            get(users::list).post(users::create)
+        }
+
+        pub const HEALTH: MethodRouter = axum::routing::get(health);
+
+        fn not_a_router() -> u32 {
+            1
+        }
+    "#};
+
+    #[test]
+    fn reads_handlers_from_method_router_builders() {
+        assert_eq!(
+            method_router_handlers(FILE, "user_routes"),
+            Some(vec![
+                (HTTPMethod::GET, "users::list".to_owned()),
+                (HTTPMethod::POST, "users::create".to_owned()),
+            ])
+        );
+        assert_eq!(
+            method_router_handlers(FILE, "HEALTH"),
+            Some(vec![(HTTPMethod::GET, "health".to_owned())])
+        );
+        assert_eq!(method_router_handlers(FILE, "not_a_router"), None);
+        assert_eq!(method_router_handlers(FILE, "missing"), None);
+    }
+}
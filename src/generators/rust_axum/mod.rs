@@ -1,54 +1,72 @@
-use super::Generator;
+use super::{FrameworkInfo, Generator};
 use crate::{
-    domain::ir::{self, Body, BodyProperty, BodyType, HTTPMethod, Parameter, PropType, Route, IR},
+    call_graph::manifest::Manifest,
+    code::imports::{self, defines_symbol, follow_reexports, ImportKind},
+    domain::ir::{
+        self, Body, BodyProperty, BodyShape, BodyType, ErrorResponse, HTTPMethod, Parameter,
+        PropType, Route, Warning, IR,
+    },
+    error::DocgenError,
+    huggingface::{
+        task::code_summarizer::SummarizeCodeOptionsBuilder, HFClient, HFClientConfigBuilder,
+    },
     llm::{
         openai::{
-            deepseek::Deepseek,
+            deepseek::{Deepseek, DeepseekBackend},
             gpt_3_5::{GPT3_5OptionsBuilder, GPT3_5},
             prompt::PROMPT,
         },
-        LLMQueryRequest, LLM,
+        LLMQueryRequest, LLMUsage, LLM,
     },
+    utils::{apply_serde_rename_rule, normalize_route_path, path_captures},
 };
-use anyhow::{anyhow, bail, Context};
+use anyhow::{anyhow, Context};
 use async_trait::async_trait;
+use cache::{RouterCache, CACHE_DIR};
+use chunks::{handler_chunks, router_chunks};
+use closures::{route_closures, RouteClosure};
+use defaults::{query_struct_names, serde_field_defaults, serde_field_names, unwrap_or_defaults};
 use derive_builder::Builder;
-use prompts::{BODY_EXTRACT_PROMPT, BODY_OUTER_EXTRACT_PROMPT};
+use docs::{
+    extract_handler_docs, find_handler_line, handler_is_public, handler_source, module_docs,
+};
+use enums::unit_enum_variants;
+use files::FileCache;
+use futures::{stream, StreamExt, TryStreamExt};
+use impls::{find_impl_file, TraitImpls};
+use method_router::method_router_handlers;
+use prompts::Prompts;
+use responses::{error_statuses, error_type, success_status};
 use serde::Deserialize;
 use serde_json::Value;
+use services::{route_services, RouteService};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Display,
     fs::read_to_string,
     future::Future,
     path::{Path, PathBuf},
     pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
+use tokio::sync::watch;
+use url::Url;
+mod cache;
+mod chunks;
+mod closures;
+mod defaults;
+mod docs;
+mod enums;
+mod files;
+mod impls;
+mod method_router;
 mod prompts;
+mod responses;
+mod services;
 
 // const AXUM_ROUTER_CREATION_SIGNATURE: &'static str = "Router::new()";
 
-#[derive(Deserialize, Clone, Debug)]
-enum ImportPath {
-    Local(PathBuf),
-    External(String),
-    Std,
-    Unknown,
-}
-
-impl Display for ImportPath {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            Self::Local(path) => path.to_str().unwrap_or("invalid path"),
-            Self::External(s) => s.as_str(),
-            Self::Std => "std",
-            Self::Unknown => "unknown",
-        };
-
-        write!(f, "{}", s)
-    }
-}
-
 fn generate_file_search_query(
     file_content: &str,
     entry_fn: &str,
@@ -72,126 +90,1140 @@ FILE_CONTENT:
     )
 }
 
+/// How much the generator prints while running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// nothing at all
+    Quiet,
+    /// progress lines only
+    #[default]
+    Normal,
+    /// progress plus the call tracing
+    Verbose,
+}
+
+/// Deepest call chain followed before the traversal gives up on a branch, unless
+/// a lower cap is set with `Logger::with_max_depth`
+const MAX_LOGGER_DEPTH: usize = 100;
+
+#[derive(Clone)]
 pub struct Logger {
     level: usize,
+    /// warnings shared by every logger levelled up from the same root
+    warnings: Arc<Mutex<Vec<Warning>>>,
+    verbosity: Verbosity,
+    max_depth: usize,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Logger {
     pub fn new() -> Self {
-        Self { level: 0 }
+        Self {
+            level: 0,
+            warnings: Arc::new(Mutex::new(Vec::new())),
+            verbosity: Verbosity::default(),
+            max_depth: MAX_LOGGER_DEPTH,
+        }
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
     }
 
-    pub fn level_up(&self) -> Self {
-        if self.level >= 100 {
-            panic!("stop here")
+    /// Caps how many levels deep `level_up` goes, never past `MAX_LOGGER_DEPTH`
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth.min(MAX_LOGGER_DEPTH);
+        self
+    }
+
+    /// Returns a logger one level deeper, or `None` (after recording a warning) once
+    /// the call chain is too deep to keep following
+    pub fn level_up(&self) -> Option<Self> {
+        if self.level >= self.max_depth {
+            self.warn(format!(
+                "call chain is deeper than {} levels, not following it further",
+                self.max_depth
+            ));
+            return None;
         }
 
-        Self {
+        Some(Self {
             level: self.level + 1,
-        }
+            warnings: self.warnings.clone(),
+            verbosity: self.verbosity,
+            max_depth: self.max_depth,
+        })
     }
 
+    /// Debug tracing, only shown in verbose mode. Goes to stderr so stdout can be piped.
     pub fn log<S: ToString>(&self, s: S) {
-        let indent = " ".repeat(self.level * 2);
-        println!("{}{}", indent, s.to_string())
+        if self.verbosity >= Verbosity::Verbose {
+            let indent = " ".repeat(self.level * 2);
+            eprintln!("{}{}", indent, s.to_string())
+        }
+    }
+
+    /// Reports the current phase of the run, hidden in quiet mode
+    pub fn progress<S: ToString>(&self, s: S) {
+        if self.verbosity >= Verbosity::Normal {
+            eprintln!("[docgen] {}", s.to_string())
+        }
+    }
+
+    /// Logs `s` and records it so it can be reported once generation is done
+    pub fn warn<S: ToString>(&self, s: S) {
+        self.record_warning(None, s.to_string());
+    }
+
+    /// Like `warn`, but ties the warning to the route at `route_path`
+    pub fn warn_route<S: ToString>(&self, route_path: &str, s: S) {
+        self.record_warning(Some(route_path.to_owned()), s.to_string());
+    }
+
+    fn record_warning(&self, route: Option<String>, message: String) {
+        let warning = Warning { route, message };
+        self.log(format!("warning: {warning}"));
+        self.warnings
+            .lock()
+            .expect("logger warnings lock poisoned")
+            .push(warning);
+    }
+
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.warnings
+            .lock()
+            .expect("logger warnings lock poisoned")
+            .clone()
+    }
+}
+
+/// The names a file's `use` declarations bring into scope, used to expand the short paths
+/// the LLM copies out of the code (`ch::get_all` after `use crate::controllers::channels as ch;`)
+#[derive(Default, Debug)]
+struct UseAliases {
+    /// local name to the full path it stands for
+    names: HashMap<String, Vec<String>>,
+    /// modules glob imported with `::*`
+    globs: Vec<Vec<String>>,
+}
+
+impl UseAliases {
+    fn from_file(file_content: &str) -> Self {
+        let mut aliases = Self::default();
+        let Ok(file) = syn::parse_file(file_content) else {
+            return aliases;
+        };
+
+        for item in &file.items {
+            if let syn::Item::Use(item_use) = item {
+                aliases.collect(&item_use.tree, &mut Vec::new());
+            }
+        }
+
+        aliases
+    }
+
+    fn collect(&mut self, tree: &syn::UseTree, prefix: &mut Vec<String>) {
+        match tree {
+            syn::UseTree::Path(path) => {
+                prefix.push(path.ident.to_string());
+                self.collect(&path.tree, prefix);
+                prefix.pop();
+            }
+            syn::UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.collect(tree, prefix);
+                }
+            }
+            // `use a::b::{self}` names `b` itself
+            syn::UseTree::Name(name) if name.ident == "self" => {
+                if let Some(last) = prefix.last() {
+                    self.names.insert(last.clone(), prefix.clone());
+                }
+            }
+            syn::UseTree::Name(name) => {
+                let mut path = prefix.clone();
+                path.push(name.ident.to_string());
+                self.names.insert(name.ident.to_string(), path);
+            }
+            syn::UseTree::Rename(rename) => {
+                let mut path = prefix.clone();
+                if rename.ident != "self" {
+                    path.push(rename.ident.to_string());
+                }
+                self.names.insert(rename.rename.to_string(), path);
+            }
+            syn::UseTree::Glob(_) => self.globs.push(prefix.clone()),
+        }
+    }
+
+    /// Full paths `import` could stand for, most likely first. Paths that already start
+    /// at a crate root are returned unchanged.
+    fn expand(&self, import: &str) -> Vec<String> {
+        let segments = import.split("::").collect::<Vec<&str>>();
+        let Some(first) = segments.first() else {
+            return vec![];
+        };
+        if matches!(*first, "crate" | "self" | "super") {
+            return vec![import.to_owned()];
+        }
+
+        let mut candidates = Vec::new();
+        if let Some(path) = self.names.get(*first) {
+            let mut expanded = path.clone();
+            expanded.extend(segments[1..].iter().map(|s| s.to_string()));
+            candidates.push(expanded.join("::"));
+        }
+        for glob in &self.globs {
+            candidates.push(format!("{}::{}", glob.join("::"), import));
+        }
+        candidates.push(import.to_owned());
+
+        candidates
     }
 }
 
-pub fn resolve_import_module_path(
-    segments: &[&str],
+/// Resolves an import written in `file_content`, trying what its short path expands to
+/// through the file's `use` aliases and globs before the path itself. A bare name, e.g.
+/// `create` after `use crate::dto::*; use crate::handlers::*;`, resolves to the module
+/// actually defining it rather than the first glob whose module exists.
+fn resolve_import_in_file(
+    import: &str,
+    file_content: &str,
+    manifest: &Manifest,
     base_dir: &Path,
-    crate_name: &str,
-) -> Option<PathBuf> {
-    let Some(first) = segments.first() else {
-        return None;
-    };
+    logger: &Logger,
+) -> ImportKind {
+    let aliases = UseAliases::from_file(file_content);
+    let bare = !import.contains("::");
+    let mut first_local = None;
+    for candidate in aliases.expand(import) {
+        let Ok(ImportKind::Local(file)) = imports::resolve(&candidate, manifest, base_dir) else {
+            continue;
+        };
+        let symbol = candidate.rsplit("::").next().unwrap_or(&candidate);
+        if !bare || follow_reexports(&file, symbol, manifest, 0).is_some() {
+            return ImportKind::Local(file);
+        }
+        first_local.get_or_insert(file);
+    }
 
-    let (mut module_dir, skip_segment) = match *first {
-        first if first == crate_name || first == "crate" => {
-            // src directory
-            let dir = base_dir
-                .ancestors()
-                .find(|d| d.join("src").exists())
-                .map(|d| d.join("src"))?;
-            (dir, 1)
-        }
-        "self" => (base_dir.to_path_buf(), 1),
-        "super" => (base_dir.parent()?.to_path_buf(), 1),
-        _ => return None,
+    match first_local {
+        Some(file) => ImportKind::Local(file),
+        None => resolve_import_or_unknown(import, manifest, base_dir, logger),
+    }
+}
+
+/// Strips module paths and generics from a rust type, e.g. `chrono::DateTime<Utc>` -> `DateTime`
+/// and `Router::<AppState>` -> `Router`
+fn base_type_name(rust_type: &str) -> &str {
+    let rust_type = rust_type.split('<').next().unwrap_or(rust_type).trim();
+    let rust_type = rust_type.trim_end_matches("::");
+    rust_type.rsplit("::").next().unwrap_or(rust_type)
+}
+
+/// Whether `path` names axum's `Router`, however its state is written, e.g. `axum::Router`,
+/// `axum::routing::Router<AppState>` or `axum::Router::<AppState>`
+fn is_axum_router(path: &str) -> bool {
+    path.trim().starts_with("axum::") && base_type_name(path) == "Router"
+}
+
+/// Picks the function route discovery should start from: `main` when there is one,
+/// otherwise the first function (or method) returning an axum `Router`
+fn detect_entry_fn(file_content: &str) -> Option<(String, Option<String>)> {
+    let file = syn::parse_file(file_content).ok()?;
+
+    let returns_router = |sig: &syn::Signature| match &sig.output {
+        syn::ReturnType::Type(_, ty) => match ty.as_ref() {
+            syn::Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|seg| seg.ident == "Router"),
+            _ => false,
+        },
+        syn::ReturnType::Default => false,
     };
 
-    for seg in &segments[skip_segment..segments.len() - 1] {
-        module_dir = module_dir.join(seg);
+    let has_main = file
+        .items
+        .iter()
+        .any(|item| matches!(item, syn::Item::Fn(f) if f.sig.ident == "main"));
+    if has_main {
+        return Some(("main".to_owned(), None));
     }
 
-    let module = segments.last()?;
-    let file_rs = module_dir.join(format!("{}.rs", module));
-    let mod_rs = module_dir.join(module).join("mod.rs");
+    file.items.iter().find_map(|item| match item {
+        syn::Item::Fn(f) if returns_router(&f.sig) => Some((f.sig.ident.to_string(), None)),
+        syn::Item::Impl(i) => {
+            let syn::Type::Path(self_ty) = i.self_ty.as_ref() else {
+                return None;
+            };
+            let struct_name = self_ty.path.segments.last()?.ident.to_string();
 
-    if file_rs.exists() {
-        Some(file_rs)
-    } else if mod_rs.exists() {
-        Some(mod_rs)
-    } else {
-        None
+            i.items.iter().find_map(|item| match item {
+                syn::ImplItem::Fn(f) if returns_router(&f.sig) => {
+                    Some((f.sig.ident.to_string(), Some(struct_name.clone())))
+                }
+                _ => None,
+            })
+        }
+        _ => None,
+    })
+}
+
+/// How many levels of nested structs are expanded in a body schema
+const MAX_BODY_DEPTH: usize = 4;
+
+/// Maps the type reported for a body field, either a JSON-ish name (`Number`) or a rust
+/// type (`u32`, `Option<bool>`), to its IR property type. Anything else is a nested object.
+fn prop_type(field_type: &str) -> PropType {
+    let field_type = field_type
+        .strip_prefix("Option<")
+        .and_then(|t| t.strip_suffix('>'))
+        .unwrap_or(field_type)
+        .trim();
+
+    match field_type {
+        "String" | "&str" | "str" | "char" => PropType::String,
+        "Boolean" | "bool" => PropType::Boolean,
+        "Number" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32"
+        | "u64" | "u128" | "usize" | "f32" | "f64" => PropType::Number,
+        _ => PropType::Object,
     }
 }
 
-fn resolve_import(import: &str, package_name: &str, base_dir: &Path) -> anyhow::Result<ImportPath> {
-    let path_segments = import.split("::").collect::<Vec<&str>>();
-    // println!("path_segments={:#?}", path_segments);
-    if let Some(first) = path_segments.first() {
-        match *first {
-            "std" => return Ok(ImportPath::Std),
-            "crate" | "self" | "super" => {
-                let path = resolve_import_module_path(
-                    &path_segments[..&path_segments.len() - 1],
-                    base_dir,
-                    package_name,
-                )
-                .ok_or(anyhow!(format!(
-                    "unable to resolve import module path for {}",
-                    import
-                )))?;
-                return Ok(ImportPath::Local(path));
-            }
-            first if first == package_name => {
-                let path = resolve_import_module_path(
-                    &path_segments[..&path_segments.len() - 1],
-                    base_dir,
-                    package_name,
-                )
-                .ok_or(anyhow!(
-                    "unable to resolve import module path for {}",
-                    import
-                ))?;
-                return Ok(ImportPath::Local(path));
+/// Maps the rust type of a path/query parameter to its IR data type
+fn param_data_type(rust_type: &str) -> ir::ParamDataType {
+    match base_type_name(rust_type) {
+        "&str" | "String" | "Uuid" | "DateTime" | "NaiveDateTime" | "NaiveDate" => {
+            ir::ParamDataType::String
+        }
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => ir::ParamDataType::Integer,
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => ir::ParamDataType::UnsignedInteger,
+        "f32" | "f64" => ir::ParamDataType::Float,
+        _ => ir::ParamDataType::Unknown,
+    }
+}
+
+/// Axum extractors that pull from request state rather than the request itself,
+/// and so are never documented as parameters
+const FRAMEWORK_EXTRACTORS: [&str; 4] = ["State", "Extension", "ConnectInfo", "DatabaseConnection"];
+
+/// A handler parameter as reported by the LLM
+#[derive(Deserialize, Debug)]
+struct IRParam {
+    param_type: String,
+    name: String,
+    data_type: String,
+    description: String,
+    #[serde(default)]
+    example: Option<serde_json::Value>,
+}
+
+fn is_framework_extractor(param: &IRParam) -> bool {
+    FRAMEWORK_EXTRACTORS.iter().any(|extractor| {
+        base_type_name(&param.data_type) == *extractor
+            || param.param_type.eq_ignore_ascii_case(extractor)
+    })
+}
+
+/// Splits the top level of a comma separated type list, leaving commas nested in
+/// generics or tuples alone
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in list.chars() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_owned());
+                current.clear();
+                continue;
             }
             _ => {}
         }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_owned());
     }
+    parts
+}
+
+/// Splits path parameters the LLM reported as a whole tuple extractor, e.g.
+/// `Path<(u32, String)>`, into one parameter per tuple element
+fn split_tuple_path_params(params: Vec<IRParam>) -> Vec<IRParam> {
+    params
+        .into_iter()
+        .flat_map(|p| {
+            let data_type = p.data_type.trim();
+            let data_type = data_type
+                .strip_prefix("Path<")
+                .and_then(|t| t.strip_suffix('>'))
+                .unwrap_or(data_type)
+                .trim();
+            let elements = match data_type
+                .strip_prefix('(')
+                .and_then(|t| t.strip_suffix(')'))
+            {
+                Some(inner) if p.param_type == "path" => split_top_level(inner),
+                _ => return vec![p],
+            };
+
+            let name = p.name.trim().trim_start_matches('(').trim_end_matches(')');
+            let names = split_top_level(name);
+            let named = names.len() == elements.len();
+            elements
+                .into_iter()
+                .enumerate()
+                .map(|(i, data_type)| IRParam {
+                    param_type: p.param_type.clone(),
+                    name: match named {
+                        true => names[i].clone(),
+                        false => format!("param{}", i + 1),
+                    },
+                    data_type,
+                    description: p.description.clone(),
+                    // a tuple's example is an array of its elements' examples
+                    example: p.example.as_ref().and_then(|e| e.get(i)).cloned(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Joins the prefix a router is nested under with one of its route paths the way
+/// `Router::nest` does, so `/api` and `/` give `/api`
+fn join_route_path(prefix: &str, path: &str) -> String {
+    normalize_route_path(&format!("{prefix}/{path}"))
+}
+
+/// The code a router is read from: the function building it when the router is named by
+/// a merge or a nest, so that routers built in one file aren't read as each other's routes,
+/// or the whole file for the entry router and for functions that can't be found in it
+fn router_source(file_content: &str, router_fn: Option<&str>) -> String {
+    router_fn
+        .and_then(|router_fn| {
+            handler_source(file_content, router_fn).or_else(|| {
+                let (_, name) = router_fn.rsplit_once("::")?;
+                handler_source(file_content, name)
+            })
+        })
+        .unwrap_or_else(|| file_content.to_owned())
+}
+
+/// Names of the `:segment` and `*wildcard` captures in an axum route path, in order
+fn path_segment_names(route_path: &str) -> Vec<&str> {
+    path_captures(route_path)
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Renames a handler's path parameters after the captures in `route_path` when they
+/// don't already match, e.g. for tuple extractors whose names come from a destructuring
+/// pattern. Tuple elements are deserialized positionally, so they are aligned in order.
+fn align_path_params(parameters: &[Parameter], route_path: &str) -> Vec<Parameter> {
+    let segments = path_segment_names(route_path);
+    let mut parameters = parameters.to_vec();
+    let mut path_params = parameters
+        .iter_mut()
+        .filter(|p| matches!(p.param_type, ir::ParamType::Path))
+        .collect::<Vec<_>>();
+
+    let matches_segments = path_params
+        .iter()
+        .all(|p| segments.contains(&p.name.as_str()));
+    if !matches_segments && path_params.len() == segments.len() {
+        for (param, segment) in path_params.iter_mut().zip(segments) {
+            param.name = segment.to_owned();
+        }
+    }
+    parameters
+}
+
+/// Parameters of a closure handler, read from its typed arguments, e.g. `Path(id): Path<u32>`.
+/// Arguments without a request extractor type are left for `build_parameters` to report.
+fn closure_params(arguments: &[(String, String)]) -> Vec<IRParam> {
+    arguments
+        .iter()
+        .map(|(pattern, rust_type)| {
+            let extractor = base_type_name(rust_type);
+            let param_type = match extractor {
+                "Path" => "path",
+                "Query" => "query",
+                "TypedHeader" => "header",
+                _ => extractor,
+            };
+            let inner_type = rust_type
+                .split_once('<')
+                .and_then(|(_, inner)| inner.strip_suffix('>'))
+                .unwrap_or(rust_type);
+            // `Path(id)` binds `id`, a bare `id` binds the whole extractor
+            let name = pattern
+                .split_once('(')
+                .and_then(|(_, inner)| inner.strip_suffix(')'))
+                .unwrap_or(pattern);
+
+            IRParam {
+                param_type: param_type.to_owned(),
+                name: name.trim().to_owned(),
+                data_type: inner_type.trim().to_owned(),
+                description: String::new(),
+                example: None,
+            }
+        })
+        .collect()
+}
+
+/// Synthetic operationId for a closure handler, e.g. `get_items_id` for `GET /items/:id`
+fn closure_operation_id(method: &HTTPMethod, path: &str) -> String {
+    let segments = path
+        .split('/')
+        .map(|segment| {
+            segment
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect::<String>()
+                .trim_matches('_')
+                .to_owned()
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<String>>();
+
+    match segments.is_empty() {
+        true => format!("{}_root", method.as_str().to_lowercase()),
+        false => format!("{}_{}", method.as_str().to_lowercase(), segments.join("_")),
+    }
+}
+
+/// Splits a `Vec` or `Option` wrapper off the type a body is read into, e.g. `Vec<ItemDto>`
+/// is an array of `ItemDto`. `wrapper` is the wrapper reported separately, if any.
+fn body_shape(identifier: &str, wrapper: Option<&str>) -> (BodyShape, String) {
+    let identifier = identifier.trim();
+    let identifier = identifier
+        .strip_prefix("Json<")
+        .and_then(|inner| inner.strip_suffix('>'))
+        .unwrap_or(identifier)
+        .trim();
+
+    for (prefix, shape) in [("Vec<", BodyShape::Array), ("Option<", BodyShape::Nullable)] {
+        if let Some(inner) = identifier
+            .strip_prefix(prefix)
+            .and_then(|inner| inner.strip_suffix('>'))
+        {
+            return (shape, inner.trim().to_owned());
+        }
+    }
+
+    let shape = match wrapper.map(str::trim) {
+        Some("Vec") | Some("array") => BodyShape::Array,
+        Some("Option") => BodyShape::Nullable,
+        _ => BodyShape::Single,
+    };
+    (shape, identifier.to_owned())
+}
+
+/// Reads an item of the handler's file with `read`, or else of the file its `type_path`
+/// is imported from. Relative imports resolve against the handler's own module, e.g.
+/// `super::models` in `src/handlers/orders.rs` is `src/handlers/models.rs`.
+fn read_local_or_imported<T>(
+    type_path: &str,
+    handler_file: &Path,
+    file_content: &str,
+    manifest: &Manifest,
+    files: &FileCache,
+    read: impl Fn(&str) -> Option<T>,
+) -> Option<T> {
+    read(file_content).or_else(|| {
+        let module_dir = imports::module_dir_of(handler_file)?;
+        UseAliases::from_file(file_content)
+            .expand(type_path)
+            .iter()
+            .find_map(
+                |candidate| match imports::resolve(candidate, manifest, &module_dir) {
+                    Ok(ImportKind::Local(file)) => read(&files.read(&file).ok()?),
+                    _ => None,
+                },
+            )
+    })
+}
+
+/// Variants of the unit enum a parameter of type `data_type` is parsed into, looked up in the
+/// handler's file first and then wherever the file imports the type from
+fn find_unit_enum(
+    data_type: &str,
+    handler_file: &Path,
+    file_content: &str,
+    manifest: &Manifest,
+    files: &FileCache,
+) -> Option<Vec<String>> {
+    let type_path = data_type.split('<').next().unwrap_or(data_type).trim();
+    let name = base_type_name(type_path);
+
+    read_local_or_imported(
+        type_path,
+        handler_file,
+        file_content,
+        manifest,
+        files,
+        |content| unit_enum_variants(content, name),
+    )
+}
+
+/// The responses of the error type `handler` returns in a `Result`, from the `IntoResponse`
+/// impl of the type, which is looked up in the handler's file first and then wherever the
+/// file imports it from
+fn find_error_responses(
+    handler: &str,
+    handler_file: &Path,
+    file_content: &str,
+    manifest: &Manifest,
+    files: &FileCache,
+) -> Vec<ErrorResponse> {
+    let Some(type_path) = error_type(file_content, handler) else {
+        return vec![];
+    };
+    let name = base_type_name(&type_path);
+
+    read_local_or_imported(
+        &type_path,
+        handler_file,
+        file_content,
+        manifest,
+        files,
+        |content| error_statuses(content, name),
+    )
+    .unwrap_or_default()
+}
+
+/// Default values of the query parameters of `handler`, from the `.unwrap_or(..)` calls in
+/// its body and the `#[serde(default = "..")]` fields of its `Query` struct, which is looked
+/// up in the handler's file first and then wherever the file imports it from
+fn find_query_defaults(
+    handler: &str,
+    handler_file: &Path,
+    file_content: &str,
+    manifest: &Manifest,
+    files: &FileCache,
+) -> HashMap<String, String> {
+    let mut field_names = HashMap::new();
+    let mut field_defaults = HashMap::new();
+    for struct_name in query_struct_names(file_content, handler) {
+        let read_struct = |read: fn(&str, &str) -> Option<HashMap<String, String>>| {
+            read_local_or_imported(
+                &struct_name,
+                handler_file,
+                file_content,
+                manifest,
+                files,
+                |content| read(content, &struct_name),
+            )
+        };
+        field_names.extend(read_struct(serde_field_names).unwrap_or_default());
+        field_defaults.extend(read_struct(serde_field_defaults).unwrap_or_default());
+    }
+
+    let mut defaults = unwrap_or_defaults(file_content, handler, &field_names);
+    defaults.extend(field_defaults);
+    defaults
+}
+
+/// Maps the parameters reported for a handler to IR parameters, dropping framework
+/// extractors the LLM sometimes reports despite being told not to. Parameters of types
+/// `find_enum` knows the variants of are documented as constrained strings.
+fn build_parameters(
+    params: Vec<IRParam>,
+    warnings: &mut Vec<String>,
+    find_enum: impl Fn(&str) -> Option<Vec<String>>,
+) -> Vec<Parameter> {
+    split_tuple_path_params(params)
+        .into_iter()
+        .filter(|p| !is_framework_extractor(p))
+        .map(|p| {
+            let mut data_type = param_data_type(&p.data_type);
+            let mut enum_values = vec![];
+            if let ir::ParamDataType::Unknown = data_type {
+                if let Some(variants) = find_enum(&p.data_type) {
+                    data_type = ir::ParamDataType::String;
+                    enum_values = variants;
+                }
+            }
+
+            let param_type = match p.param_type.as_str() {
+                "path" => ir::ParamType::Path,
+                "query" => ir::ParamType::Query,
+                "header" => ir::ParamType::Header,
+                _ => ir::ParamType::Unknown,
+            };
+
+            if let ir::ParamType::Unknown = param_type {
+                warnings.push(format!(
+                    "unknown param type `{}` for parameter {}",
+                    p.param_type, p.name
+                ));
+            }
+
+            if let ir::ParamDataType::Unknown = data_type {
+                warnings.push(format!(
+                    "unknown data type `{}` for parameter {}",
+                    p.data_type, p.name
+                ));
+            }
+
+            Parameter {
+                format: param_format(&p.data_type),
+                name: p.name,
+                description: p.description,
+                data_type,
+                param_type,
+                enum_values,
+                example: p.example,
+                default: None,
+            }
+        })
+        .collect()
+}
+
+/// The module path of a source file under `src_dir`, e.g. `handlers::channels` for
+/// `src/handlers/channels.rs` or `src/handlers/channels/mod.rs`
+fn module_path(file: &Path, src_dir: &Path) -> String {
+    let relative = file
+        .strip_prefix(src_dir)
+        .unwrap_or(file)
+        .with_extension("");
+    let mut segments = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    let last = segments.last().map(String::as_str);
+    if last == Some("mod") || (segments.len() == 1 && matches!(last, Some("main" | "lib"))) {
+        segments.pop();
+    }
+
+    match segments.is_empty() {
+        true => "crate".to_owned(),
+        false => segments.join("::"),
+    }
+}
+
+/// The tags of `routes` named after a module their handlers are defined in, e.g. `channels`
+/// for `handlers::channels::create`, described by that module's doc comment. Tags without
+/// a matching documented module are left out.
+fn module_tags(routes: &[Route], src_dir: &Path, files: &FileCache) -> Vec<ir::Tag> {
+    let mut tags: BTreeMap<&str, Option<String>> = BTreeMap::new();
+    for route in routes {
+        let Some(handler) = &route.handler else {
+            continue;
+        };
+        let segments = handler.module.split("::").collect::<Vec<_>>();
+
+        for tag in &route.docs.tags {
+            if tags.get(tag.as_str()).is_some_and(Option::is_some) {
+                continue;
+            }
+            let Some(position) = segments.iter().rposition(|segment| segment == tag) else {
+                continue;
+            };
+
+            let module = src_dir.join(segments[..=position].join("/"));
+            let description = [module.with_extension("rs"), module.join("mod.rs")]
+                .iter()
+                .find_map(|file| files.read(file).ok())
+                .and_then(|content| module_docs(&content));
+            tags.insert(tag, description);
+        }
+    }
+
+    tags.into_iter()
+        .filter_map(|(name, description)| {
+            description.map(|description| ir::Tag {
+                name: name.to_owned(),
+                description: Some(description),
+            })
+        })
+        .collect()
+}
+
+/// Sets the description of every named handler without one in its doc comment to a
+/// HuggingFace summary of its source. The client is built from the `HF_TOKEN` environment
+/// variable, and a handler that can't be summarized is left undescribed with a warning.
+async fn describe_handlers(
+    routes: &mut [Route],
+    proxy: Option<&Url>,
+    files: &FileCache,
+    logger: &Logger,
+) -> anyhow::Result<()> {
+    let access_token =
+        std::env::var("HF_TOKEN").context("HF_TOKEN must be set to summarize handlers")?;
+    let client = HFClient::new(
+        HFClientConfigBuilder::default()
+            .access_token(access_token)
+            .proxy(proxy.map(Url::to_string))
+            .build()?,
+    )?;
+
+    for route in routes.iter_mut() {
+        let (Some(handler), Some(location)) = (&route.handler, &route.source) else {
+            continue;
+        };
+        if route.docs.description.is_some() {
+            continue;
+        }
+
+        let content = files.read(&location.file).unwrap_or_default();
+        let Some(code) = handler_source(&content, &handler.name) else {
+            logger.warn_route(
+                &route.path,
+                format!("could not find the source of {} to summarize", handler.name),
+            );
+            continue;
+        };
+
+        logger.progress(format!("summarizing {} {}", route.method, route.path));
+        let summary = client
+            .summarize_code(SummarizeCodeOptionsBuilder::default().code(code).build()?)
+            .await;
+        match summary {
+            Ok(summary) => route.docs.description = Some(summary.summary),
+            Err(e) => logger.warn_route(
+                &route.path,
+                format!("could not summarize {}: {e}", handler.name),
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps the request content type reported by the LLM to the IR body type
+fn body_type(content_type: &str) -> Option<BodyType> {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or(content_type)
+        .trim();
+    match mime.to_lowercase().as_str() {
+        "application/json" => Some(BodyType::Json),
+        "multipart/form-data" => Some(BodyType::Multipart),
+        "application/x-www-form-urlencoded" => Some(BodyType::Form),
+        "application/octet-stream" => Some(BodyType::Binary),
+        "application/xml" | "text/xml" => Some(BodyType::Xml),
+        _ => None,
+    }
+}
+
+/// OpenAPI `format` hint for the rust type of a parameter, so generated clients use native types
+fn param_format(rust_type: &str) -> Option<String> {
+    let format = match base_type_name(rust_type) {
+        "Uuid" => "uuid",
+        "DateTime" | "NaiveDateTime" => "date-time",
+        "NaiveDate" => "date",
+        "i32" | "u32" => "int32",
+        "i64" | "u64" => "int64",
+        "f32" => "float",
+        "f64" => "double",
+        _ => return None,
+    };
+
+    Some(format.to_owned())
+}
+
+/// Like `imports::resolve`, but degrades to `ImportKind::Unknown` with a warning
+/// so a single unresolvable import doesn't abort the whole generation
+fn resolve_import_or_unknown(
+    import: &str,
+    manifest: &Manifest,
+    base_dir: &Path,
+    logger: &Logger,
+) -> ImportKind {
+    match imports::resolve(import, manifest, base_dir) {
+        Ok(import_path) => import_path,
+        Err(e) => {
+            logger.warn(format!("{e}, skipping it"));
+            ImportKind::Unknown
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct FunctionCallNode {
+    caller: Option<String>,
+    callee: String,
+    associated_struct: Option<String>,
+    module: Option<String>,
+    import_path: ImportKind,
+    arguments: Vec<Argument>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+enum Argument {
+    Str(String),
+    Function {
+        identifier: String,
+        associated_struct: Option<String>,
+        module: String,
+        import_path: ImportKind,
+    },
+    FunctionCall(FunctionCallNode),
+    Other {
+        dtype: String,
+        value: String,
+    },
+}
+
+#[derive(Debug)]
+struct RouteHandler {
+    pub identifier: String,
+    pub method_of: Option<String>,
+    pub import_path: PathBuf,
+    /// set for handlers written inline in the `.route()` call
+    pub closure: Option<RouteClosure>,
+    /// set for services mounted with `.route_service()` or `.nest_service()`
+    pub service: Option<RouteService>,
+}
+
+impl RouteHandler {
+    fn is_same(&self, other: &RouteHandler) -> bool {
+        self.identifier == other.identifier
+            && self.method_of == other.method_of
+            && self.import_path == other.import_path
+    }
+}
+
+impl Display for RouteHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.method_of {
+            Some(struct_name) => write!(f, "{struct_name}::{}", self.identifier)?,
+            None => write!(f, "{}", self.identifier)?,
+        }
+        write!(f, " in {}", self.import_path.display())
+    }
+}
+
+struct BasicRoute {
+    pub path: String,
+    pub method: HTTPMethod,
+    pub handler: RouteHandler,
+}
+
+/// Adds `route` unless an earlier one has its method and path. The same handler again is
+/// the same router reached from more than one place, but another handler would be dropped
+/// without notice, so that is warned about.
+fn add_unique_route(routes: &mut Vec<BasicRoute>, route: BasicRoute, logger: &Logger) {
+    let Some(kept) = routes
+        .iter()
+        .find(|r| r.method == route.method && r.path == route.path)
+    else {
+        routes.push(route);
+        return;
+    };
+
+    if !kept.handler.is_same(&route.handler) {
+        logger.warn_route(
+            &route.path,
+            format!(
+                "{} {} is registered by both {} and {}, only the first is documented",
+                route.method, route.path, kept.handler, route.handler
+            ),
+        );
+    }
+}
 
-    Ok(ImportPath::External(import.to_owned()))
+/// The crate router discovery walks through
+struct DiscoveredCrate<'a> {
+    base_dir: PathBuf,
+    manifest: &'a Manifest,
+    files: &'a FileCache,
+    /// scanned once, for `dispatch_trait_call`
+    trait_impls: TraitImpls,
+}
+
+/// Whether `node` is a call to axum's `Router::new()`, which makes the file it's made in
+/// one that defines routes. `Router::<AppState>::new()` may come back with its generics on
+/// either the module or the associated struct.
+fn constructs_axum_router(node: &FunctionCallNode) -> bool {
+    node.module.as_deref().is_some_and(is_axum_router)
+        && node.callee == "new"
+        && node
+            .associated_struct
+            .as_deref()
+            .is_some_and(|s| base_type_name(s) == "Router")
+}
+
+/// A call made through a trait declared in the crate, e.g. `feature.routes()` on a
+/// `Box<dyn Routable>`, can't be followed to a single function, so it's followed into
+/// every `impl` of the trait that defines the callee instead
+fn dispatch_trait_call(
+    node: FunctionCallNode,
+    trait_impls: &TraitImpls,
+    logger: &Logger,
+) -> Vec<FunctionCallNode> {
+    let (ImportKind::Local(_) | ImportKind::Unknown, Some(trait_name)) =
+        (&node.import_path, node.associated_struct.as_deref())
+    else {
+        return vec![node];
+    };
+    let impls = trait_impls.defining(trait_name, &node.callee);
+    if impls.is_empty() {
+        return vec![node];
+    }
+
+    logger.log(format!(
+        "=> {} dispatched to {}",
+        node.callee,
+        impls
+            .iter()
+            .map(|i| format!("<{} as {}>", i.self_ty, i.trait_name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    impls
+        .into_iter()
+        .map(|i| FunctionCallNode {
+            associated_struct: Some(i.self_ty.clone()),
+            import_path: ImportKind::Local(i.file.clone()),
+            ..node.clone()
+        })
+        .collect()
 }
 
 #[derive(Builder, Default, Debug)]
 #[builder(setter(into))]
 pub struct RustAxumGeneratorArgs {
     code_dir: PathBuf,
+    /// security scheme name recorded on routes whose handlers require auth
+    #[builder(default = "bearerAuth".to_owned())]
+    security_scheme: String,
+    /// maximum number of handler files analysed at the same time
+    #[builder(default = 4)]
+    concurrency: usize,
+    #[builder(default)]
+    verbosity: Verbosity,
+    /// function route discovery starts from, e.g. `app` or `Application::routes`.
+    /// Detected from the entry file when not given.
+    #[builder(default)]
+    entry_fn: Option<String>,
+    /// file the router is built in, relative to `code_dir`, read instead of locating it
+    /// from the entry function
+    #[builder(default)]
+    routes_file: Option<PathBuf>,
+    /// stop after route discovery and print what would be analysed
+    #[builder(default)]
+    dry_run: bool,
+    /// reuse the router location found by a previous run while its files are unchanged
+    #[builder(default = true)]
+    cache: bool,
+    /// directory of prompt files replacing the built-in extraction prompts
+    #[builder(default)]
+    prompt_dir: Option<PathBuf>,
+    /// how many calls deep router discovery follows the code, `MAX_LOGGER_DEPTH` when not given
+    #[builder(default)]
+    max_depth: Option<usize>,
+    /// ask the LLM for an example value of every parameter and body field
+    #[builder(default)]
+    examples: bool,
+    /// document services mounted with `route_service` or `nest_service`, e.g. static files
+    #[builder(default = true)]
+    services: bool,
+    /// describe handlers with a HuggingFace summary of their source, see `describe_handlers`
+    #[builder(default)]
+    handler_summaries: bool,
+    /// estimated tokens a single LLM call may read, larger router and handler files are
+    /// split across several calls
+    #[builder(default = 8000)]
+    max_tokens_per_call: usize,
+    /// document routes whose handlers aren't `pub`
+    #[builder(default = true)]
+    private_handlers: bool,
+    /// proxy the HuggingFace client of `describe_handlers` sends its requests through
+    #[builder(default)]
+    proxy: Option<Url>,
 }
 
 pub struct RustAxumGenerator {
     args: RustAxumGeneratorArgs,
+    logger: Logger,
+    /// routes built so far, kept so an interrupted run can still be flushed
+    partial_routes: Mutex<Vec<Route>>,
+    /// number of partial routes, bumped whenever more are added
+    partial_count: watch::Sender<usize>,
+    /// shared with the LLM backend of the run, so it's current even if the run is cut short
+    usage: Arc<Mutex<LLMUsage>>,
 }
 
 impl RustAxumGenerator {
+    /// Framework conventions, available without building a generator
+    pub fn info() -> FrameworkInfo {
+        FrameworkInfo {
+            name: "rust-axum",
+            default_entry: PathBuf::from("src/main.rs"),
+            router_signatures: vec!["Router::new()", ".route(", ".nest(", ".merge("],
+        }
+    }
+
+    fn add_partial_routes(&self, routes: &[Route]) {
+        let mut partial = self
+            .partial_routes
+            .lock()
+            .expect("partial routes lock poisoned");
+        partial.extend(routes.iter().cloned());
+        self.partial_count.send_replace(partial.len());
+    }
+
+    /// The file route discovery starts from in the codebase at `code_dir`, `src/main.rs`
+    /// or `src/lib.rs` for a library
+    pub fn entry_file(code_dir: &Path) -> PathBuf {
+        let main_rs = code_dir.join("src/main.rs");
+        let lib_rs = code_dir.join("src/lib.rs");
+        if !main_rs.exists() && lib_rs.exists() {
+            lib_rs
+        } else {
+            main_rs
+        }
+    }
+
     pub fn new(args: RustAxumGeneratorArgs) -> Self {
-        Self { args }
+        Self {
+            logger: Logger::new()
+                .with_verbosity(args.verbosity)
+                .with_max_depth(args.max_depth.unwrap_or(MAX_LOGGER_DEPTH)),
+            partial_routes: Mutex::new(Vec::new()),
+            partial_count: watch::Sender::new(0),
+            usage: Arc::new(Mutex::new(LLMUsage::default())),
+            args,
+        }
     }
 
     fn get_codebase_entry_file(&self) -> PathBuf {
         // might later move this as a generator param
-        self.args.code_dir.join("src/main.rs")
+        Self::entry_file(&self.args.code_dir)
+    }
+
+    /// The entry function as `(function, struct it's defined on)`
+    fn get_entry_fn(&self, entry_file: &Path) -> anyhow::Result<(String, Option<String>)> {
+        if let Some(entry_fn) = &self.args.entry_fn {
+            return Ok(match entry_fn.rsplit_once("::") {
+                Some((struct_name, method)) => (method.to_owned(), Some(struct_name.to_owned())),
+                None => (entry_fn.to_owned(), None),
+            });
+        }
+
+        let file_content = read_to_string(entry_file)
+            .context(format!("failed to read entry file {:?}", entry_file))?;
+        detect_entry_fn(&file_content).ok_or(anyhow!(
+            "couldn't find `main` or a function returning a Router in {:?}, pass one with --entry-fn",
+            entry_file
+        ))
     }
 
     // fn crawl_for_api_route_definitions(
@@ -238,10 +1270,14 @@ impl RustAxumGenerator {
 
 #[async_trait]
 impl Generator for RustAxumGenerator {
+    fn framework_info(&self) -> FrameworkInfo {
+        Self::info()
+    }
+
     /// Assumptions:
     /// there will always be a src/main.rs in the root directory of codebase
     /// the src/main.rs file will always contain a main function
-    async fn generate_ir(&self) -> anyhow::Result<ir::IR> {
+    async fn generate_ir(&self) -> Result<ir::IR, DocgenError> {
         let entry_file = self.get_codebase_entry_file();
         // let mut call_graph = CallGraph::try_new(&entry_file, EntryPoint::Func("main".to_owned()))?;
         // call_graph.build()?;
@@ -251,7 +1287,17 @@ impl Generator for RustAxumGenerator {
         //     .build()
         //     .expect("failed to build gpt options");
         // let mut llm = GPT3_5::new(llm_options);
-        let mut llm = Deepseek::new(&PROMPT);
+        // every conversation of the run goes through this one client
+        let rate_limit_logger = self.logger.clone();
+        let backend = DeepseekBackend::try_new()?
+            .with_usage(self.usage.clone())
+            .with_on_rate_limited(Arc::new(move |delay: Duration| {
+                rate_limit_logger.progress(format!(
+                    "rate limited, retrying in {:.1}s",
+                    delay.as_secs_f32()
+                ))
+            }));
+        let mut llm = backend.conversation(&PROMPT);
 
         #[derive(Deserialize, Debug, Clone)]
         struct IntermediateNodeRepr {
@@ -262,36 +1308,52 @@ impl Generator for RustAxumGenerator {
             arguments: Vec<IRArgumentRepr>,
         }
 
-        #[derive(Deserialize, Clone, Debug)]
-        struct FunctionCallNode {
-            caller: Option<String>,
-            callee: String,
-            associated_struct: Option<String>,
-            module: Option<String>,
-            import_path: ImportPath,
-            arguments: Vec<Argument>,
+        /// Identifies a function of the call chain by its file and name, e.g.
+        /// `("src/startup.rs", "Application::build")`. Functions outside the crate have none.
+        fn visit_key(node: &FunctionCallNode) -> Option<(PathBuf, String)> {
+            let ImportKind::Local(file_path) = &node.import_path else {
+                return None;
+            };
+            let function = match &node.associated_struct {
+                Some(struct_name) => format!("{struct_name}::{}", node.callee),
+                None => node.callee.clone(),
+            };
+            Some((file_path.clone(), function))
         }
 
-        #[derive(Deserialize, Debug, Clone)]
-        enum Argument {
-            Str(String),
-            Function {
-                identifier: String,
-                associated_struct: Option<String>,
-                module: String,
-                import_path: ImportPath,
-            },
-            FunctionCall(FunctionCallNode),
-            Other {
-                dtype: String,
-                value: String,
-            },
+        /// The struct whose impl defines the callee, resolving `Self` to the caller's struct
+        fn impl_struct(
+            node: &IntermediateNodeRepr,
+            parent_node: &FunctionCallNode,
+        ) -> Option<String> {
+            let self_struct = || {
+                node.module
+                    .as_deref()
+                    .and_then(|m| m.strip_prefix("Self::"))
+                    .map(str::to_owned)
+                    .or_else(|| parent_node.associated_struct.clone())
+            };
+
+            match node.associated_struct.as_deref() {
+                Some("Self") => self_struct(),
+                Some(struct_name) => Some(struct_name.to_owned()),
+                None if node
+                    .module
+                    .as_deref()
+                    .is_some_and(|m| m.starts_with("Self")) =>
+                {
+                    self_struct()
+                }
+                None => None,
+            }
         }
 
         fn from_ir_to_node(
             node: &IntermediateNodeRepr,
             parent_node: &FunctionCallNode,
             base_dir: &PathBuf,
+            manifest: &Manifest,
+            files: &FileCache,
             logger: &Logger,
         ) -> anyhow::Result<FunctionCallNode> {
             let module = node.module.clone();
@@ -299,14 +1361,29 @@ impl Generator for RustAxumGenerator {
                 if module.starts_with("Self") {
                     parent_node.import_path.clone()
                 } else {
-                    resolve_import(
-                        &module,
-                        "sabbatical_server", // TODO:  get from manifest
-                        base_dir.as_path(),
-                    )?
+                    resolve_import_or_unknown(&module, manifest, base_dir.as_path(), logger)
                 }
             } else {
-                ImportPath::Unknown
+                ImportKind::Unknown
+            };
+
+            // `Self::routes(..)` and `Application::routes(..)` live wherever the struct's impl
+            // does, which may not be the file the struct or its caller is in
+            let associated_struct = impl_struct(node, parent_node);
+            let import_path = match (&import_path, &associated_struct) {
+                (ImportKind::External(_) | ImportKind::Std, _) | (_, None) => import_path,
+                (_, Some(struct_name)) => {
+                    let mut preferred = Vec::new();
+                    for import_path in [&import_path, &parent_node.import_path] {
+                        if let ImportKind::Local(file) = import_path {
+                            preferred.push(file.as_path());
+                        }
+                    }
+                    match find_impl_file(base_dir, struct_name, &node.callee, &preferred, files) {
+                        Some(file) => ImportKind::Local(file),
+                        None => import_path,
+                    }
+                }
             };
 
             logger.log(format!("=> {} => {}", node.callee, import_path));
@@ -315,12 +1392,19 @@ impl Generator for RustAxumGenerator {
                 caller: node.caller.clone(),
                 callee: node.callee.clone(),
                 module: node.module.clone(),
-                associated_struct: node.associated_struct.clone(),
+                associated_struct,
                 import_path,
                 arguments: {
                     let mut args = Vec::new();
                     for arg in node.arguments.clone() {
-                        args.push(from_ir_arg_to_arg(&arg, &parent_node, &base_dir, &logger)?);
+                        args.push(from_ir_arg_to_arg(
+                            &arg,
+                            &parent_node,
+                            &base_dir,
+                            manifest,
+                            files,
+                            &logger,
+                        )?);
                     }
                     args
                 },
@@ -331,13 +1415,20 @@ impl Generator for RustAxumGenerator {
             ir: &IRArgumentRepr,
             parent_node: &FunctionCallNode,
             base_dir: &PathBuf,
+            manifest: &Manifest,
+            files: &FileCache,
             logger: &Logger,
         ) -> anyhow::Result<Argument> {
             Ok(match ir {
                 IRArgumentRepr::Str(s) => Argument::Str(s.to_owned()),
-                IRArgumentRepr::FunctionCall(node) => Argument::FunctionCall(
-                    from_ir_to_node(&node, parent_node, base_dir, logger).expect("invalid node"),
-                ),
+                IRArgumentRepr::FunctionCall(node) => Argument::FunctionCall(from_ir_to_node(
+                    &node,
+                    parent_node,
+                    base_dir,
+                    manifest,
+                    files,
+                    logger,
+                )?),
                 IRArgumentRepr::Function {
                     identifier,
                     associated_struct,
@@ -346,11 +1437,7 @@ impl Generator for RustAxumGenerator {
                     let import_path = if module.clone().starts_with("Self") {
                         parent_node.import_path.clone()
                     } else {
-                        resolve_import(
-                            &module,
-                            "sabbatical_server", // TODO:  get from manifest
-                            base_dir.as_path(),
-                        )?
+                        resolve_import_or_unknown(&module, manifest, base_dir.as_path(), logger)
                     };
 
                     Argument::Function {
@@ -438,15 +1525,22 @@ impl Generator for RustAxumGenerator {
             node: FunctionCallNode,
             llm: &'a mut Deepseek,
             logger: Logger,
-            base_dir: PathBuf,
+            krate: &'a DiscoveredCrate<'a>,
             mut route_list: &'b mut Vec<FunctionCallNode>,
+            visited: &'a mut HashSet<(PathBuf, String)>,
         ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>
         where
             'b: 'a,
         {
             Box::pin(async move {
+                // recursive functions would otherwise be followed until the depth cap
+                if visit_key(&node).is_some_and(|key| !visited.insert(key)) {
+                    logger.log(format!("{} was already followed", node.callee));
+                    return Ok(());
+                }
+
                 match &node.import_path {
-                    ImportPath::Local(file_path) => {
+                    ImportKind::Local(file_path) => {
                         let file = read_to_string(&file_path);
                         let file_content =
                             file.expect(&format!("failed to read file in path {:#?}", file_path));
@@ -460,57 +1554,93 @@ impl Generator for RustAxumGenerator {
                             ),
                         };
 
+                        // the files along the call chain are unrelated to each other
+                        llm.reset_history();
                         let response = llm.execute_query(query).await?;
 
                         let response = match serde_json::from_str::<Response>(&response.text) {
                             Ok(nodes) => nodes,
-                            Err(e) => bail!(format!(
-                                "llm returned unserializable string {e} \n\n{}",
-                                response.text,
-                            )),
+                            Err(e) => {
+                                return Err(DocgenError::InvalidLlmJson {
+                                    message: e.to_string(),
+                                    raw: response.text,
+                                }
+                                .into())
+                            }
                         };
 
                         if node.callee == "routes" {
                             // println!("fcalls = {:#?}", response.fcalls);
                         }
 
+                        let mut nodes = Vec::new();
                         for node_ir in response.fcalls {
-                            let node = from_ir_to_node(&node_ir, &node, &base_dir, &logger)?;
-                            if let ImportPath::External(path) = &node.import_path {
-                                if path.as_str() == "axum::Router" && node.callee.trim() == "route"
-                                {
+                            let node = from_ir_to_node(
+                                &node_ir,
+                                &node,
+                                &krate.base_dir,
+                                krate.manifest,
+                                krate.files,
+                                &logger,
+                            )?;
+                            nodes.extend(dispatch_trait_call(node, &krate.trait_impls, &logger));
+                        }
+
+                        for node in nodes {
+                            if let ImportKind::External(path) = &node.import_path {
+                                if is_axum_router(path) && node.callee.trim() == "route" {
                                     route_list.push(node.clone());
                                 }
                             }
 
+                            let Some(child_logger) = logger.level_up() else {
+                                continue;
+                            };
+
                             read_file_and_extract_nodes_from_entry_function(
                                 node,
                                 llm,
-                                logger.level_up(),
-                                base_dir.clone(),
+                                child_logger,
+                                krate,
                                 &mut route_list,
+                                visited,
                             )
                             .await?
                         }
                     }
-                    ImportPath::External(..) => {}
-                    ImportPath::Std => {}
-                    ImportPath::Unknown => {}
+                    ImportKind::External(..) => {}
+                    ImportKind::Std => {}
+                    ImportKind::Unknown => {}
                 };
 
                 Ok(())
             })
         }
 
-        fn find_routes_file<'a>(
+        /// Collects every file reachable from `node` that constructs an axum `Router`
+        fn find_routes_files<'a>(
             node: FunctionCallNode,
             llm: &'a mut Deepseek,
             logger: Logger,
-            base_dir: PathBuf,
-        ) -> Pin<Box<dyn Future<Output = anyhow::Result<PathBuf>> + Send + 'a>> {
+            krate: &'a DiscoveredCrate<'a>,
+            traversed: &'a mut Vec<PathBuf>,
+            visited: &'a mut HashSet<(PathBuf, String)>,
+        ) -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<PathBuf>>> + Send + 'a>> {
             Box::pin(async move {
+                // the routers found past an already followed function were returned from
+                // its first visit
+                if visit_key(&node).is_some_and(|key| !visited.insert(key)) {
+                    logger.log(format!("{} was already followed", node.callee));
+                    return Ok(vec![]);
+                }
+
+                let mut route_files: Vec<PathBuf> = Vec::new();
+
                 match &node.import_path {
-                    ImportPath::Local(file_path) => {
+                    ImportKind::Local(file_path) => {
+                        if !traversed.contains(file_path) {
+                            traversed.push(file_path.clone());
+                        }
                         let file = read_to_string(&file_path);
                         let file_content =
                             file.expect(&format!("failed to read file in path {:#?}", file_path));
@@ -524,149 +1654,493 @@ impl Generator for RustAxumGenerator {
                             ),
                         };
 
+                        // the files along the call chain are unrelated to each other
+                        llm.reset_history();
                         let response = llm.execute_query(query).await?;
 
                         let response = match serde_json::from_str::<Response>(&response.text) {
                             Ok(nodes) => nodes,
-                            Err(e) => bail!(format!(
-                                "llm returned unserializable string {e} \n\n{}",
-                                response.text,
-                            )),
+                            Err(e) => {
+                                return Err(DocgenError::InvalidLlmJson {
+                                    message: e.to_string(),
+                                    raw: response.text,
+                                }
+                                .into())
+                            }
                         };
 
+                        let mut nodes = Vec::new();
                         for node_ir in response.fcalls {
-                            let node = from_ir_to_node(&node_ir, &node, &base_dir, &logger)?;
-                            if node.module == Some("axum::Router".to_owned())
-                                && node.callee == "new".to_owned()
-                                && node.associated_struct == Some("Router".to_owned())
-                            {
-                                return Ok(file_path.clone());
+                            let node = from_ir_to_node(
+                                &node_ir,
+                                &node,
+                                &krate.base_dir,
+                                krate.manifest,
+                                krate.files,
+                                &logger,
+                            )?;
+                            nodes.extend(dispatch_trait_call(node, &krate.trait_impls, &logger));
+                        }
+
+                        for node in nodes {
+                            if constructs_axum_router(&node) {
+                                if !route_files.contains(file_path) {
+                                    route_files.push(file_path.clone());
+                                }
+                                continue;
                             }
 
-                            if let Ok(a) =
-                                find_routes_file(node, llm, logger.level_up(), base_dir.clone())
-                                    .await
+                            let Some(child_logger) = logger.level_up() else {
+                                continue;
+                            };
+
+                            if let Ok(files) = find_routes_files(
+                                node,
+                                llm,
+                                child_logger,
+                                krate,
+                                traversed,
+                                visited,
+                            )
+                            .await
                             {
-                                return Ok(a);
+                                for file in files {
+                                    if !route_files.contains(&file) {
+                                        route_files.push(file);
+                                    }
+                                }
                             }
                         }
                     }
-                    ImportPath::External(..) => {}
-                    ImportPath::Std => {}
-                    ImportPath::Unknown => {}
+                    ImportKind::External(..) => {}
+                    ImportKind::Std => {}
+                    ImportKind::Unknown => {}
                 };
 
-                bail!("couldnt retrieve route file")
+                Ok(route_files)
             })
         }
-        #[derive(Debug)]
-        pub struct RouteHandler {
-            pub identifier: String,
-            pub method_of: Option<String>,
-            pub import_path: PathBuf,
-        }
+        /// Reads the routes registered in `route_file`, or only in its `router_fn` when the
+        /// router is built by a named function, under `prefix`, following the routers it merges
+        /// or nests. Every sub router file read is recorded in `followed`, and `ancestors` holds
+        /// the files and functions of the routers currently being read.
+        #[allow(clippy::too_many_arguments)]
+        fn get_route_list_from_route_file<'a>(
+            route_file: &'a PathBuf,
+            router_fn: Option<String>,
+            prefix: String,
+            ancestors: Vec<(PathBuf, Option<String>)>,
+            followed: &'a mut Vec<PathBuf>,
+            base_dir: &'a PathBuf,
+            manifest: &'a Manifest,
+            prompts: &'a Prompts,
+            backend: &'a DeepseekBackend,
+            files: &'a FileCache,
+            max_tokens_per_call: usize,
+            logger: &'a Logger,
+        ) -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<BasicRoute>>> + Send + 'a>> {
+            Box::pin(async move {
+                logger.log(format!("route_path={:#?}", route_file));
+                logger.progress(format!("reading routes from {}", route_file.display()));
+
+                // let llm_options = GPT3_5OptionsBuilder::default()
+                //     .prompt(PROMPT.to_owned())
+                //     .build()
+                //     .expect("failed to build gpt options");
+                // let mut llm = GPT3_5::new(llm_options);
+                let file_content = files
+                    .read(route_file)
+                    .context("failed to read route file")?;
+
+                /// one of the (possibly chained) method handlers registered on a path
+                #[derive(Deserialize, Debug)]
+                struct IRMethodHandler {
+                    method: String,
+                    handler: String,
+                    module: String,
+                }
 
-        struct BasicRoute {
-            pub path: String,
-            pub method: HTTPMethod,
-            pub handler: RouteHandler,
-        }
+                /// a function or const building the path's method router, e.g. `.route("/x", x_routes())`
+                #[derive(Deserialize, Debug)]
+                struct IRMethodRouter {
+                    function: String,
+                    module: String,
+                }
 
-        async fn get_route_list_from_route_file(
-            route_file: &PathBuf,
-            base_dir: &PathBuf,
-        ) -> anyhow::Result<Vec<BasicRoute>> {
-            println!("route_path={:#?}", route_file);
-            const PROMPT: &'static str = r##"
-You are a Rust axum framework documentation assistant.
-You will be given the contents of a rust file. Return a json object containing an array of all the axum routes defined according to the file, the path, their methods, the name of their handlers and the import statement for the handler (i.e import path to handler definition).
+                #[derive(Deserialize, Debug)]
+                struct IRRoute {
+                    path: String,
+                    #[serde(default)]
+                    methods: Vec<IRMethodHandler>,
+                    #[serde(default)]
+                    method_router: Option<IRMethodRouter>,
+                }
 
-Example object:
-{
-"routes": [
-    {
-        "path": "/",
-        "method": "GET",
-        "handler": "controllers::create",
-        "module": "crate::controllers::create"
-    }
-]
-}
-        "##;
+                /// a router folded in with `.merge(...)` or `.nest("/prefix", ...)`
+                #[derive(Deserialize, Debug)]
+                struct IRSubRouter {
+                    kind: String,
+                    prefix: Option<String>,
+                    function: String,
+                    module: String,
+                }
 
-            // let llm_options = GPT3_5OptionsBuilder::default()
-            //     .prompt(PROMPT.to_owned())
-            //     .build()
-            //     .expect("failed to build gpt options");
-            // let mut llm = GPT3_5::new(llm_options);
-            let mut llm = Deepseek::new(&PROMPT);
+                #[derive(Deserialize, Debug)]
+                struct Response {
+                    routes: Vec<IRRoute>,
+                    #[serde(default)]
+                    sub_routers: Vec<IRSubRouter>,
+                }
 
-            let file_content = read_to_string(route_file).context("failed to read route file")?;
-            let query = LLMQueryRequest {
-                history: vec![],
-                query: file_content,
-            };
+                let router_code = router_source(&file_content, router_fn.as_deref());
 
-            #[derive(Deserialize, Debug)]
-            struct IRRoute {
-                path: String,
-                method: String,
-                handler: String,
-                module: String,
-            }
+                // a file too large for one call is read a segment of its router at a time
+                let chunks = router_chunks(&router_code, max_tokens_per_call);
+                if chunks.len() > 1 {
+                    logger.progress(format!(
+                        "{} is larger than {max_tokens_per_call} tokens, reading it in {} parts",
+                        route_file.display(),
+                        chunks.len()
+                    ));
+                }
 
-            #[derive(Deserialize, Debug)]
-            struct Response {
-                routes: Vec<IRRoute>,
-            }
+                let mut response = Response {
+                    routes: vec![],
+                    sub_routers: vec![],
+                };
+                for chunk in chunks {
+                    let mut llm = backend.conversation(&prompts.route_extract);
+                    let query = LLMQueryRequest {
+                        history: vec![],
+                        query: chunk,
+                    };
+                    let chunk_response = llm.execute_query(query).await?;
 
-            let response = llm.execute_query(query).await?;
+                    let chunk_response =
+                        match serde_json::from_str::<Response>(&chunk_response.text) {
+                            Ok(nodes) => nodes,
+                            Err(e) => {
+                                return Err(DocgenError::InvalidLlmJson {
+                                    message: e.to_string(),
+                                    raw: chunk_response.text,
+                                }
+                                .into())
+                            }
+                        };
+                    response.routes.extend(chunk_response.routes);
+                    response.sub_routers.extend(chunk_response.sub_routers);
+                }
 
-            let response = match serde_json::from_str::<Response>(&response.text) {
-                Ok(nodes) => nodes,
-                Err(e) => bail!(format!(
-                    "llm returned unserializable string {e} \n\n{}",
-                    response.text,
-                )),
-            };
+                // closures are read from the file itself, the LLM can't give them an import path
+                let closures = route_closures(&router_code);
+                let services = route_services(&router_code);
+                if response.routes.is_empty()
+                    && response.sub_routers.is_empty()
+                    && closures.is_empty()
+                    && services.is_empty()
+                {
+                    logger.warn(format!(
+                        "{} builds a Router but no routes were found in it",
+                        route_file.display()
+                    ));
+                }
+
+                // handlers the LLM reported, and how many of them resolved to local code
+                let (mut reported, mut resolved) = (0, 0);
+                // handlers defined in the router file itself are referred to by bare name
+                let local_handlers = match syn::parse_file(&file_content) {
+                    Ok(parsed) => response
+                        .routes
+                        .iter()
+                        .flat_map(|route| &route.methods)
+                        .map(|method_handler| method_handler.module.clone())
+                        .filter(|module| !module.contains("::") && defines_symbol(&parsed, module))
+                        .collect(),
+                    Err(_) => HashSet::new(),
+                };
+                let mut routes = Vec::new();
+                for route in response.routes {
+                    // services have no handler to analyze, they're documented from the source
+                    if services.iter().any(|s| s.path == route.path) {
+                        continue;
+                    }
+
+                    let path = join_route_path(&prefix, &route.path);
+                    if let Some(method_router) = &route.method_router {
+                        routes.extend(routes_from_method_router(
+                            &path,
+                            &method_router.function,
+                            &method_router.module,
+                            &file_content,
+                            manifest,
+                            base_dir,
+                            files,
+                            logger,
+                        ));
+                    }
+
+                    for method_handler in route.methods {
+                        let method: HTTPMethod = method_handler.method.as_str().try_into()?;
+                        if closures
+                            .iter()
+                            .any(|c| c.path == route.path && c.method == method)
+                        {
+                            continue;
+                        }
 
-            let mut routes = Vec::new();
-            for route in response.routes {
-                if let ImportPath::Local(import_path) = resolve_import(
-                    &route.module,
-                    "sabbatical_server", // TODO:  get from manifest
-                    base_dir.as_path(),
-                )? {
+                        reported += 1;
+                        let import_path = match local_handlers.contains(&method_handler.module) {
+                            true => ImportKind::Local(route_file.clone()),
+                            false => resolve_import_in_file(
+                                &method_handler.module,
+                                &file_content,
+                                manifest,
+                                base_dir.as_path(),
+                                logger,
+                            ),
+                        };
+                        let import_path = match import_path {
+                            ImportKind::Local(import_path) => import_path,
+                            module @ (ImportKind::External(_) | ImportKind::Std) => {
+                                logger.warn_route(
+                                    &path,
+                                    format!(
+                                        "{} handler {} comes from the external module {module}, skipping it",
+                                        method_handler.method, method_handler.handler
+                                    ),
+                                );
+                                continue;
+                            }
+                            ImportKind::Unknown => {
+                                logger.warn_route(
+                                    &path,
+                                    format!(
+                                        "{} handler {} could not be found from module {}, skipping it",
+                                        method_handler.method,
+                                        method_handler.handler,
+                                        method_handler.module
+                                    ),
+                                );
+                                continue;
+                            }
+                        };
+                        resolved += 1;
+
+                        routes.push(BasicRoute {
+                            path: path.to_owned(),
+                            method,
+                            handler: RouteHandler {
+                                identifier: method_handler.handler.to_owned(),
+                                import_path,
+                                method_of: None,
+                                closure: None,
+                                service: None,
+                            },
+                        });
+                    }
+                }
+
+                if resolved < reported {
+                    logger.progress(format!(
+                        "{}: {reported} handlers reported, {resolved} resolved to local code",
+                        route_file.display()
+                    ));
+                } else {
+                    logger.log(format!("{reported} handlers reported, all resolved"));
+                }
+
+                for closure in closures {
+                    let path = join_route_path(&prefix, &closure.path);
                     routes.push(BasicRoute {
-                        path: route.path.to_owned(),
-                        method: route.method.as_str().try_into()?,
                         handler: RouteHandler {
-                            identifier: route.handler.to_owned(),
-                            import_path,
+                            identifier: closure_operation_id(&closure.method, &path),
+                            import_path: route_file.clone(),
                             method_of: None,
+                            closure: Some(closure.clone()),
+                            service: None,
                         },
+                        method: closure.method,
+                        path,
                     });
                 }
-            }
 
-            Ok(routes)
+                for service in services {
+                    let path = join_route_path(&prefix, &service.path);
+                    routes.push(BasicRoute {
+                        handler: RouteHandler {
+                            identifier: closure_operation_id(&HTTPMethod::GET, &path),
+                            import_path: route_file.clone(),
+                            method_of: None,
+                            closure: None,
+                            service: Some(service),
+                        },
+                        method: HTTPMethod::GET,
+                        path,
+                    });
+                }
+
+                let mut ancestors = ancestors;
+                ancestors.push((route_file.clone(), router_fn));
+                for sub_router in response.sub_routers {
+                    let sub_prefix = match (sub_router.kind.as_str(), &sub_router.prefix) {
+                        ("nest", Some(sub_prefix)) => join_route_path(&prefix, sub_prefix),
+                        _ => prefix.clone(),
+                    };
+
+                    let ImportKind::Local(sub_router_file) = resolve_import_in_file(
+                        &sub_router.module,
+                        &file_content,
+                        manifest,
+                        base_dir.as_path(),
+                        logger,
+                    ) else {
+                        logger.warn(format!(
+                            "{} router {} is not a local import, skipping it",
+                            sub_router.kind, sub_router.function
+                        ));
+                        continue;
+                    };
+
+                    let sub_router_fn = Some(sub_router.function.clone());
+                    if ancestors.contains(&(sub_router_file.clone(), sub_router_fn.clone())) {
+                        logger.log(format!(
+                            "{} router {} is already being read from {:?}",
+                            sub_router.kind, sub_router.function, sub_router_file
+                        ));
+                        continue;
+                    }
+
+                    if !followed.contains(&sub_router_file) {
+                        followed.push(sub_router_file.clone());
+                    }
+                    let sub_routes = get_route_list_from_route_file(
+                        &sub_router_file,
+                        sub_router_fn,
+                        sub_prefix,
+                        ancestors.clone(),
+                        followed,
+                        base_dir,
+                        manifest,
+                        prompts,
+                        backend,
+                        files,
+                        max_tokens_per_call,
+                        logger,
+                    )
+                    .await;
+                    match sub_routes {
+                        Ok(sub_routes) => routes.extend(sub_routes),
+                        Err(e) => logger.warn(format!(
+                            "failed to read {} router {} from {}, skipping it: {e:#}",
+                            sub_router.kind,
+                            sub_router.function,
+                            sub_router_file.display()
+                        )),
+                    }
+                }
+
+                Ok(routes)
+            })
         }
 
-        let root_node = FunctionCallNode {
-            caller: None,
-            callee: "main".to_owned(),
-            associated_struct: None,
-            module: Some("crate".to_owned()),
-            import_path: ImportPath::Local(entry_file.clone()),
-            arguments: vec![],
-        };
+        /// Follows a method router built elsewhere to the handlers it registers
+        #[allow(clippy::too_many_arguments)]
+        fn routes_from_method_router(
+            path: &str,
+            method_router_fn: &str,
+            method_router_module: &str,
+            route_file_content: &str,
+            manifest: &Manifest,
+            base_dir: &Path,
+            files: &FileCache,
+            logger: &Logger,
+        ) -> Vec<BasicRoute> {
+            let ImportKind::Local(builder_file) = resolve_import_in_file(
+                method_router_module,
+                route_file_content,
+                manifest,
+                base_dir,
+                logger,
+            ) else {
+                logger.warn_route(
+                    path,
+                    format!(
+                        "method router {} is not a local import, skipping it",
+                        method_router_fn
+                    ),
+                );
+                return vec![];
+            };
 
-        let logger = Logger::new();
+            let builder_content = files.read(&builder_file).unwrap_or_default();
+            let name = method_router_fn
+                .rsplit("::")
+                .next()
+                .unwrap_or(method_router_fn);
+            let Some(handlers) = method_router_handlers(&builder_content, name) else {
+                logger.warn_route(
+                    path,
+                    format!(
+                        "couldn't read the handlers registered by {} in {:?}",
+                        method_router_fn, builder_file
+                    ),
+                );
+                return vec![];
+            };
+
+            let builder = syn::parse_file(&builder_content).ok();
+            handlers
+                .into_iter()
+                .filter_map(|(method, handler)| {
+                    let identifier = handler.rsplit("::").next().unwrap_or(&handler).to_owned();
+                    // handlers next to the builder are referred to by bare name
+                    let import_path = if !handler.contains("::")
+                        && builder
+                            .as_ref()
+                            .is_some_and(|f| defines_symbol(f, &handler))
+                    {
+                        ImportKind::Local(builder_file.clone())
+                    } else {
+                        resolve_import_in_file(
+                            &handler,
+                            &builder_content,
+                            manifest,
+                            base_dir,
+                            logger,
+                        )
+                    };
+
+                    let ImportKind::Local(import_path) = import_path else {
+                        logger.warn_route(
+                            path,
+                            format!(
+                                "{} handler {handler} is not a local import, skipping it",
+                                method
+                            ),
+                        );
+                        return None;
+                    };
+
+                    Some(BasicRoute {
+                        path: path.to_owned(),
+                        method,
+                        handler: RouteHandler {
+                            identifier,
+                            import_path,
+                            method_of: None,
+                            closure: None,
+                            service: None,
+                        },
+                    })
+                })
+                .collect()
+        }
+
+        let logger = self.logger.clone();
         let base_dir = entry_file
             .parent()
             .unwrap_or_else(|| Path::new("."))
             .to_path_buf();
-        logger.log("=> main");
         // let mut route_list = Vec::new();
         // read_file_and_extract_nodes_from_entry_function(
         //     root_node,
@@ -677,10 +2151,209 @@ Example object:
         // )
         // .await?;
 
-        let route_file = find_routes_file(root_node, &mut llm, logger, base_dir.clone()).await?;
-        let basic_routes = get_route_list_from_route_file(&route_file, &base_dir).await?;
+        let manifest = Manifest::try_new(&self.args.code_dir)?;
+        let mut prompts = match &self.args.prompt_dir {
+            Some(prompt_dir) => Prompts::load(prompt_dir)?,
+            None => Prompts::default(),
+        };
+        if self.args.examples {
+            prompts = prompts.with_examples();
+        }
+
+        let files = FileCache::default();
+        let route_files = match &self.args.routes_file {
+            Some(routes_file) => {
+                let routes_file = self.args.code_dir.join(routes_file);
+                if !routes_file.is_file() {
+                    return Err(anyhow!("routes file {:?} does not exist", routes_file).into());
+                }
+                vec![routes_file]
+            }
+            None => {
+                let (entry_fn, entry_struct) = self.get_entry_fn(&entry_file)?;
+                let root_node = FunctionCallNode {
+                    caller: None,
+                    callee: entry_fn.clone(),
+                    associated_struct: entry_struct,
+                    module: Some("crate".to_owned()),
+                    import_path: ImportKind::Local(entry_file.clone()),
+                    arguments: vec![],
+                };
+
+                logger.log(format!("=> {entry_fn}"));
+                let cache = RouterCache::new(&self.args.code_dir);
+                let cache_key = root_node
+                    .associated_struct
+                    .as_ref()
+                    .map_or(entry_fn.clone(), |s| format!("{s}::{entry_fn}"));
+                let cached_route_files = match self.args.cache {
+                    true => cache.load(&entry_file, &cache_key),
+                    false => None,
+                };
+
+                match cached_route_files {
+                    Some(route_files) => {
+                        logger.progress(format!("using cached router location from {CACHE_DIR}"));
+                        route_files
+                    }
+                    None => {
+                        logger.progress("locating router");
+                        let krate = DiscoveredCrate {
+                            trait_impls: TraitImpls::scan(&base_dir, &files),
+                            base_dir: base_dir.clone(),
+                            manifest: &manifest,
+                            files: &files,
+                        };
+                        let mut traversed = Vec::new();
+                        let route_files = find_routes_files(
+                            root_node,
+                            &mut llm,
+                            logger.clone(),
+                            &krate,
+                            &mut traversed,
+                            &mut HashSet::new(),
+                        )
+                        .await?;
+
+                        if self.args.cache && !route_files.is_empty() {
+                            if let Err(e) =
+                                cache.store(&entry_file, &cache_key, &traversed, &route_files)
+                            {
+                                logger.log(format!("failed to cache router location: {e}"));
+                            }
+                        }
+
+                        route_files
+                    }
+                }
+            }
+        };
+
+        if route_files.is_empty() {
+            return Err(DocgenError::RouterNotFound);
+        }
+
+        let mut followed = Vec::new();
+        let mut routes_per_file = Vec::with_capacity(route_files.len());
+        for route_file in &route_files {
+            let routes = get_route_list_from_route_file(
+                route_file,
+                None,
+                String::new(),
+                vec![],
+                &mut followed,
+                &base_dir,
+                &manifest,
+                &prompts,
+                &backend,
+                &files,
+                self.args.max_tokens_per_call,
+                &logger,
+            )
+            .await?;
+            routes_per_file.push((route_file, routes));
+        }
+
+        let mut basic_routes: Vec<BasicRoute> = Vec::new();
+        for (route_file, routes) in routes_per_file {
+            // merged and nested routers were read under their parent's prefix already
+            if followed.contains(route_file) {
+                continue;
+            }
+
+            for route in routes {
+                add_unique_route(&mut basic_routes, route, &logger);
+            }
+        }
+
+        logger.log(format!("routes in route list: {}", basic_routes.len()));
+        if basic_routes.is_empty() {
+            logger.warn(format!(
+                "no routes were found in {}, any that were skipped are listed in the other warnings",
+                route_files
+                    .iter()
+                    .map(|f| f.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        /// Builds a route handled by a closure from its signature alone
+        fn route_from_closure(route: BasicRoute, closure: &RouteClosure, logger: &Logger) -> Route {
+            let mut warnings = Vec::new();
+            let parameters =
+                build_parameters(closure_params(&closure.arguments), &mut warnings, |_| None);
+            for warning in warnings {
+                logger.warn_route(&route.path, format!("closure handler {warning}"));
+            }
+
+            Route {
+                parameters: align_path_params(&parameters, &route.path),
+                method: route.method,
+                body: None,
+                response: None,
+                response_status: None,
+                error_responses: vec![],
+                security: None,
+                docs: ir::OperationDocs {
+                    operation_id: Some(route.handler.identifier),
+                    ..Default::default()
+                },
+                source: Some(ir::SourceLocation {
+                    file: route.handler.import_path,
+                    line: closure.line,
+                }),
+                handler: None,
+                path: route.path,
+            }
+        }
+
+        /// Builds the route of a mounted service, e.g. static files served by `ServeDir`
+        fn route_from_service(route: BasicRoute, service: &RouteService) -> Route {
+            let served = match service.nested {
+                true => format!("{} and everything below it", route.path),
+                false => route.path.clone(),
+            };
+
+            Route {
+                parameters: vec![],
+                method: route.method,
+                body: None,
+                response: Some(Body {
+                    body_type: BodyType::Binary,
+                    identifier: None,
+                    shape: BodyShape::Single,
+                    required_fields: vec![],
+                    properties: HashMap::new(),
+                    alternatives: vec![],
+                }),
+                response_status: None,
+                error_responses: vec![],
+                security: None,
+                docs: ir::OperationDocs {
+                    summary: Some(format!("Serve {served}")),
+                    description: Some(format!(
+                        "Served by the `{}` service rather than a handler, so the response is \
+                         whatever it serves, e.g. static files",
+                        service.service
+                    )),
+                    operation_id: Some(route.handler.identifier),
+                    ..Default::default()
+                },
+                source: Some(ir::SourceLocation {
+                    file: route.handler.import_path,
+                    line: service.line,
+                }),
+                handler: None,
+                path: route.path,
+            }
+        }
 
-        println!("routes in rountelis === {}", basic_routes.len());
+        // closures and services are documented from the source alone, without the LLM
+        let (inline_routes, basic_routes): (Vec<BasicRoute>, Vec<BasicRoute>) = basic_routes
+            .into_iter()
+            .filter(|route| self.args.services || route.handler.service.is_none())
+            .partition(|route| route.handler.closure.is_some() || route.handler.service.is_some());
 
         struct BodyStructure {}
 
@@ -704,7 +2377,23 @@ Example object:
         //             let response = llm.execute_query(query);
         // }
 
-        async fn build_route_info(route: BasicRoute, base_dir: &Path) -> anyhow::Result<Route> {
+        /// Builds the route info for every route whose handler lives in `handler_file`.
+        /// The file is read once and its handlers are analyzed in a single LLM call, or in a few
+        /// when the file is larger than `max_tokens_per_call`.
+        #[allow(clippy::too_many_arguments)]
+        async fn build_route_infos_for_file(
+            handler_file: &Path,
+            mut routes: Vec<BasicRoute>,
+            base_dir: &Path,
+            manifest: &Manifest,
+            security_scheme: &str,
+            prompts: &Prompts,
+            backend: &DeepseekBackend,
+            files: &FileCache,
+            max_tokens_per_call: usize,
+            private_handlers: bool,
+            logger: &Logger,
+        ) -> anyhow::Result<Vec<Route>> {
             // build params
 
             // let llm_options = GPT3_5OptionsBuilder::default()
@@ -712,28 +2401,55 @@ Example object:
             //     .build()
             //     .expect("failed to build gpt options");
             // let mut llm = GPT3_5::new(llm_options);
-            let mut llm = Deepseek::new(&BODY_EXTRACT_PROMPT);
-
-            let file_content = read_to_string(route.handler.import_path.clone())
-                .context("failed to read route file")?;
-            let query = LLMQueryRequest {
-                history: vec![],
-                query: format!(
-                    "
-function_name: {}
-file_content: {}
-###
-                ",
-                    route.handler.identifier, file_content
-                ),
+
+            let file_content = match files.read(handler_file) {
+                Ok(file_content) => file_content,
+                Err(e) => {
+                    for route in &routes {
+                        logger.warn_route(
+                            &route.path,
+                            format!(
+                                "{} handler {} is in {}, which could not be read, skipping it: {e}",
+                                route.method,
+                                route.handler.identifier,
+                                handler_file.display()
+                            ),
+                        );
+                    }
+                    return Ok(vec![]);
+                }
             };
+            // the LLM can still read it, but doc comments and source lines can't be
+            if let Err(e) = syn::parse_file(&file_content) {
+                logger.warn(format!(
+                    "{} could not be parsed, skipping its doc comments: {e}",
+                    handler_file.display()
+                ));
+            }
 
-            #[derive(Deserialize, Debug)]
-            struct IRParam {
-                param_type: String,
-                name: String,
-                data_type: String,
-                description: String,
+            // handlers that aren't `pub` are internal when they're left out
+            if !private_handlers {
+                routes.retain(|route| {
+                    let public =
+                        handler_is_public(&file_content, &route.handler.identifier).unwrap_or(true);
+                    if !public {
+                        logger.progress(format!(
+                            "skipping {} {}, its handler {} isn't public",
+                            route.method, route.path, route.handler.identifier
+                        ));
+                    }
+                    public
+                });
+                if routes.is_empty() {
+                    return Ok(vec![]);
+                }
+            }
+
+            let mut function_names: Vec<&str> = Vec::new();
+            for route in &routes {
+                if !function_names.contains(&route.handler.identifier.as_str()) {
+                    function_names.push(&route.handler.identifier);
+                }
             }
 
             #[derive(Deserialize, Debug, Clone)]
@@ -741,166 +2457,705 @@ file_content: {}
                 #[serde(rename = "type")]
                 r#type: String,
                 required: bool,
+                /// the field's explicit `#[serde(rename = "..")]`, if any
+                #[serde(default)]
+                serialized_name: Option<String>,
+                /// whether the field has a `#[serde(default)]`
+                #[serde(default)]
+                default: bool,
+                /// name of the struct, for fields that are structs themselves
+                #[serde(default)]
+                identifier: Option<String>,
+                /// import path of that struct when it isn't defined in the same file
+                #[serde(default)]
+                module: Option<String>,
+                /// the struct's fields when it is defined in the same file
+                #[serde(default)]
+                structure: Option<HashMap<String, IRBodyStructureRef>>,
+                #[serde(default)]
+                rename_all: Option<String>,
+                /// only asked for when generating examples
+                #[serde(default)]
+                example: Option<serde_json::Value>,
             }
 
             #[derive(Deserialize, Debug, Clone)]
             struct IRBody {
                 content_type: String,
+                /// other content types the same body is accepted in
+                #[serde(default)]
+                additional_content_types: Vec<String>,
                 structure: Option<HashMap<String, IRBodyStructureRef>>,
+                /// the container's `#[serde(rename_all = "..")]` rule, if any
+                #[serde(default)]
+                rename_all: Option<String>,
                 module: Option<String>,
+                #[serde(default)]
                 identifier: String,
+                /// `Vec` or `Option` when the struct is wrapped in one, e.g. `Json<Vec<ItemDto>>`
+                #[serde(default)]
+                wrapper: Option<String>,
             }
 
             #[derive(Deserialize, Debug)]
-            struct Response {
+            struct IRHandler {
+                function_name: String,
                 parameters: Vec<IRParam>,
                 body: Option<IRBody>,
+                #[serde(default)]
+                response: Option<IRBody>,
+                /// success status the handler sets, e.g. 201
+                #[serde(default)]
+                status: Option<u16>,
+                #[serde(default)]
+                requires_auth: bool,
+                /// one line summary, used when the handler's doc comment has none
+                #[serde(default)]
+                summary: Option<String>,
             }
 
-            let response = llm.execute_query(query).await?;
-
-            let response = match serde_json::from_str::<Response>(&response.text) {
-                Ok(nodes) => nodes,
-                Err(e) => bail!(format!(
-                    "llm returned unserializable string {e} \n\n{}",
-                    response.text,
-                )),
-            };
-
-            println!("Route={} Response={:#?}", route.path, response);
-
-            let parameters = response
-                .parameters
-                .into_iter()
-                .map(|p| {
-                    let data_type = match p.data_type.as_str() {
-                        "&str" | "String" => ir::ParamDataType::String,
-                        "u32" | "usize" | "isize" | "u64" => ir::ParamDataType::Integer,
-                        "f32" | "f64" => ir::ParamDataType::Integer,
-                        _ => ir::ParamDataType::Unknown,
-                    };
+            #[derive(Deserialize, Debug)]
+            struct Response {
+                handlers: Vec<IRHandler>,
+            }
 
-                    let param_type = match p.data_type.as_str() {
-                        "path" => ir::ParamType::Path,
-                        "query" => ir::ParamType::Path,
-                        _ => ir::ParamType::Unknown,
-                    };
+            // a file too large for one call is analyzed a few handlers at a time
+            let chunks = handler_chunks(&file_content, &function_names, max_tokens_per_call);
+            if chunks.len() > 1 {
+                logger.progress(format!(
+                    "{} is larger than {max_tokens_per_call} tokens, analyzing it in {} parts",
+                    handler_file.display(),
+                    chunks.len()
+                ));
+            }
 
-                    Parameter {
-                        name: p.name.to_owned(),
-                        description: p.description.to_owned(),
-                        data_type,
-                        param_type,
+            let mut response = Response { handlers: vec![] };
+            for (chunk_function_names, chunk) in chunks {
+                let mut llm = backend.conversation(&prompts.body_extract);
+                let query = LLMQueryRequest {
+                    history: vec![],
+                    query: format!(
+                        "
+function_names: {}
+file_content: {}
+###
+                ",
+                        chunk_function_names.join(", "),
+                        chunk
+                    ),
+                };
+                let chunk_response = llm.execute_query(query).await?;
+
+                let chunk_response = match serde_json::from_str::<Response>(&chunk_response.text) {
+                    Ok(nodes) => nodes,
+                    Err(e) => {
+                        return Err(DocgenError::InvalidLlmJson {
+                            message: e.to_string(),
+                            raw: chunk_response.text,
+                        }
+                        .into())
                     }
-                })
-                .collect::<Vec<Parameter>>();
+                };
+                response.handlers.extend(chunk_response.handlers);
+            }
+
+            logger.log(format!("File={:#?} Response={:#?}", handler_file, response));
 
             async fn find_and_extract_type_structure(
                 module: &str,
                 base_dir: &Path,
+                manifest: &Manifest,
                 identifier: &str,
-            ) -> anyhow::Result<Option<HashMap<String, IRBodyStructureRef>>> {
-                if let ImportPath::Local(import_path) = resolve_import(
-                    &module,
-                    "sabbatical_server", // TODO:  get from manifest
-                    base_dir,
-                )? {
-                    let mut llm = Deepseek::new(&BODY_OUTER_EXTRACT_PROMPT);
+                outer_prompt: &str,
+                backend: &DeepseekBackend,
+                files: &FileCache,
+                logger: &Logger,
+            ) -> anyhow::Result<
+                Option<(PathBuf, HashMap<String, IRBodyStructureRef>, Option<String>)>,
+            > {
+                if let ImportKind::Local(import_path) =
+                    resolve_import_or_unknown(&module, manifest, base_dir, logger)
+                {
+                    return extract_type_structure_from_file(
+                        &import_path,
+                        identifier,
+                        outer_prompt,
+                        backend,
+                        files,
+                    )
+                    .await
+                    .map(|(structure, rename_all)| Some((import_path, structure, rename_all)));
+                }
 
-                    let file_content =
-                        read_to_string(import_path.clone()).context("failed to read route file")?;
-                    let query = LLMQueryRequest {
-                        history: vec![],
-                        query: format!(
-                            "
+                Ok(None)
+            }
+
+            async fn extract_type_structure_from_file(
+                file: &Path,
+                identifier: &str,
+                outer_prompt: &str,
+                backend: &DeepseekBackend,
+                files: &FileCache,
+            ) -> anyhow::Result<(HashMap<String, IRBodyStructureRef>, Option<String>)> {
+                let mut llm = backend.conversation(outer_prompt);
+
+                let file_content = files.read(file).context("failed to read route file")?;
+                let query = LLMQueryRequest {
+                    history: vec![],
+                    query: format!(
+                        "
 struct_name: {}
 file_content: {}
 ###
                 ",
-                            identifier, file_content
-                        ),
-                    };
-
-                    #[derive(Deserialize)]
-                    struct Extractor {
-                        structure: HashMap<String, IRBodyStructureRef>,
-                    }
-
-                    let response = llm.execute_query(query).await?;
-                    let response = match serde_json::from_str::<Extractor>(&response.text) {
-                        Ok(nodes) => nodes,
-                        Err(e) => bail!(format!(
-                            "llm returned unserializable string {e} \n\n{}",
-                            response.text,
-                        )),
-                    };
+                        identifier, file_content
+                    ),
+                };
 
-                    return Ok(Some(response.structure));
+                #[derive(Deserialize)]
+                struct Extractor {
+                    structure: HashMap<String, IRBodyStructureRef>,
+                    #[serde(default)]
+                    rename_all: Option<String>,
                 }
 
-                Ok(None)
-            }
-
-            let body = if let Some(body) = &response.body {
-                let structure = if let Some(structure) = body.structure.clone() {
-                    Some(structure)
-                } else {
-                    if let Some(module) = &body.module {
-                        find_and_extract_type_structure(&module, base_dir, &body.identifier).await?
-                    } else {
-                        None
+                let response = llm.execute_query(query).await?;
+                let response = match serde_json::from_str::<Extractor>(&response.text) {
+                    Ok(nodes) => nodes,
+                    Err(e) => {
+                        return Err(DocgenError::InvalidLlmJson {
+                            message: e.to_string(),
+                            raw: response.text,
+                        }
+                        .into())
                     }
                 };
 
-                if let Some(structure) = structure {
-                    let (properties, required_fields) = {
-                        let mut props = HashMap::new();
-                        let mut required = vec![];
-                        for (prop, struct_ref) in structure {
-                            let value = BodyProperty {
-                                prop_type: match struct_ref.r#type.as_ref() {
-                                    "String" => PropType::String,
-                                    "Number" => PropType::Number,
-                                    "Boolean" => PropType::Boolean,
-                                    _ => PropType::Object,
-                                },
-                            };
+                Ok((response.structure, response.rename_all))
+            }
+
+            /// Converts an extracted structure into body properties keyed by their wire names.
+            /// Struct fields are resolved recursively, from `file` when defined there or from
+            /// the module they're imported from, up to `MAX_BODY_DEPTH` levels deep.
+            #[allow(clippy::too_many_arguments)]
+            fn build_body_properties<'a>(
+                structure: HashMap<String, IRBodyStructureRef>,
+                rename_all: Option<String>,
+                file: &'a Path,
+                base_dir: &'a Path,
+                manifest: &'a Manifest,
+                outer_prompt: &'a str,
+                backend: &'a DeepseekBackend,
+                files: &'a FileCache,
+                logger: &'a Logger,
+                depth: usize,
+            ) -> Pin<
+                Box<
+                    dyn Future<
+                            Output = anyhow::Result<(HashMap<String, BodyProperty>, Vec<String>)>,
+                        > + Send
+                        + 'a,
+                >,
+            > {
+                Box::pin(async move {
+                    let mut props = HashMap::new();
+                    let mut required = vec![];
+                    for (field, struct_ref) in structure {
+                        // property keys must be the names used on the wire
+                        let prop = match (&struct_ref.serialized_name, &rename_all) {
+                            (Some(name), _) => name.to_owned(),
+                            (None, Some(rule)) => apply_serde_rename_rule(&field, rule),
+                            (None, None) => field,
+                        };
+
+                        let is_option = struct_ref.r#type.starts_with("Option<");
+                        let prop_type = prop_type(&struct_ref.r#type);
+
+                        let nested = match (&prop_type, &struct_ref.identifier) {
+                            _ if depth >= MAX_BODY_DEPTH => None,
+                            (PropType::Object, _) if struct_ref.structure.is_some() => Some((
+                                file.to_path_buf(),
+                                struct_ref.structure.clone().unwrap_or_default(),
+                                struct_ref.rename_all.clone(),
+                            )),
+                            (PropType::Object, Some(identifier)) => {
+                                let resolved = match &struct_ref.module {
+                                    Some(module) => {
+                                        find_and_extract_type_structure(
+                                            module,
+                                            base_dir,
+                                            manifest,
+                                            identifier,
+                                            outer_prompt,
+                                            backend,
+                                            files,
+                                            logger,
+                                        )
+                                        .await
+                                    }
+                                    None => extract_type_structure_from_file(
+                                        file,
+                                        identifier,
+                                        outer_prompt,
+                                        backend,
+                                        files,
+                                    )
+                                    .await
+                                    .map(|(s, r)| Some((file.to_path_buf(), s, r))),
+                                };
+
+                                match resolved {
+                                    Ok(resolved) => resolved,
+                                    Err(e) => {
+                                        logger.warn(format!(
+                                            "could not resolve the structure of {identifier}: {e}"
+                                        ));
+                                        None
+                                    }
+                                }
+                            }
+                            _ => None,
+                        };
 
-                            props.insert(prop.clone(), value);
-                            if struct_ref.required {
-                                required.push(prop);
+                        let (properties, required_fields) = match nested {
+                            Some((nested_file, structure, rename_all)) => {
+                                build_body_properties(
+                                    structure,
+                                    rename_all,
+                                    &nested_file,
+                                    base_dir,
+                                    manifest,
+                                    outer_prompt,
+                                    backend,
+                                    files,
+                                    logger,
+                                    depth + 1,
+                                )
+                                .await?
                             }
+                            None => (HashMap::new(), vec![]),
+                        };
+
+                        let value = BodyProperty {
+                            prop_type,
+                            properties,
+                            required_fields,
+                            example: struct_ref.example.clone(),
+                        };
+
+                        props.insert(prop.clone(), value);
+                        if struct_ref.required && !struct_ref.default && !is_option {
+                            required.push(prop);
                         }
-                        (props, required)
-                    };
+                    }
 
-                    Some(Body {
-                        body_type: BodyType::Json,
+                    Ok((props, required))
+                })
+            }
+
+            /// Resolves the schema of a request or response body, recording what it can't
+            /// resolve in `warnings`
+            #[allow(clippy::too_many_arguments)]
+            async fn resolve_body(
+                body: &IRBody,
+                handler_file: &Path,
+                base_dir: &Path,
+                manifest: &Manifest,
+                prompts: &Prompts,
+                backend: &DeepseekBackend,
+                files: &FileCache,
+                logger: &Logger,
+                warnings: &mut Vec<String>,
+            ) -> anyhow::Result<Option<Body>> {
+                let Some(body_type) = body_type(&body.content_type) else {
+                    warnings.push(format!(
+                        "unsupported body content type `{}`",
+                        body.content_type
+                    ));
+                    return Ok(None);
+                };
+                let (shape, identifier) = body_shape(&body.identifier, body.wrapper.as_deref());
+
+                let structure = if let BodyType::Binary = body_type {
+                    // raw bytes have no structure to resolve
+                    Some((handler_file.to_path_buf(), HashMap::new(), None))
+                } else if let Some(structure) = body.structure.clone() {
+                    Some((
+                        handler_file.to_path_buf(),
+                        structure,
+                        body.rename_all.clone(),
+                    ))
+                } else if let Some(module) = &body.module {
+                    find_and_extract_type_structure(
+                        module,
+                        base_dir,
+                        manifest,
+                        &identifier,
+                        &prompts.body_outer_extract,
+                        backend,
+                        files,
+                        logger,
+                    )
+                    .await?
+                } else {
+                    None
+                };
+
+                if let Some((structure_file, structure, rename_all)) = structure {
+                    let (properties, required_fields) = build_body_properties(
+                        structure,
+                        rename_all,
+                        &structure_file,
+                        base_dir,
+                        manifest,
+                        &prompts.body_outer_extract,
+                        backend,
+                        files,
+                        logger,
+                        0,
+                    )
+                    .await?;
+
+                    let resolved = Body {
+                        body_type,
+                        identifier: (!identifier.is_empty()).then_some(identifier),
+                        shape,
                         properties,
                         required_fields,
-                    })
+                        alternatives: vec![],
+                    };
+                    Ok(Some(with_alternatives(resolved, body, warnings)))
+                } else if let BodyType::Multipart = body_type {
+                    Ok(Some(with_alternatives(
+                        unstructured_body(body_type),
+                        body,
+                        warnings,
+                    )))
                 } else {
-                    None
+                    warnings.push(format!(
+                        "could not resolve the body schema for {}",
+                        body.identifier
+                    ));
+                    Ok(None)
                 }
-            } else {
-                None
-            };
+            }
+
+            /// A body without a declared schema, e.g. multipart fields, which are usually
+            /// read one by one rather than declared up front
+            fn unstructured_body(body_type: BodyType) -> Body {
+                Body {
+                    body_type,
+                    identifier: None,
+                    shape: BodyShape::Single,
+                    properties: HashMap::new(),
+                    required_fields: vec![],
+                    alternatives: vec![],
+                }
+            }
+
+            /// Adds the other content types `body` is accepted in to `resolved`. Structured
+            /// ones share its schema, since the same struct is read from each of them.
+            fn with_alternatives(
+                mut resolved: Body,
+                body: &IRBody,
+                warnings: &mut Vec<String>,
+            ) -> Body {
+                for content_type in &body.additional_content_types {
+                    let Some(body_type) = body_type(content_type) else {
+                        warnings.push(format!("unsupported body content type `{content_type}`"));
+                        continue;
+                    };
+                    if body_type == resolved.body_type
+                        || resolved
+                            .alternatives
+                            .iter()
+                            .any(|a| a.body_type == body_type)
+                    {
+                        continue;
+                    }
+
+                    let alternative = match body_type {
+                        BodyType::Multipart | BodyType::Binary => unstructured_body(body_type),
+                        BodyType::Json | BodyType::Form | BodyType::Xml => Body {
+                            body_type,
+                            alternatives: vec![],
+                            ..resolved.clone()
+                        },
+                    };
+                    resolved.alternatives.push(alternative);
+                }
+                resolved
+            }
+
+            struct HandlerInfo {
+                parameters: Vec<Parameter>,
+                body: Option<Body>,
+                response: Option<Body>,
+                response_status: Option<u16>,
+                error_responses: Vec<ErrorResponse>,
+                requires_auth: bool,
+                summary: Option<String>,
+                /// unresolved items, reported against every route using the handler
+                warnings: Vec<String>,
+            }
+
+            let mut handler_infos: HashMap<String, HandlerInfo> = HashMap::new();
+
+            for handler in response.handlers {
+                let mut warnings = Vec::new();
+                let mut parameters =
+                    build_parameters(handler.parameters, &mut warnings, |data_type| {
+                        find_unit_enum(data_type, handler_file, &file_content, manifest, files)
+                    });
+                let defaults = find_query_defaults(
+                    &handler.function_name,
+                    handler_file,
+                    &file_content,
+                    manifest,
+                    files,
+                );
+                for param in &mut parameters {
+                    if let ir::ParamType::Query = param.param_type {
+                        param.default = defaults.get(&param.name).cloned();
+                    }
+                }
+
+                let body = match &handler.body {
+                    Some(body) => {
+                        resolve_body(
+                            body,
+                            handler_file,
+                            base_dir,
+                            manifest,
+                            prompts,
+                            backend,
+                            files,
+                            logger,
+                            &mut warnings,
+                        )
+                        .await?
+                    }
+                    None => None,
+                };
+                let response_body = match &handler.response {
+                    Some(response) => {
+                        resolve_body(
+                            response,
+                            handler_file,
+                            base_dir,
+                            manifest,
+                            prompts,
+                            backend,
+                            files,
+                            logger,
+                            &mut warnings,
+                        )
+                        .await?
+                    }
+                    None => None,
+                };
+
+                // read from the code when it's spelled out, the LLM's reading otherwise
+                let response_status =
+                    success_status(&file_content, &handler.function_name).or(handler.status);
+                let error_responses = find_error_responses(
+                    &handler.function_name,
+                    handler_file,
+                    &file_content,
+                    manifest,
+                    files,
+                );
+
+                handler_infos.insert(
+                    handler.function_name,
+                    HandlerInfo {
+                        parameters,
+                        body,
+                        response: response_body,
+                        response_status,
+                        error_responses,
+                        requires_auth: handler.requires_auth,
+                        summary: handler.summary.filter(|s| !s.trim().is_empty()),
+                        warnings,
+                    },
+                );
+            }
 
             // let body = retrieve_body_structure_info(&route).await?;
 
-            Ok(Route {
-                path: route.path,
-                method: route.method,
-                parameters,
-                body,
-            })
+            let mut file_routes = Vec::with_capacity(routes.len());
+            for route in routes {
+                // the rest of the file's handlers are still documented when the LLM skips one
+                let Some(info) = handler_infos.get(&route.handler.identifier) else {
+                    logger.warn_route(
+                        &route.path,
+                        format!(
+                            "the LLM returned nothing for {} in {}, the route is left out",
+                            route.handler.identifier,
+                            handler_file.display()
+                        ),
+                    );
+                    continue;
+                };
+
+                for warning in &info.warnings {
+                    logger.warn_route(
+                        &route.path,
+                        format!("{} {}", route.handler.identifier, warning),
+                    );
+                }
+
+                let mut docs = extract_handler_docs(&file_content, &route.handler.identifier);
+                if docs.summary.is_none() {
+                    docs.summary = info.summary.clone();
+                }
+
+                file_routes.push(Route {
+                    parameters: align_path_params(&info.parameters, &route.path),
+                    path: route.path,
+                    method: route.method,
+                    body: info.body.clone(),
+                    response: info.response.clone(),
+                    response_status: info.response_status,
+                    error_responses: info.error_responses.clone(),
+                    security: info.requires_auth.then(|| security_scheme.to_owned()),
+                    docs,
+                    source: find_handler_line(&file_content, &route.handler.identifier).map(
+                        |line| ir::SourceLocation {
+                            file: handler_file.to_path_buf(),
+                            line,
+                        },
+                    ),
+                    handler: Some(ir::Handler {
+                        module: module_path(handler_file, &manifest.root_dir().join("src")),
+                        name: route.handler.identifier,
+                    }),
+                });
+            }
+
+            Ok(file_routes)
         }
 
-        let mut routes = Vec::new();
+        let mut routes_by_file: BTreeMap<PathBuf, Vec<BasicRoute>> = BTreeMap::new();
         for route in basic_routes {
-            routes.push(build_route_info(route, &entry_file).await?);
+            routes_by_file
+                .entry(route.handler.import_path.clone())
+                .or_default()
+                .push(route);
+        }
+
+        let total_routes =
+            routes_by_file.values().map(Vec::len).sum::<usize>() + inline_routes.len();
+
+        if self.args.dry_run {
+            // discovery can't be planned without the LLM, it has to follow the router through the code
+            println!(
+                "router discovery read {} route file(s) with the LLM:",
+                route_files.len()
+            );
+            for route_file in &route_files {
+                println!("  {}", route_file.display());
+            }
+
+            println!("{total_routes} route(s) found:");
+            for (handler_file, file_routes) in &routes_by_file {
+                println!("  {}", handler_file.display());
+                for route in file_routes {
+                    println!(
+                        "    {} {} -> {}",
+                        route.method, route.path, route.handler.identifier
+                    );
+                }
+            }
+            for route in &inline_routes {
+                let handler = match &route.handler.service {
+                    Some(service) => format!("{} service", service.service),
+                    None => "inline closure".to_owned(),
+                };
+                println!(
+                    "  {} {} -> {handler} in {}",
+                    route.method,
+                    route.path,
+                    route.handler.import_path.display()
+                );
+            }
+
+            println!(
+                "analysis would make at least {} LLM call(s), one per handler file, \
+                 plus one per request body type it has to look up",
+                routes_by_file.len()
+            );
+
+            return Ok(ir::IR {
+                routes: vec![],
+                warnings: logger.warnings(),
+                tags: vec![],
+            });
         }
 
+        let inline_routes = inline_routes
+            .into_iter()
+            .filter_map(
+                |route| match (&route.handler.closure, &route.handler.service) {
+                    (Some(closure), _) => {
+                        let closure = closure.clone();
+                        Some(route_from_closure(route, &closure, &logger))
+                    }
+                    (_, Some(service)) => {
+                        let service = service.clone();
+                        Some(route_from_service(route, &service))
+                    }
+                    _ => None,
+                },
+            )
+            .collect::<Vec<Route>>();
+        self.add_partial_routes(&inline_routes);
+
+        let mut analyzed_routes = 0;
+
+        // `buffered` keeps the results in file order even though the calls run concurrently
+        let routes = stream::iter(routes_by_file)
+            .map(|(handler_file, file_routes)| {
+                let (entry_file, manifest, prompts, backend, files, logger) =
+                    (&entry_file, &manifest, &prompts, &backend, &files, &logger);
+                async move {
+                    logger.progress(format!("analyzing {}", handler_file.display()));
+                    build_route_infos_for_file(
+                        &handler_file,
+                        file_routes,
+                        entry_file,
+                        manifest,
+                        &self.args.security_scheme,
+                        prompts,
+                        backend,
+                        files,
+                        self.args.max_tokens_per_call,
+                        self.args.private_handlers,
+                        logger,
+                    )
+                    .await
+                }
+            })
+            .buffered(self.args.concurrency.max(1))
+            .inspect_ok(|file_routes| {
+                for route in file_routes {
+                    analyzed_routes += 1;
+                    logger.progress(format!(
+                        "analyzed route {analyzed_routes}/{total_routes} {} {}",
+                        route.method, route.path
+                    ));
+                }
+
+                self.add_partial_routes(file_routes);
+            })
+            .try_concat()
+            .await?;
+        let mut routes = [routes, inline_routes].concat();
+        if self.args.handler_summaries {
+            describe_handlers(&mut routes, self.args.proxy.as_ref(), &files, &logger).await?;
+        }
+        let tags = module_tags(&routes, &manifest.root_dir().join("src"), &files);
+
         // let mut routes = Vec::new();
         // for route in route_list {
         //     // println!("route={:#?}", route);
@@ -921,7 +3176,7 @@ file_content: {}
         //                 bail!("failed to get route handler {:#?}", route)
         //             };
 
-        //             let ImportPath::Local(import_path) = &import_path else {
+        //             let ImportKind::Local(import_path) = &import_path else {
         //                 bail!("route handler import path is not a local import");
         //             };
 
@@ -952,7 +3207,31 @@ file_content: {}
 
         // unimplemented!()
 
-        Ok(ir::IR { routes })
+        Ok(ir::IR {
+            routes,
+            warnings: logger.warnings(),
+            tags,
+        })
+    }
+
+    fn partial_ir(&self) -> IR {
+        IR {
+            routes: self
+                .partial_routes
+                .lock()
+                .expect("partial routes lock poisoned")
+                .clone(),
+            warnings: self.logger.warnings(),
+            tags: vec![],
+        }
+    }
+
+    fn watch_partial_ir(&self) -> watch::Receiver<usize> {
+        self.partial_count.subscribe()
+    }
+
+    fn usage(&self) -> LLMUsage {
+        *self.usage.lock().expect("usage lock poisoned")
     }
 }
 
@@ -985,3 +3264,608 @@ file_content: {}
 //         IR {}
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_unique_route, align_path_params, base_type_name, body_shape, build_parameters,
+        closure_operation_id, closure_params, constructs_axum_router, detect_entry_fn,
+        dispatch_trait_call, find_error_responses, find_unit_enum, is_axum_router, join_route_path,
+        module_path, module_tags, param_data_type, param_format, prop_type, resolve_import_in_file,
+        router_source, BasicRoute, FileCache, FunctionCallNode, ImportKind, Logger, RouteHandler,
+        TraitImpls, UseAliases, Verbosity, MAX_LOGGER_DEPTH,
+    };
+    use crate::{
+        call_graph::manifest::Manifest,
+        domain::ir::{
+            self, BodyShape, HTTPMethod, ParamDataType, ParamType, Parameter, PropType, Route,
+        },
+        utils::fixtures::CrateFixture,
+    };
+    use indoc::indoc;
+    use std::path::Path;
+
+    #[test]
+    fn expands_use_aliases_and_globs() {
+        let aliases = UseAliases::from_file(
+            "use crate::controllers::channels as ch;\n\
+             use crate::controllers::{users::{self, create as create_user}, health};\n\
+             use crate::handlers::*;\n",
+        );
+
+        assert_eq!(
+            aliases.expand("ch::get_all")[0],
+            "crate::controllers::channels::get_all"
+        );
+        assert_eq!(
+            aliases.expand("users::delete")[0],
+            "crate::controllers::users::delete"
+        );
+        assert_eq!(
+            aliases.expand("create_user")[0],
+            "crate::controllers::users::create"
+        );
+        assert_eq!(
+            aliases.expand("items::get"),
+            vec!["crate::handlers::items::get", "items::get"]
+        );
+        assert_eq!(
+            aliases.expand("crate::health::ping"),
+            vec!["crate::health::ping"]
+        );
+    }
+
+    #[test]
+    fn documents_enum_params_as_constrained_strings() {
+        let params = serde_json::from_str(
+            r#"[
+                {"param_type": "path", "name": "status", "data_type": "OrderStatus", "description": ""},
+                {"param_type": "query", "name": "filter", "data_type": "Filter", "description": ""}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut warnings = Vec::new();
+        let params = build_parameters(params, &mut warnings, |data_type| {
+            (data_type == "OrderStatus").then(|| vec!["pending".to_owned(), "done".to_owned()])
+        });
+
+        assert!(matches!(params[0].data_type, ParamDataType::String));
+        assert_eq!(params[0].enum_values, vec!["pending", "done"]);
+        assert!(matches!(params[1].data_type, ParamDataType::Unknown));
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn splits_tuple_path_extractors() {
+        // what the LLM reports for `Path((id, slug)): Path<(u32, String)>`
+        let params = serde_json::from_str(
+            r#"[
+                {"param_type": "path", "name": "(id, slug)", "data_type": "Path<(u32, String)>", "description": "the item"},
+                {"param_type": "query", "name": "page", "data_type": "u32", "description": ""}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut warnings = Vec::new();
+        let params = build_parameters(params, &mut warnings, |_| None);
+
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[0].name, "id");
+        assert!(matches!(
+            params[0].data_type,
+            ParamDataType::UnsignedInteger
+        ));
+        assert_eq!(params[1].name, "slug");
+        assert!(matches!(params[1].data_type, ParamDataType::String));
+        assert!(warnings.is_empty());
+
+        // the destructured names differ from the route's captures
+        let params = align_path_params(
+            &[
+                Parameter {
+                    name: "item".to_owned(),
+                    ..params[0].clone()
+                },
+                Parameter {
+                    name: "name".to_owned(),
+                    ..params[1].clone()
+                },
+                params[2].clone(),
+            ],
+            "/items/:id/:slug",
+        );
+        let names = params.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["id", "slug", "page"]);
+    }
+
+    #[test]
+    fn describes_tags_with_module_docs() {
+        let krate = CrateFixture::package("api");
+        krate.write(
+            "src/handlers/channels/mod.rs",
+            "//! Channels members post messages to\n\nmod create;\n",
+        );
+        krate.write("src/handlers/users.rs", "pub async fn list() {}\n");
+
+        let route = |module: &str, tags: &[&str]| Route {
+            path: "/".to_owned(),
+            method: HTTPMethod::GET,
+            parameters: vec![],
+            body: None,
+            response: None,
+            response_status: None,
+            error_responses: vec![],
+            security: None,
+            docs: ir::OperationDocs {
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                ..Default::default()
+            },
+            source: None,
+            handler: Some(ir::Handler {
+                name: "handler".to_owned(),
+                module: module.to_owned(),
+            }),
+        };
+        let routes = [
+            route("handlers::channels::create", &["channels", "write"]),
+            route("handlers::users", &["users"]),
+        ];
+
+        assert_eq!(
+            module_tags(&routes, &krate.root().join("src"), &FileCache::default()),
+            vec![ir::Tag {
+                name: "channels".to_owned(),
+                description: Some("Channels members post messages to".to_owned()),
+            }]
+        );
+    }
+
+    #[test]
+    fn derives_module_paths_from_files() {
+        let src = Path::new("/work/api/src");
+        assert_eq!(
+            module_path(&src.join("handlers/channels.rs"), src),
+            "handlers::channels"
+        );
+        assert_eq!(
+            module_path(&src.join("handlers/messages/mod.rs"), src),
+            "handlers::messages"
+        );
+        assert_eq!(module_path(&src.join("main.rs"), src), "crate");
+    }
+
+    #[test]
+    fn reads_each_router_from_the_function_building_it() {
+        let file = indoc! {r#"
+            use axum::{routing::get, Router};
+
+            pub fn public() -> Router {
+                Router::new().route("/health", get(health))
+            }
+
+            pub fn admin() -> Router {
+                Router::new().route("/users", get(list_users))
+            }
+        "#};
+
+        let admin = router_source(file, Some("admin"));
+        assert!(admin.contains("/users"));
+        assert!(!admin.contains("/health"));
+
+        let public = router_source(file, Some("routes::public"));
+        assert!(public.contains("/health"));
+        assert!(!public.contains("/users"));
+
+        assert_eq!(router_source(file, None), file);
+        assert_eq!(router_source(file, Some("missing")), file);
+    }
+
+    #[test]
+    fn joins_nested_route_paths() {
+        assert_eq!(join_route_path("", "/users"), "/users");
+        assert_eq!(join_route_path("", "/"), "/");
+        assert_eq!(join_route_path("/api", "/users"), "/api/users");
+        assert_eq!(join_route_path("/api/", "/users/:id"), "/api/users/:id");
+        assert_eq!(join_route_path("/api", "/"), "/api");
+        assert_eq!(
+            join_route_path(&join_route_path("/api", "/v1"), "/items"),
+            "/api/v1/items"
+        );
+        // prefixes and paths that both carry the slash
+        assert_eq!(join_route_path("/api/", "/v1/"), "/api/v1");
+        assert_eq!(
+            join_route_path(&join_route_path("/api/", "//v1"), "/users/"),
+            "/api/v1/users"
+        );
+        assert_eq!(join_route_path("/", "//health"), "/health");
+    }
+
+    #[test]
+    fn reads_params_from_closure_arguments() {
+        let arguments = [
+            ("Path((id, slug))", "Path<(u32, String)>"),
+            ("State(state)", "State<AppState>"),
+            ("Query(page)", "axum::extract::Query<u64>"),
+        ]
+        .map(|(pattern, rust_type)| (pattern.to_owned(), rust_type.to_owned()));
+
+        let mut warnings = Vec::new();
+        let params = build_parameters(closure_params(&arguments), &mut warnings, |_| None);
+
+        let names = params.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["id", "slug", "page"]);
+        assert!(matches!(params[1].data_type, ParamDataType::String));
+        assert!(matches!(params[2].param_type, ParamType::Query));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn names_closure_operations_after_the_route() {
+        assert_eq!(closure_operation_id(&HTTPMethod::GET, "/ping"), "get_ping");
+        assert_eq!(
+            closure_operation_id(&HTTPMethod::DELETE, "/items/:id"),
+            "delete_items_id"
+        );
+        assert_eq!(closure_operation_id(&HTTPMethod::GET, "/"), "get_root");
+    }
+
+    #[test]
+    fn reads_vec_and_option_body_wrappers() {
+        let cases = [
+            ("CreateItemDto", None, BodyShape::Single),
+            ("CreateItemDto", Some("Vec"), BodyShape::Array),
+            ("CreateItemDto", Some("Option"), BodyShape::Nullable),
+            ("Vec<CreateItemDto>", None, BodyShape::Array),
+            ("Json<Vec<CreateItemDto>>", None, BodyShape::Array),
+            ("Option<CreateItemDto>", None, BodyShape::Nullable),
+        ];
+
+        for (identifier, wrapper, shape) in cases {
+            assert_eq!(
+                body_shape(identifier, wrapper),
+                (shape, "CreateItemDto".to_owned()),
+                "{identifier}"
+            );
+        }
+    }
+
+    #[test]
+    fn drops_framework_extractors_from_params() {
+        // what the LLM reports for
+        // `async fn get(State(state): State<AppState>, Path(id): Path<u32>)`
+        let params = serde_json::from_str(
+            r#"[
+                {"param_type": "state", "name": "state", "data_type": "State<AppState>", "description": "app state"},
+                {"param_type": "path", "name": "id", "data_type": "u32", "description": "the item id"}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut warnings = Vec::new();
+        let params = build_parameters(params, &mut warnings, |_| None);
+
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].name, "id");
+        assert!(matches!(params[0].param_type, ParamType::Path));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn maps_body_field_types() {
+        let cases = [
+            ("String", PropType::String),
+            ("Option<String>", PropType::String),
+            ("u32", PropType::Number),
+            ("Number", PropType::Number),
+            ("Option<bool>", PropType::Boolean),
+            ("Object", PropType::Object),
+            ("AddressDto", PropType::Object),
+        ];
+
+        for (field_type, expected) in cases {
+            assert_eq!(
+                std::mem::discriminant(&prop_type(field_type)),
+                std::mem::discriminant(&expected),
+                "type: {field_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn detects_the_entry_function() {
+        let main = "fn app() -> axum::Router { todo!() }\nfn main() {}";
+        assert_eq!(detect_entry_fn(main), Some(("main".to_owned(), None)));
+
+        let lib = "pub fn helper() {}\npub fn app() -> axum::Router { todo!() }";
+        assert_eq!(detect_entry_fn(lib), Some(("app".to_owned(), None)));
+
+        let method = "impl Application { pub fn routes(state: AppState) -> Router { todo!() } }";
+        assert_eq!(
+            detect_entry_fn(method),
+            Some(("routes".to_owned(), Some("Application".to_owned())))
+        );
+
+        let generic =
+            "pub fn app(state: AppState) -> Router<AppState> { Router::<AppState>::new() }";
+        assert_eq!(detect_entry_fn(generic), Some(("app".to_owned(), None)));
+
+        assert_eq!(detect_entry_fn("pub fn helper() {}"), None);
+    }
+
+    #[test]
+    fn warns_when_another_handler_registers_a_route_again() {
+        let route = |path: &str, handler: &str, file: &str| BasicRoute {
+            path: path.to_owned(),
+            method: HTTPMethod::GET,
+            handler: RouteHandler {
+                identifier: handler.to_owned(),
+                method_of: None,
+                import_path: file.into(),
+                closure: None,
+                service: None,
+            },
+        };
+        let logger = Logger::new().with_verbosity(Verbosity::Quiet);
+
+        let mut routes = Vec::new();
+        add_unique_route(
+            &mut routes,
+            route("/users", "list", "src/users.rs"),
+            &logger,
+        );
+        // the same router reached a second time
+        add_unique_route(
+            &mut routes,
+            route("/users", "list", "src/users.rs"),
+            &logger,
+        );
+        assert!(logger.warnings().is_empty());
+
+        add_unique_route(&mut routes, route("/users", "all", "src/admin.rs"), &logger);
+        add_unique_route(&mut routes, route("/admin", "all", "src/admin.rs"), &logger);
+        assert_eq!(
+            routes.iter().map(|r| r.path.as_str()).collect::<Vec<_>>(),
+            vec!["/users", "/admin"]
+        );
+        let warnings = logger.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].route.as_deref(), Some("/users"));
+        assert_eq!(
+            warnings[0].message,
+            "GET /users is registered by both list in src/users.rs and all in src/admin.rs, \
+             only the first is documented"
+        );
+    }
+
+    #[test]
+    fn dispatches_calls_through_crate_traits_to_each_impl() {
+        let mut trait_impls = TraitImpls::default();
+        trait_impls.add_file(
+            Path::new("src/lib.rs"),
+            "pub trait Routable { fn routes() -> Router; }",
+        );
+        trait_impls.add_file(
+            Path::new("src/users.rs"),
+            "impl Routable for Users { fn routes() -> Router { Router::new() } }\n\
+             impl Default for Users { fn default() -> Self { Users } }",
+        );
+        let node = |callee: &str, associated_struct: Option<&str>, import_path| FunctionCallNode {
+            caller: Some("app".to_owned()),
+            callee: callee.to_owned(),
+            associated_struct: associated_struct.map(str::to_owned),
+            module: None,
+            import_path,
+            arguments: vec![],
+        };
+        let logger = Logger::new().with_verbosity(Verbosity::Quiet);
+        let dispatched = |node| {
+            dispatch_trait_call(node, &trait_impls, &logger)
+                .into_iter()
+                .map(|n| (n.associated_struct, n.import_path))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            dispatched(node("routes", Some("Routable"), ImportKind::Unknown)),
+            vec![(
+                Some("Users".to_owned()),
+                ImportKind::Local("src/users.rs".into())
+            )]
+        );
+        // calls on an unknown receiver, or through traits from outside the crate, are left be
+        assert_eq!(
+            dispatched(node("routes", None, ImportKind::Unknown)),
+            vec![(None, ImportKind::Unknown)]
+        );
+        assert_eq!(
+            dispatched(node("default", Some("Default"), ImportKind::Unknown)),
+            vec![(Some("Default".to_owned()), ImportKind::Unknown)]
+        );
+    }
+
+    #[test]
+    fn recognizes_the_router_with_generic_state() {
+        for router in [
+            "axum::Router",
+            "axum::Router<AppState>",
+            "axum::Router::<AppState>",
+            "axum::routing::Router<Arc<AppState>>",
+        ] {
+            assert!(is_axum_router(router), "{router}");
+        }
+        assert!(!is_axum_router("crate::Router"));
+        assert!(!is_axum_router("axum::routing::get"));
+
+        assert_eq!(base_type_name("Router::<AppState>"), "Router");
+        assert_eq!(base_type_name("chrono::DateTime<Utc>"), "DateTime");
+    }
+
+    #[test]
+    fn spots_router_construction_with_generics_on_either_field() {
+        let node = |callee: &str, module: &str, associated_struct: Option<&str>| FunctionCallNode {
+            caller: Some("app".to_owned()),
+            callee: callee.to_owned(),
+            associated_struct: associated_struct.map(str::to_owned),
+            module: Some(module.to_owned()),
+            import_path: ImportKind::External(module.to_owned()),
+            arguments: vec![],
+        };
+
+        for (module, associated_struct) in [
+            ("axum::Router", Some("Router")),
+            ("axum::Router::<AppState>", Some("Router")),
+            ("axum::Router", Some("Router::<AppState>")),
+            ("axum::routing::Router<AppState>", Some("Router<AppState>")),
+        ] {
+            let router = node("new", module, associated_struct);
+            assert!(constructs_axum_router(&router), "{router:?}");
+        }
+        for (callee, module, associated_struct) in [
+            ("route", "axum::Router::<AppState>", Some("Router")),
+            ("new", "crate::Router", Some("Router")),
+            ("new", "axum::Router::<AppState>", None),
+        ] {
+            let other = node(callee, module, associated_struct);
+            assert!(!constructs_axum_router(&other), "{other:?}");
+        }
+    }
+
+    #[test]
+    fn stops_levelling_up_at_the_depth_cap() {
+        let mut logger = Logger::new().with_verbosity(Verbosity::Quiet);
+        for _ in 0..MAX_LOGGER_DEPTH {
+            logger = logger.level_up().expect("below the depth cap");
+        }
+
+        assert!(logger.level_up().is_none());
+        assert_eq!(logger.warnings().len(), 1);
+
+        let logger = Logger::new()
+            .with_verbosity(Verbosity::Quiet)
+            .with_max_depth(1);
+        let child = logger.level_up().expect("below the depth cap");
+        assert!(child.level_up().is_none());
+        assert_eq!(
+            logger.warnings()[0].message,
+            "call chain is deeper than 1 levels, not following it further"
+        );
+    }
+
+    #[test]
+    fn maps_every_integer_primitive() {
+        for ty in ["i8", "i16", "i32", "i64", "i128", "isize"] {
+            assert!(
+                matches!(param_data_type(ty), ParamDataType::Integer),
+                "type: {ty}"
+            );
+        }
+
+        for ty in ["u8", "u16", "u32", "u64", "u128", "usize"] {
+            assert!(
+                matches!(param_data_type(ty), ParamDataType::UnsignedInteger),
+                "type: {ty}"
+            );
+        }
+
+        assert!(matches!(param_data_type("f64"), ParamDataType::Float));
+        assert!(matches!(param_data_type("Vec<u8>"), ParamDataType::Unknown));
+    }
+
+    #[test]
+    fn hints_formats_for_common_types() {
+        let cases = [
+            ("Uuid", Some("uuid")),
+            ("uuid::Uuid", Some("uuid")),
+            ("DateTime<Utc>", Some("date-time")),
+            ("chrono::NaiveDateTime", Some("date-time")),
+            ("NaiveDate", Some("date")),
+            ("i64", Some("int64")),
+            ("u32", Some("int32")),
+            ("f64", Some("double")),
+            ("String", None),
+            ("u8", None),
+        ];
+
+        for (ty, expected) in cases {
+            assert_eq!(param_format(ty).as_deref(), expected, "type: {ty}");
+        }
+        assert!(matches!(
+            param_data_type("DateTime<Utc>"),
+            ParamDataType::String
+        ));
+    }
+
+    #[test]
+    fn finds_types_imported_relative_to_the_handlers_module() {
+        let krate = CrateFixture::package("api");
+        krate.write("src/main.rs", "mod handlers;\nfn main() {}\n");
+        krate.write("src/handlers/mod.rs", "mod models;\nmod orders;\n");
+        krate.write(
+            "src/handlers/models.rs",
+            indoc! {"
+                pub enum OrderStatus { Open, Shipped }
+
+                pub enum ApiError { NotFound }
+
+                impl IntoResponse for ApiError {
+                    fn into_response(self) -> Response {
+                        match self {
+                            Self::NotFound => StatusCode::NOT_FOUND.into_response(),
+                        }
+                    }
+                }
+            "},
+        );
+        let orders = indoc! {"
+            use super::models::{ApiError, OrderStatus};
+
+            pub async fn get(Path(id): Path<u32>) -> Result<Json<Order>, ApiError> {
+                todo!()
+            }
+        "};
+        let handler_file = krate.write("src/handlers/orders.rs", orders);
+        let manifest = Manifest::try_new(&krate.root()).unwrap();
+        let files = FileCache::default();
+
+        assert_eq!(
+            find_unit_enum("OrderStatus", &handler_file, orders, &manifest, &files),
+            Some(vec!["Open".to_owned(), "Shipped".to_owned()])
+        );
+        assert_eq!(
+            find_error_responses("get", &handler_file, orders, &manifest, &files),
+            vec![ir::ErrorResponse {
+                status: 404,
+                variants: vec!["ApiError::NotFound".to_owned()],
+            }]
+        );
+    }
+
+    #[test]
+    fn resolves_bare_names_to_the_glob_defining_them() {
+        let krate = CrateFixture::package("api");
+        let dto = krate.write("src/dto.rs", "pub struct CreateUser {}\n");
+        let handlers = krate.write(
+            "src/handlers.rs",
+            "pub async fn create() {}\npub async fn list() {}\n",
+        );
+
+        let src = krate.root().join("src");
+        let manifest = Manifest::try_new(&krate.root()).unwrap();
+        let routes = "use crate::dto::*;\nuse crate::handlers::{list as list_users, *};\n";
+        let resolve = |import: &str| match resolve_import_in_file(
+            import,
+            routes,
+            &manifest,
+            &src,
+            &Logger::new(),
+        ) {
+            ImportKind::Local(path) => path,
+            other => panic!("{import} resolved to {other}"),
+        };
+
+        assert_eq!(resolve("create"), handlers);
+        assert_eq!(resolve("list_users"), handlers);
+        assert_eq!(resolve("CreateUser"), dto);
+    }
+}
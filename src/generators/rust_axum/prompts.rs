@@ -1,17 +1,145 @@
+use anyhow::{bail, Context};
+use std::{fs::read_to_string, path::Path};
+
+pub const ROUTE_LIST_EXTRACT_PROMPT: &'static str = r##"
+You are a Rust axum framework documentation assistant.
+You will be given the contents of a rust file. Return a json object containing an array of all the axum routes defined according to the file. Each route should contain the path and a methods array.
+A single route can register more than one method, either by chaining method routers (e.g get(handler_a).post(handler_b)) or with separate .route calls on the same path. Include one object in the methods array for every method registered on the path, containing the method, the name of its handler and the import statement for the handler (i.e import path to handler definition).
+If the method router is built by calling a function or referencing a const instead (e.g .route("/users", user_routes()) or .route("/health", HEALTH)), return an empty methods array and a method_router object containing the function (or const) name and the import path to its definition.
+Services mounted with .route_service(...), .nest_service(...) or .fallback_service(...) (e.g ServeDir::new("assets")) are not routes either, never include them in the routes array.
+Routers combined into this one with .merge(...) or .nest("/prefix", ...) are not routes. Return them in a separate sub_routers array, in the order they appear, with the kind (merge or nest), the prefix (null for merge), the name of the function building the sub router and the import path to its definition.
+
+Example 1.
+Input:
+Router::new()
+    .route("/", get(controllers::root))
+    .route("/items", get(items::get_all).post(items::create))
+
+Output:
+{
+"routes": [
+    {
+        "path": "/",
+        "methods": [
+            {
+                "method": "GET",
+                "handler": "controllers::root",
+                "module": "crate::controllers::root"
+            }
+        ]
+    },
+    {
+        "path": "/items",
+        "methods": [
+            {
+                "method": "GET",
+                "handler": "items::get_all",
+                "module": "crate::controllers::items::get_all"
+            },
+            {
+                "method": "POST",
+                "handler": "items::create",
+                "module": "crate::controllers::items::create"
+            }
+        ]
+    }
+]
+}
+
+Example 2.
+Input:
+use crate::routes::users::user_routes;
+
+Router::new()
+    .route("/users", user_routes())
+
+Output:
+{
+"routes": [
+    {
+        "path": "/users",
+        "methods": [],
+        "method_router": {
+            "function": "user_routes",
+            "module": "crate::routes::users::user_routes"
+        }
+    }
+]
+}
+
+Example 3.
+Input:
+use crate::{admin::admin_router, health::health_router};
+
+Router::new()
+    .route("/", get(controllers::root))
+    .merge(health_router())
+    .nest("/admin", admin_router())
+
+Output:
+{
+"routes": [
+    {
+        "path": "/",
+        "methods": [
+            {
+                "method": "GET",
+                "handler": "controllers::root",
+                "module": "crate::controllers::root"
+            }
+        ]
+    }
+],
+"sub_routers": [
+    {
+        "kind": "merge",
+        "prefix": null,
+        "function": "health_router",
+        "module": "crate::health::health_router"
+    },
+    {
+        "kind": "nest",
+        "prefix": "/admin",
+        "function": "admin_router",
+        "module": "crate::admin::admin_router"
+    }
+]
+}
+"##;
+
 pub const BODY_EXTRACT_PROMPT: &'static str = r##"
 You are a Rust axum framework documentation assistant.
-You will be given the contents of a rust file (in between ### <file content> ###), a function name (that could optionally include a struct name prepended to it, e.g Struct::method_name). 
-The function is a axum route handler that we're trying to extract parameter information from so that we can use the information to build a open api parameters array and requestBody object.
-Return a json object containing:
+You will be given the contents of a rust file (in between ### <file content> ###), and a comma separated list of function names (each could optionally include a struct name prepended to it, e.g Struct::method_name). 
+Each function is a axum route handler that we're trying to extract parameter information from so that we can use the information to build a open api parameters array and requestBody object.
+Return a json object containing a handlers array with one object per function name. Each object should contain:
+0. a function_name property whose value is the function name exactly as it was given to you
 1. a parameters array, which object in the array containing what type of parameter it is (e.g path, query, e.tc), the name of the parameter, a description of the parameter (based on its usage through the file) and the data_type of the parameter. If you cannot find any parameters, return an empty array
 2. a body object that includes the content_type (e.g application/json, application/octet-stream e.tc), and if content_type is json, form-data or any other structured type, include a structure property which is a map of field names to an object containing their type and if they are required, if it doesnt have a content-type with structure, return null for structure. If you cannot figure out the structure of the body because the struct definition is not in the current file sent to you, include a property module in the body whose value is to the import path of the struct definition. If it doesnt have any body, return null. and return an identifier property which is the name of the struct of the body object
+If the body wraps the struct in a Vec or an Option (e.g Json<Vec<CreateItemDto>> or Json<Option<CreateItemDto>>), the identifier is still the struct name (CreateItemDto) and include a wrapper property of Vec or Option, otherwise return null for wrapper.
+Handlers taking the Multipart extractor have a content_type of multipart/form-data, and handlers taking the raw body (Bytes, Body, axum::body::Body e.tc) have a content_type of application/octet-stream. For both, return null for structure and module, and the extractor name as the identifier.
+Handlers taking the Form extractor (e.g Form<LoginForm>) have a content_type of application/x-www-form-urlencoded, and their structure, module and identifier are given just like a json body.
+If the handler accepts the body in more than one content type, e.g a custom extractor that reads either json or a form depending on the Content-Type header, give the main one as content_type and include an additional_content_types array with the others, otherwise return an empty array for additional_content_types.
+3. a requires_auth boolean that is true when the handler requires an authenticated caller, e.g it takes an authentication extractor (AuthUser, Claims, TypedHeader<Authorization<Bearer>> e.tc) or its body rejects requests without valid credentials
+4. a summary string, one short line in the imperative describing what the handler does (e.g Create a payment), based on its body
+5. a response object describing the body of the handler's successful response, in the same shape as the body object, e.g a handler returning Json<Vec<ItemDto>> has a content_type of application/json, an identifier of ItemDto and a wrapper of Vec. Return null if the handler responds without a body or you cannot tell what it responds with.
+When the handler returns impl IntoResponse (or Response, or a Result of either), its signature doesn't tell the payload, so read the expressions it returns on success instead, including inside Ok(..): returning Json(user) where user is a UserDto responds with UserDto, and returning (StatusCode::CREATED, Json(item)) responds with the type of item. Skip the expressions returned on errors.
+6. a status integer, the HTTP status of the handler's successful response when the handler sets one, e.g 201 for (StatusCode::CREATED, Json(item)) or 204 for StatusCode::NO_CONTENT, otherwise null.
+If the handler negotiates its response on the Accept header (e.g it reads the Accept header and responds with json or xml depending on it), give application/json as the response content_type and include the other content types it can respond with (e.g application/xml) in the response's additional_content_types array. When it doesn't branch on Accept, return an empty array for additional_content_types.
+Headers the handler reads are parameters with a param_type of header, named after the HTTP header: TypedHeader<UserAgent> is User-Agent, and headers.get("x-request-id") on a HeaderMap is x-request-id. Their data_type is String unless the handler parses the value into another type.
+A tuple path extractor such as Path((id, slug)): Path<(u32, String)> is one path parameter per tuple element, in order: id with a data_type of u32 and slug with a data_type of String.
+A wildcard segment (e.g /files/*rest) is a path parameter named after the wildcard (rest) with a data_type of String. The RawQuery extractor reads the raw query string instead of named parameters, never include it in the parameters array.
+Framework extractors that don't come from the request (State, Extension, ConnectInfo, DatabaseConnection e.tc) are not parameters, never include them in the parameters array.
+Keep the rust field names as the keys of the structure map. If a field has a serde rename attribute (e.g #[serde(rename = "userName")]), include a serialized_name property on that field with the renamed value. If the struct has a serde rename_all attribute (e.g #[serde(rename_all = "camelCase")]), include a rename_all property on the body object with the rule exactly as written, otherwise return null for rename_all.
+Fields wrapped in Option, fields marked #[serde(default)] and every field of a struct marked #[serde(default)] are not required; include "default": true on fields that have a serde default. If a field's type is another struct (e.g address: AddressDto), use Object as its type and include an identifier property with the struct name. If that struct is defined in the file, also include its structure (in the same shape as above, with its own rename_all), otherwise include a module property whose value is the import path of the struct definition.
 
 
 Example 1. 
 Input: 
-function_name: add_item_to_collection
+function_names: add_item_to_collection, get_collection
 file_content:
 ###
+use crate::dto::CollectionItemDto;
+
 pub struct RequestPayloadDto {
     name: String,
     description: String,
@@ -20,42 +148,84 @@ pub struct RequestPayloadDto {
 
 pub async fn add_item_to_collection(
     State(state): State<AppState>,
+    user: AuthUser,
     Path(collection_id): Path<String>,
     Json(payload): Json<RequestPayloadDto>,
 ) -> Result<Json, CollectionError> {
     // skipping the code here for brevity
 }
+
+pub async fn get_collection(
+    State(state): State<AppState>,
+    Path(collection_id): Path<String>,
+) -> Result<Json<Vec<CollectionItemDto>>, CollectionError> {
+    // skipping the code here for brevity
+}
 ###
 
 Output:
 {
-"parameters": [
+"handlers": [
     {
-        "param_type": "path",
-        "name": "collection_id",
-        "data_type": "String",
-        "description": "The id of the collection to add the item"
-    }
-],
-"body": {
-    "content_type": "application/json",
-    "structure": {
-        "name": {
-            "type": "String",
-            "required": true
+        "function_name": "add_item_to_collection",
+        "parameters": [
+            {
+                "param_type": "path",
+                "name": "collection_id",
+                "data_type": "String",
+                "description": "The id of the collection to add the item"
+            }
+        ],
+        "body": {
+            "content_type": "application/json",
+            "structure": {
+                "name": {
+                    "type": "String",
+                    "required": true
+                },
+                "description": {
+                    "type": "String",
+                    "required": true
+                },
+                "amount": {
+                    "type": "u32",
+                    "required": false
+                }
+            },
+            "rename_all": null,
+            "module": null,
+            "identifier": "RequestPayloadDto",
+            "wrapper": null
         },
-        "description": {
-            "type": "String",
-            "required": true
-        },
-        "amount": {
-            "type": "u32",
-            "required": false
-        }
+        "response": null,
+        "status": null,
+        "requires_auth": true,
+        "summary": "Create a payment"
     },
-    "module": null,
-    "identifier": "RequestPayloadDto"
-}
+    {
+        "function_name": "get_collection",
+        "parameters": [
+            {
+                "param_type": "path",
+                "name": "collection_id",
+                "data_type": "String",
+                "description": "The id of the collection to fetch"
+            }
+        ],
+        "body": null,
+        "response": {
+            "content_type": "application/json",
+            "structure": null,
+            "rename_all": null,
+            "module": "crate::dto::CollectionItemDto",
+            "identifier": "CollectionItemDto",
+            "wrapper": "Vec"
+        },
+        "status": null,
+        "requires_auth": false,
+        "summary": "Get a collection"
+    }
+]
 }
         
 "##;
@@ -65,6 +235,8 @@ You are a Rust axum framework documentation assistant.
 You will be given the contents of a rust file (in between ### <file content> ###), a identifier (that could optionally include a module name prepended to it, e.g some_module::StructName). 
 The identifier is a axum route handler body deserialization struct or enum that we're trying to extract the structural/model information from so that we can use the information to build a open api requestBody object.
 Do your best to understand the deserialization format and use information around the struct to give the best output
+Keep the rust field names as the keys of the structure map. If a field has a serde rename attribute (e.g #[serde(rename = "userName")]), include a serialized_name property on that field with the renamed value. If the struct or enum has a serde rename_all attribute (e.g #[serde(rename_all = "camelCase")]), include a top level rename_all property with the rule exactly as written, otherwise return null for rename_all.
+Fields wrapped in Option, fields marked #[serde(default)] and every field of a struct marked #[serde(default)] are not required; include "default": true on fields that have a serde default. If a field's type is another struct (e.g address: AddressDto), use Object as its type and include an identifier property with the struct name. If that struct is defined in the file, also include its structure (in the same shape as above, with its own rename_all), otherwise include a module property whose value is the import path of the struct definition.
 
 Example 1. 
 Input: 
@@ -127,4 +299,169 @@ Output:
     }
 }
  
+
+Example 3. 
+Input: 
+struct_name: CreateUserDto
+file_content:
+###
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateUserDto {
+    first_name: String,
+    #[serde(rename = "mail")]
+    email_address: String,
+}
+###
+
+Output:
+{
+    "structure": {
+        "first_name": {
+            "type": "String",
+            "required": true
+        },
+        "email_address": {
+            "type": "String",
+            "required": true,
+            "serialized_name": "mail"
+        }
+    },
+    "rename_all": "camelCase"
+}
+
+
+Example 4.
+Input:
+struct_name: CreateProfileDto
+file_content:
+###
+use crate::dto::PhoneDto;
+
+#[derive(Deserialize)]
+pub struct AddressDto {
+    city: String,
+    #[serde(default)]
+    country: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateProfileDto {
+    bio: Option<String>,
+    address: AddressDto,
+    phone: PhoneDto,
+}
+###
+
+Output:
+{
+    "structure": {
+        "bio": {
+            "type": "String",
+            "required": false
+        },
+        "address": {
+            "type": "Object",
+            "required": true,
+            "identifier": "AddressDto",
+            "structure": {
+                "city": {
+                    "type": "String",
+                    "required": true
+                },
+                "country": {
+                    "type": "String",
+                    "required": false,
+                    "default": true
+                }
+            },
+            "rename_all": null
+        },
+        "phone": {
+            "type": "Object",
+            "required": true,
+            "identifier": "PhoneDto",
+            "module": "crate::dto::PhoneDto"
+        }
+    },
+    "rename_all": null
+}
+
 "##;
+
+/// Appended to the body prompts when examples are requested, as they cost extra tokens
+const EXAMPLES_PROMPT: &str = r##"
+Also include an example property on every parameter and on every field of a structure, whose value is a realistic example of that type (e.g 42, "jane@example.com", true, or an array of values for a tuple). Return null for example if you cannot come up with one.
+"##;
+
+/// The prompts sent for route and body extraction, which power users can override
+/// without recompiling
+#[derive(Debug, Clone)]
+pub struct Prompts {
+    pub route_extract: String,
+    pub body_extract: String,
+    pub body_outer_extract: String,
+}
+
+impl Default for Prompts {
+    fn default() -> Self {
+        Self {
+            route_extract: ROUTE_LIST_EXTRACT_PROMPT.to_owned(),
+            body_extract: BODY_EXTRACT_PROMPT.to_owned(),
+            body_outer_extract: BODY_OUTER_EXTRACT_PROMPT.to_owned(),
+        }
+    }
+}
+
+impl Prompts {
+    /// Reads `route_extract.txt`, `body_extract.txt` and `body_outer_extract.txt` from `dir`,
+    /// keeping the built-in prompt for any of them that isn't there
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        if !dir.is_dir() {
+            bail!("prompt directory {} does not exist", dir.display());
+        }
+
+        let read = |file_name: &str, built_in: &str| -> anyhow::Result<String> {
+            let file = dir.join(file_name);
+            match file.is_file() {
+                true => read_to_string(&file)
+                    .context(format!("failed to read prompt from {}", file.display())),
+                false => Ok(built_in.to_owned()),
+            }
+        };
+
+        Ok(Self {
+            route_extract: read("route_extract.txt", ROUTE_LIST_EXTRACT_PROMPT)?,
+            body_extract: read("body_extract.txt", BODY_EXTRACT_PROMPT)?,
+            body_outer_extract: read("body_outer_extract.txt", BODY_OUTER_EXTRACT_PROMPT)?,
+        })
+    }
+
+    /// Asks for an example value of every parameter and body field along with its type
+    pub fn with_examples(mut self) -> Self {
+        self.body_extract.push_str(EXAMPLES_PROMPT);
+        self.body_outer_extract.push_str(EXAMPLES_PROMPT);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Prompts, BODY_EXTRACT_PROMPT, BODY_OUTER_EXTRACT_PROMPT};
+    use std::fs;
+
+    #[test]
+    fn overrides_only_the_prompts_provided() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("route_extract.txt"), "list the routes").unwrap();
+
+        let prompts = Prompts::load(dir.path()).unwrap();
+        assert_eq!(prompts.route_extract, "list the routes");
+        assert_eq!(prompts.body_extract, BODY_EXTRACT_PROMPT);
+        assert_eq!(prompts.body_outer_extract, BODY_OUTER_EXTRACT_PROMPT);
+
+        let path = dir.path().to_path_buf();
+        dir.close().unwrap();
+        assert!(Prompts::load(&path).is_err());
+    }
+}
@@ -0,0 +1,467 @@
+use super::docs::handler_source;
+use crate::domain::ir::ErrorResponse;
+use std::collections::{BTreeMap, HashSet};
+use syn::{
+    visit::Visit, Expr, ExprMatch, ExprPath, ExprTuple, GenericArgument, Item, ItemFn, Pat,
+    PathArguments, ReturnType, Stmt, Type,
+};
+
+/// The success status `handler` responds with, read from the `StatusCode` constant it
+/// returns, e.g. 201 for `(StatusCode::CREATED, Json(user))` or 204 for a bare
+/// `StatusCode::NO_CONTENT`. Handlers returning `impl IntoResponse` name their status this
+/// way rather than in their signature. Error statuses are skipped, and `None` is returned
+/// when no success status is set.
+pub fn success_status(file_content: &str, handler: &str) -> Option<u16> {
+    // methods parse as free functions too, receiver included
+    let handler: ItemFn = syn::parse_str(&handler_source(file_content, handler)?).ok()?;
+
+    let mut visitor = StatusVisitor { statuses: vec![] };
+    visitor.visit_block(&handler.block);
+    // a status returned without a body isn't in a tuple
+    let tail = returned_expr(handler.block.stmts.last());
+    visitor.statuses.extend(tail.and_then(status_code));
+
+    visitor
+        .statuses
+        .into_iter()
+        .find(|status| (200..300).contains(status))
+}
+
+/// The error type of a handler returning `Result<T, E>`, as written, e.g. `ApiError` or
+/// `crate::errors::ApiError`
+pub fn error_type(file_content: &str, handler: &str) -> Option<String> {
+    let handler: ItemFn = syn::parse_str(&handler_source(file_content, handler)?).ok()?;
+    let ReturnType::Type(_, ty) = &handler.sig.output else {
+        return None;
+    };
+    let Type::Path(ty) = ty.as_ref() else {
+        return None;
+    };
+    let result = ty.path.segments.last()?;
+    if result.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &result.arguments else {
+        return None;
+    };
+    let Some(GenericArgument::Type(Type::Path(error))) = args.args.get(1) else {
+        return None;
+    };
+
+    let segments = error
+        .path
+        .segments
+        .iter()
+        .map(|s| s.ident.to_string())
+        .collect::<Vec<_>>();
+    Some(segments.join("::"))
+}
+
+/// The error statuses the `IntoResponse` impl of `name` maps its variants to, read from the
+/// arms of the `match` in its impls, e.g. `ApiError::NotFound => StatusCode::NOT_FOUND`.
+/// When `name` is an enum of `file_content`, only its variants are listed. `None` when
+/// `file_content` has no `IntoResponse` impl of `name`.
+pub fn error_statuses(file_content: &str, name: &str) -> Option<Vec<ErrorResponse>> {
+    let file = syn::parse_file(file_content).ok()?;
+    let impls = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Impl(block) => match block.self_ty.as_ref() {
+                Type::Path(ty) if ty.path.segments.last()?.ident == name => Some(block),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let into_response = impls.iter().any(|block| {
+        block
+            .trait_
+            .as_ref()
+            .and_then(|(_, path, _)| path.segments.last())
+            .is_some_and(|t| t.ident == "IntoResponse")
+    });
+    if !into_response {
+        return None;
+    }
+
+    let enum_variants = file.items.iter().find_map(|item| match item {
+        Item::Enum(e) if e.ident == name => {
+            Some(e.variants.iter().map(|v| v.ident.to_string()).collect())
+        }
+        _ => None,
+    });
+
+    // the status is often picked in an inherent method, e.g. `fn status(&self) -> StatusCode`
+    let mut visitor = ErrorArmVisitor {
+        name,
+        enum_variants,
+        statuses: BTreeMap::new(),
+    };
+    for block in impls {
+        visitor.visit_item_impl(block);
+    }
+
+    Some(
+        visitor
+            .statuses
+            .into_iter()
+            .map(|(status, variants)| ErrorResponse { status, variants })
+            .collect(),
+    )
+}
+
+/// The expression a block ends with, unwrapped from `Ok(..)`
+fn returned_expr(stmt: Option<&Stmt>) -> Option<&Expr> {
+    match stmt? {
+        Stmt::Expr(expr, None) => Some(unwrap_ok(expr)),
+        _ => None,
+    }
+}
+
+fn unwrap_ok(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Call(call) if call.args.len() == 1 => match call.func.as_ref() {
+            Expr::Path(func) if func.path.is_ident("Ok") => unwrap_ok(&call.args[0]),
+            _ => expr,
+        },
+        Expr::Paren(paren) => unwrap_ok(&paren.expr),
+        _ => expr,
+    }
+}
+
+/// The code of a `StatusCode::NAME` expression
+fn status_code(expr: &Expr) -> Option<u16> {
+    match expr {
+        Expr::Path(path) => status_path_code(path),
+        _ => None,
+    }
+}
+
+fn status_path_code(path: &ExprPath) -> Option<u16> {
+    let segments = path.path.segments.iter().collect::<Vec<_>>();
+    let [.., ty, name] = segments.as_slice() else {
+        return None;
+    };
+    if ty.ident != "StatusCode" {
+        return None;
+    }
+
+    let code = match name.ident.to_string().as_str() {
+        "OK" => 200,
+        "CREATED" => 201,
+        "ACCEPTED" => 202,
+        "NON_AUTHORITATIVE_INFORMATION" => 203,
+        "NO_CONTENT" => 204,
+        "RESET_CONTENT" => 205,
+        "PARTIAL_CONTENT" => 206,
+        "MULTI_STATUS" => 207,
+        "ALREADY_REPORTED" => 208,
+        "IM_USED" => 226,
+        "BAD_REQUEST" => 400,
+        "UNAUTHORIZED" => 401,
+        "PAYMENT_REQUIRED" => 402,
+        "FORBIDDEN" => 403,
+        "NOT_FOUND" => 404,
+        "METHOD_NOT_ALLOWED" => 405,
+        "NOT_ACCEPTABLE" => 406,
+        "PROXY_AUTHENTICATION_REQUIRED" => 407,
+        "REQUEST_TIMEOUT" => 408,
+        "CONFLICT" => 409,
+        "GONE" => 410,
+        "LENGTH_REQUIRED" => 411,
+        "PRECONDITION_FAILED" => 412,
+        "PAYLOAD_TOO_LARGE" => 413,
+        "URI_TOO_LONG" => 414,
+        "UNSUPPORTED_MEDIA_TYPE" => 415,
+        "RANGE_NOT_SATISFIABLE" => 416,
+        "EXPECTATION_FAILED" => 417,
+        "IM_A_TEAPOT" => 418,
+        "MISDIRECTED_REQUEST" => 421,
+        "UNPROCESSABLE_ENTITY" => 422,
+        "LOCKED" => 423,
+        "FAILED_DEPENDENCY" => 424,
+        "UPGRADE_REQUIRED" => 426,
+        "PRECONDITION_REQUIRED" => 428,
+        "TOO_MANY_REQUESTS" => 429,
+        "REQUEST_HEADER_FIELDS_TOO_LARGE" => 431,
+        "UNAVAILABLE_FOR_LEGAL_REASONS" => 451,
+        "INTERNAL_SERVER_ERROR" => 500,
+        "NOT_IMPLEMENTED" => 501,
+        "BAD_GATEWAY" => 502,
+        "SERVICE_UNAVAILABLE" => 503,
+        "GATEWAY_TIMEOUT" => 504,
+        "HTTP_VERSION_NOT_SUPPORTED" => 505,
+        "VARIANT_ALSO_NEGOTIATES" => 506,
+        "INSUFFICIENT_STORAGE" => 507,
+        "LOOP_DETECTED" => 508,
+        "NOT_EXTENDED" => 510,
+        "NETWORK_AUTHENTICATION_REQUIRED" => 511,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Collects the status of every `(StatusCode::NAME, ..)` tuple, in the order they appear
+struct StatusVisitor {
+    statuses: Vec<u16>,
+}
+
+impl<'ast> Visit<'ast> for StatusVisitor {
+    fn visit_expr_tuple(&mut self, node: &'ast ExprTuple) {
+        syn::visit::visit_expr_tuple(self, node);
+
+        if let Some(status) = node.elems.first().and_then(status_code) {
+            self.statuses.push(status);
+        }
+    }
+}
+
+/// Collects the error status of every arm matching a variant of the error type `name`,
+/// from the first `match` of each function
+struct ErrorArmVisitor<'a> {
+    name: &'a str,
+    /// the variants of `name`, when it's an enum of the file
+    enum_variants: Option<HashSet<String>>,
+    statuses: BTreeMap<u16, Vec<String>>,
+}
+
+impl<'ast> Visit<'ast> for ErrorArmVisitor<'_> {
+    fn visit_expr_match(&mut self, node: &'ast ExprMatch) {
+        for arm in &node.arms {
+            let mut variants = Vec::new();
+            pat_variants(&arm.pat, self, &mut variants);
+            if variants.is_empty() && !is_catch_all(&arm.pat) {
+                continue;
+            }
+
+            let mut first = FirstStatusVisitor { status: None };
+            first.visit_expr(&arm.body);
+            let Some(status) = first.status.filter(|status| *status >= 400) else {
+                continue;
+            };
+            let listed = self.statuses.entry(status).or_default();
+            for variant in variants {
+                if !listed.contains(&variant) {
+                    listed.push(variant);
+                }
+            }
+        }
+        // arms are matched once, a nested `match` matches something else
+    }
+}
+
+/// The variants of the error type a pattern matches, e.g. `ApiError::NotFound` for
+/// `Self::NotFound | ..`. Paths into other types are skipped, and so are bare names, e.g.
+/// `NotFound` brought in by a `use ApiError::*`, unless they're variants of its enum.
+fn pat_variants(pat: &Pat, error: &ErrorArmVisitor, variants: &mut Vec<String>) {
+    let is_variant = |variant: &str| {
+        error
+            .enum_variants
+            .as_ref()
+            .is_some_and(|known| known.contains(variant))
+    };
+    let path = match pat {
+        Pat::Or(or) => {
+            for case in &or.cases {
+                pat_variants(case, error, variants);
+            }
+            return;
+        }
+        Pat::Reference(reference) => return pat_variants(&reference.pat, error, variants),
+        Pat::Ident(ident) => {
+            match &ident.subpat {
+                Some((_, subpat)) => pat_variants(subpat, error, variants),
+                None if is_variant(&ident.ident.to_string()) => {
+                    variants.push(format!("{}::{}", error.name, ident.ident));
+                }
+                None => {}
+            }
+            return;
+        }
+        Pat::Path(path) => &path.path,
+        Pat::TupleStruct(tuple) => &tuple.path,
+        Pat::Struct(s) => &s.path,
+        _ => return,
+    };
+
+    let segments = path.segments.iter().collect::<Vec<_>>();
+    let variant = match segments.as_slice() {
+        [variant] if is_variant(&variant.ident.to_string()) => variant,
+        [.., ty, variant]
+            if (ty.ident == "Self" || ty.ident == error.name)
+                && (error.enum_variants.is_none() || is_variant(&variant.ident.to_string())) =>
+        {
+            variant
+        }
+        _ => return,
+    };
+    variants.push(format!("{}::{}", error.name, variant.ident));
+}
+
+/// Whether an arm matches whatever is left, with `_` or a binding such as `e`. Bindings
+/// are lowercase, unlike unit variants and constants, which parse the same.
+fn is_catch_all(pat: &Pat) -> bool {
+    match pat {
+        Pat::Wild(_) => true,
+        Pat::Ident(ident) => {
+            ident.subpat.is_none()
+                && ident
+                    .ident
+                    .to_string()
+                    .starts_with(|c: char| c == '_' || c.is_lowercase())
+        }
+        _ => false,
+    }
+}
+
+struct FirstStatusVisitor {
+    status: Option<u16>,
+}
+
+impl<'ast> Visit<'ast> for FirstStatusVisitor {
+    fn visit_expr_path(&mut self, node: &'ast ExprPath) {
+        if self.status.is_none() {
+            self.status = status_path_code(node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error_statuses, error_type, success_status};
+    use crate::domain::ir::ErrorResponse;
+    use indoc::indoc;
+
+    #[test]
+    fn reads_the_status_returned_by_the_handler() {
+        let file = indoc! {r#"
+            pub async fn create(Json(payload): Json<CreateUser>) -> impl IntoResponse {
+                if payload.name.is_empty() {
+                    return (StatusCode::BAD_REQUEST, Json(json!({ "error": "name is required" })))
+                        .into_response();
+                }
+                let user = User::new(payload.name);
+                (StatusCode::CREATED, Json(user)).into_response()
+            }
+
+            pub async fn delete(Path(id): Path<u32>) -> Result<impl IntoResponse, AppError> {
+                User::delete(id).await?;
+                Ok(StatusCode::NO_CONTENT)
+            }
+
+            pub async fn list() -> impl IntoResponse {
+                Json(User::all().await)
+            }
+        "#};
+
+        assert_eq!(success_status(file, "create"), Some(201));
+        assert_eq!(success_status(file, "delete"), Some(204));
+        assert_eq!(success_status(file, "list"), None);
+        assert_eq!(success_status(file, "missing"), None);
+    }
+
+    #[test]
+    fn maps_error_variants_to_their_statuses() {
+        let handlers = indoc! {r#"
+            pub async fn get(Path(id): Path<u32>) -> Result<Json<User>, errors::ApiError> {
+                Ok(Json(User::find(id).await?))
+            }
+
+            pub async fn list() -> Json<Vec<User>> {
+                Json(User::all().await)
+            }
+        "#};
+        let errors = indoc! {r#"
+            pub enum ApiError {
+                NotFound,
+                Unauthorized,
+                Conflict { field: String },
+                Database(sqlx::Error),
+                Internal(anyhow::Error),
+            }
+
+            impl ApiError {
+                fn status(&self) -> StatusCode {
+                    match self {
+                        Self::NotFound => StatusCode::NOT_FOUND,
+                        ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+                        Self::Conflict { .. } => StatusCode::CONFLICT,
+                        Self::Database(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+                        Self::Database(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                    }
+                }
+            }
+
+            impl IntoResponse for ApiError {
+                fn into_response(self) -> Response {
+                    (self.status(), Json(json!({ "error": self.to_string() }))).into_response()
+                }
+            }
+        "#};
+
+        assert_eq!(
+            error_type(handlers, "get"),
+            Some("errors::ApiError".to_owned())
+        );
+        assert_eq!(error_type(handlers, "list"), None);
+
+        let response = |status, variants: &[&str]| ErrorResponse {
+            status,
+            variants: variants.iter().map(|v| v.to_string()).collect(),
+        };
+        assert_eq!(
+            error_statuses(errors, "ApiError"),
+            Some(vec![
+                response(401, &["ApiError::Unauthorized"]),
+                response(404, &["ApiError::NotFound", "ApiError::Database"]),
+                response(409, &["ApiError::Conflict"]),
+                response(500, &["ApiError::Database", "ApiError::Internal"]),
+            ])
+        );
+        assert_eq!(error_statuses(handlers, "ApiError"), None);
+    }
+
+    #[test]
+    fn lists_only_variants_of_the_error_enum() {
+        let errors = indoc! {r#"
+            use AppError::*;
+
+            const TEAPOT: u16 = 418;
+
+            pub enum AppError {
+                NotFound,
+                Forbidden(String),
+                Other(anyhow::Error),
+            }
+
+            impl IntoResponse for AppError {
+                fn into_response(self) -> Response {
+                    let status = match self {
+                        NotFound => StatusCode::NOT_FOUND,
+                        Forbidden(_) => StatusCode::FORBIDDEN,
+                        Self::Gone => StatusCode::GONE,
+                        None => StatusCode::BAD_REQUEST,
+                        TEAPOT => StatusCode::IM_A_TEAPOT,
+                        e => StatusCode::INTERNAL_SERVER_ERROR,
+                    };
+                    status.into_response()
+                }
+            }
+        "#};
+
+        let response = |status, variants: &[&str]| ErrorResponse {
+            status,
+            variants: variants.iter().map(|v| v.to_string()).collect(),
+        };
+        assert_eq!(
+            error_statuses(errors, "AppError"),
+            Some(vec![
+                response(403, &["AppError::Forbidden"]),
+                response(404, &["AppError::NotFound"]),
+                // the catch-all arm, whichever variants are left
+                response(500, &[]),
+            ])
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use syn::{visit::Visit, Expr, ExprMethodCall, Lit};
+
+/// A tower service mounted on the router instead of a handler, e.g.
+///
+/// ```text
+/// .nest_service("/assets", ServeDir::new("assets"))
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteService {
+    pub path: String,
+    /// name of the service type, e.g. `ServeDir`
+    pub service: String,
+    /// mounted with `nest_service`, so it also serves everything below `path`
+    pub nested: bool,
+    /// 1-based line of the `route_service` or `nest_service` call
+    pub line: usize,
+}
+
+/// Every service mounted with `.route_service()` or `.nest_service()` in `file_content`,
+/// in the order they appear. Files that can't be parsed have none.
+pub fn route_services(file_content: &str) -> Vec<RouteService> {
+    let Ok(file) = syn::parse_file(file_content) else {
+        return vec![];
+    };
+
+    let mut visitor = RouteServiceVisitor { services: vec![] };
+    visitor.visit_file(&file);
+    visitor.services
+}
+
+struct RouteServiceVisitor {
+    services: Vec<RouteService>,
+}
+
+impl<'ast> Visit<'ast> for RouteServiceVisitor {
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        syn::visit::visit_expr_method_call(self, node);
+
+        let nested = match node.method.to_string().as_str() {
+            "route_service" => false,
+            "nest_service" => true,
+            _ => return,
+        };
+        let (Some(Expr::Lit(path)), Some(service)) = (node.args.first(), node.args.get(1)) else {
+            return;
+        };
+        let Lit::Str(path) = &path.lit else {
+            return;
+        };
+
+        self.services.push(RouteService {
+            path: path.value(),
+            service: service_name(service).unwrap_or_else(|| "service".to_owned()),
+            nested,
+            line: node.method.span().start().line,
+        });
+    }
+}
+
+/// The type a service expression builds, e.g. `ServeDir` for
+/// `ServeDir::new("assets").not_found_service(..)`
+fn service_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::MethodCall(call) => service_name(&call.receiver),
+        Expr::Call(call) => service_name(&call.func),
+        Expr::Path(path) => {
+            let segments = &path.path.segments;
+            // `ServeDir::new` names the type, a bare `serve_assets` names a function
+            let segment = match segments.len() {
+                0 => return None,
+                1 => &segments[0],
+                n => &segments[n - 2],
+            };
+            Some(segment.ident.to_string())
+        }
+        Expr::Paren(paren) => service_name(&paren.expr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{route_services, RouteService};
+    use indoc::indoc;
+
+    #[test]
+    fn finds_services_mounted_on_the_router() {
+        let file = indoc! {r#"
+            pub fn app() -> Router {
+                Router::new()
+                    .route("/users", get(users::list))
+                    .route_service("/favicon.ico", ServeFile::new("assets/favicon.ico"))
+                    .nest_service(
+                        "/assets",
+                        ServeDir::new("assets").not_found_service(ServeFile::new("404.html")),
+                    )
+                    .fallback_service(ServeDir::new("public"))
+            }
+        "#};
+
+        assert_eq!(
+            route_services(file),
+            vec![
+                RouteService {
+                    path: "/favicon.ico".to_owned(),
+                    service: "ServeFile".to_owned(),
+                    nested: false,
+                    line: 4,
+                },
+                RouteService {
+                    path: "/assets".to_owned(),
+                    service: "ServeDir".to_owned(),
+                    nested: true,
+                    line: 5,
+                },
+            ]
+        );
+    }
+}
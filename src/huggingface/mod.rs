@@ -1,5 +1,12 @@
+use crate::llm::retry::RetryPolicy;
+use anyhow::{bail, Context};
 use derive_builder::Builder;
-use reqwest::header::{self, AUTHORIZATION};
+use reqwest::{
+    header::{self, AUTHORIZATION},
+    StatusCode,
+};
+use serde::Deserialize;
+use std::time::Duration;
 
 pub mod task;
 
@@ -7,20 +14,91 @@ pub mod task;
 #[builder(setter(into))]
 pub struct HFClientConfig {
     access_token: String,
+    /// timeout for a single request, including reading the response
+    #[builder(default = Duration::from_secs(120))]
+    timeout: Duration,
+    /// idle connections kept open to the inference router
+    #[builder(default = 4)]
+    max_idle_connections: usize,
+    /// retries for 5xx responses, timeouts and models that are still loading
+    #[builder(default)]
+    retry_policy: RetryPolicy,
+    /// proxy for every request, e.g. `http://proxy.internal:3128`. Without it the
+    /// `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY` environment variables are honoured.
+    #[builder(default)]
+    proxy: Option<String>,
 }
 
 pub struct HFClient {
     client: reqwest::Client,
+    retry_policy: RetryPolicy,
+}
+
+/// Body of the 503 returned while the inference router spins a model up
+#[derive(Deserialize)]
+struct ModelLoading {
+    error: String,
+    estimated_time: f64,
 }
 
 impl HFClient {
-    pub fn new(config: HFClientConfig) -> Self {
+    /// Fails when `config.proxy` isn't a valid proxy url
+    pub fn new(config: HFClientConfig) -> anyhow::Result<Self> {
         let headers = Self::get_default_headers(&config);
-        let client = reqwest::Client::builder()
+        let mut builder = reqwest::Client::builder()
             .default_headers(headers)
-            .build()
-            .expect("failed to create reqwest client");
-        Self { client }
+            .timeout(config.timeout)
+            .pool_max_idle_per_host(config.max_idle_connections);
+        if let Some(proxy) = &config.proxy {
+            let proxy = reqwest::Proxy::all(proxy).context(format!("invalid proxy url {proxy}"))?;
+            builder = builder.proxy(proxy);
+        }
+        let client = builder.build().context("failed to create reqwest client")?;
+        Ok(Self {
+            client,
+            retry_policy: config.retry_policy,
+        })
+    }
+
+    /// Posts `body` to the inference endpoint of `model` and returns the response text.
+    /// Timeouts, connection failures and 5xx responses are retried according to the
+    /// retry policy; a model that is still loading is waited on for its estimated time.
+    async fn post_to_model(&self, model: &str, body: String) -> anyhow::Result<String> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .client
+                .post(self.get_inference_url_for_model(model))
+                .body(body.clone())
+                .send()
+                .await;
+
+            let delay = match result {
+                Ok(res) if res.status().is_success() => return Ok(res.text().await?),
+                Ok(res) => {
+                    let status = res.status();
+                    let text = res.text().await?;
+                    if !status.is_server_error() || attempt >= self.retry_policy.max_retries {
+                        bail!("{model} request failed with {status}: {text}")
+                    }
+
+                    match model_loading_delay(status, &text) {
+                        Some(delay) => delay,
+                        None => self.retry_policy.backoff(attempt),
+                    }
+                }
+                Err(e)
+                    if (e.is_timeout() || e.is_connect())
+                        && attempt < self.retry_policy.max_retries =>
+                {
+                    self.retry_policy.backoff(attempt)
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
     fn get_inference_url_for_model(&self, model: &str) -> String {
@@ -37,3 +115,61 @@ impl HFClient {
         headers
     }
 }
+
+/// How long to wait for a model that is still loading, as estimated by the router
+fn model_loading_delay(status: StatusCode, body: &str) -> Option<Duration> {
+    if status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+
+    let loading = serde_json::from_str::<ModelLoading>(body).ok()?;
+    if !loading.error.contains("is currently loading") {
+        return None;
+    }
+
+    Duration::try_from_secs_f64(loading.estimated_time).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{model_loading_delay, HFClient, HFClientConfigBuilder};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[test]
+    fn waits_for_loading_models() {
+        let loading =
+            r#"{"error":"Model google/gemma-2-2b-it is currently loading","estimated_time":20.5}"#;
+
+        assert_eq!(
+            model_loading_delay(StatusCode::SERVICE_UNAVAILABLE, loading),
+            Some(Duration::from_secs_f64(20.5))
+        );
+        assert_eq!(
+            model_loading_delay(StatusCode::INTERNAL_SERVER_ERROR, loading),
+            None
+        );
+        assert_eq!(
+            model_loading_delay(StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable"),
+            None
+        );
+    }
+
+    #[test]
+    fn fails_on_an_invalid_proxy_instead_of_panicking() {
+        let client = |proxy: &str| {
+            HFClient::new(
+                HFClientConfigBuilder::default()
+                    .access_token("hf_test")
+                    .proxy(Some(proxy.to_owned()))
+                    .build()
+                    .unwrap(),
+            )
+        };
+
+        assert!(client("http://proxy.internal:3128").is_ok());
+        assert!(client("http://proxy internal")
+            .err()
+            .is_some_and(|e| e.to_string().contains("invalid proxy url")));
+    }
+}
@@ -1,36 +1,68 @@
 use crate::huggingface::HFClient;
-// use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use derive_builder::Builder;
 use serde::Deserialize;
+use serde_json::json;
 
-// const CODE_SUMMARIZER_MODEL: &'static str = "Qwen/Qwen2.5-Coder-32B-Instruct";
+const CODE_SUMMARIZER_MODEL: &str = "Qwen/Qwen2.5-Coder-32B-Instruct";
 
 #[derive(Builder, Default)]
+#[builder(setter(into))]
 pub struct SummarizeCodeOptions {
-    // code: String,
+    code: String,
+    /// longest summary to generate, in tokens
+    #[builder(default = 120)]
+    max_new_tokens: usize,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct SummarizeCodeResponse {}
+#[derive(Debug, Clone)]
+pub struct SummarizeCodeResponse {
+    pub summary: String,
+}
+
+#[derive(Deserialize)]
+struct GeneratedText {
+    generated_text: String,
+}
 
 impl HFClient {
+    /// Describes what `opts.code` does in a sentence or two of prose
     pub async fn summarize_code(
         &self,
-        // opts: SummarizeCodeOptions,
+        opts: SummarizeCodeOptions,
     ) -> anyhow::Result<SummarizeCodeResponse> {
-        // let res = self
-        //     .client
-        //     .post(self.get_inference_url_for_model(CODE_SUMMARIZER_MODEL))
-        //     .body(json!({ "inputs": opts.inputs.to_owned() }).to_string())
-        //     .send()
-        //     .await?
-        //     .text()
-        //     .await?;
-
-        // let res = serde_json::from_str::<Vec<SummarizeCodeResponse>>(&res);
-        // let res = res.context("failed to deserialize response into `TextGeneratorResponse`")?;
-        // Ok(res.get(0).ok_or(anyhow!("failed to get response"))?.clone())
-        unimplemented!()
+        let inputs = format!(
+            "Describe what the following Rust function does in one or two plain sentences, \
+             without mentioning Rust or repeating the code.\n\n{}\n\nDescription:",
+            opts.code
+        );
+        let res = self
+            .post_to_model(
+                CODE_SUMMARIZER_MODEL,
+                json!({
+                    "inputs": inputs,
+                    "parameters": {
+                        "max_new_tokens": opts.max_new_tokens,
+                        "return_full_text": false,
+                    },
+                })
+                .to_string(),
+            )
+            .await?;
+
+        let res = serde_json::from_str::<Vec<GeneratedText>>(&res)
+            .context("failed to deserialize response into `GeneratedText`")?;
+        let summary = res
+            .first()
+            .ok_or(anyhow!("failed to get response"))?
+            .generated_text
+            .trim()
+            .to_owned();
+        if summary.is_empty() {
+            bail!("{CODE_SUMMARIZER_MODEL} returned an empty summary");
+        }
+
+        Ok(SummarizeCodeResponse { summary })
     }
 }
 
@@ -49,6 +81,7 @@ mod tests {
         // let client = HFClient::new(config);
 
         // let opts = SummarizeCodeOptionsBuilder::default()
+        //     .code("fn add(a: i32, b: i32) -> i32 { a + b }")
         //     .build()
         //     .expect("failed to create Summarize code options");
 
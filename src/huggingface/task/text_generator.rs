@@ -23,12 +23,10 @@ impl HFClient {
         opts: TextGeneratorOptions,
     ) -> anyhow::Result<TextGeneratorResponse> {
         let res = self
-            .client
-            .post(self.get_inference_url_for_model(TEXT_GENERATOR_MODEL))
-            .body(json!({ "inputs": opts.inputs.to_owned() }).to_string())
-            .send()
-            .await?
-            .text()
+            .post_to_model(
+                TEXT_GENERATOR_MODEL,
+                json!({ "inputs": opts.inputs.to_owned() }).to_string(),
+            )
             .await?;
 
         let res = serde_json::from_str::<Vec<TextGeneratorResponse>>(&res);
@@ -49,7 +47,7 @@ mod tests {
             .build()
             .expect("failed to create HFCLient config");
 
-        let client = HFClient::new(config);
+        let client = HFClient::new(config).expect("failed to create HFClient");
 
         let opts = TextGeneratorOptionsBuilder::default()
             .inputs("The definition of machine learning inference is ")
@@ -2,7 +2,12 @@ pub mod call_graph;
 pub mod cli;
 pub mod code;
 pub mod domain;
+pub mod error;
 pub mod generators;
 pub mod huggingface;
 pub mod llm;
+pub mod openapi;
 pub mod utils;
+
+pub use error::DocgenError;
+pub use openapi::{build_spec, SpecOptions};
@@ -1,3 +1,4 @@
+use crate::error::DocgenError;
 use async_trait::async_trait;
 use serde::Serialize;
 
@@ -37,6 +38,20 @@ pub struct LLMQueryRequest {
     pub history: LLMHistory,
 }
 
+/// Requests made to an LLM and the tokens they used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LLMUsage {
+    pub calls: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl LLMUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
 #[derive(Debug)]
 pub struct LLMQueryResponse {
     pub text: String,
@@ -49,5 +64,8 @@ where
 {
     fn model(&self) -> String;
     fn role(&self) -> String;
-    async fn execute_query(&mut self, q: LLMQueryRequest) -> anyhow::Result<LLMQueryResponse>;
+    async fn execute_query(&mut self, q: LLMQueryRequest) -> Result<LLMQueryResponse, DocgenError>;
+    /// Forgets the earlier queries and responses, keeping only the system prompt, so the
+    /// next query starts from a fresh context
+    fn reset_history(&mut self);
 }
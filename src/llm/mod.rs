@@ -1,3 +1,7 @@
 pub mod llm;
 pub mod openai;
-pub use llm::{IntoLLMHistory, LLMHistory, LLMMessage, LLMQueryRequest, LLMQueryResponse, LLM};
+pub mod repair;
+pub mod retry;
+pub use llm::{
+    IntoLLMHistory, LLMHistory, LLMMessage, LLMQueryRequest, LLMQueryResponse, LLMUsage, LLM,
+};
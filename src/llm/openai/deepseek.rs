@@ -1,28 +1,101 @@
-use crate::llm::{LLMMessage, LLMQueryRequest, LLMQueryResponse, LLM};
-use anyhow::Context;
+use crate::error::DocgenError;
+use crate::llm::{
+    repair::{is_truncated_json, COMPACT_JSON_PROMPT, CONTINUE_JSON_PROMPT, MAX_CONTINUATIONS},
+    retry::{parse_retry_after, retry_rate_limited, Attempt, OnRateLimited, RetryPolicy},
+    LLMMessage, LLMQueryRequest, LLMQueryResponse, LLMUsage, LLM,
+};
+use anyhow::anyhow;
 use async_trait::async_trait;
 use deepseek_rs::{
+    client::client::ClientInitErrors,
+    errors::request_errors::RequestErrors,
     request::{Message, RequestBody, ResponseFormat, ResponseFormatType, Role, Temperature},
     DeepSeekClient,
 };
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Environment variable the DeepSeek client reads its API key from
+pub const API_KEY_VAR: &str = "DEEP_SEEK_API_KEY";
+
+/// A DeepSeek API client created once per run and shared by all its conversations,
+/// which may run concurrently
+#[derive(Clone)]
+pub struct DeepseekBackend {
+    client: Arc<DeepSeekClient>,
+    retry_policy: RetryPolicy,
+    usage: Arc<Mutex<LLMUsage>>,
+    on_rate_limited: Option<OnRateLimited>,
+}
+
+impl DeepseekBackend {
+    /// Fails with `DocgenError::MissingApiKey` when `DEEP_SEEK_API_KEY` isn't set
+    pub fn try_new() -> Result<Self, DocgenError> {
+        let client = DeepSeekClient::default().map_err(|e| match e {
+            ClientInitErrors::DeepSeekApiKeyNotSet(_) => DocgenError::MissingApiKey(API_KEY_VAR),
+            e => DocgenError::Other(e.into()),
+        })?;
+
+        Ok(Self {
+            client: Arc::new(client),
+            retry_policy: RetryPolicy::default(),
+            usage: Arc::new(Mutex::new(LLMUsage::default())),
+            on_rate_limited: None,
+        })
+    }
+
+    /// Counts the requests of every conversation into `usage`, e.g. one owned by whoever
+    /// reports on the run
+    pub fn with_usage(mut self, usage: Arc<Mutex<LLMUsage>>) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// The requests made by all conversations so far
+    pub fn usage(&self) -> LLMUsage {
+        *self.usage.lock().expect("usage lock poisoned")
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Reports the waits on rate limits of every conversation through `on_rate_limited`
+    pub fn with_on_rate_limited(mut self, on_rate_limited: OnRateLimited) -> Self {
+        self.on_rate_limited = Some(on_rate_limited);
+        self
+    }
+
+    /// Starts a conversation with `prompt` as its system message
+    pub fn conversation(&self, prompt: &str) -> Deepseek {
+        Deepseek {
+            history: vec![Deepseek::build_prompt(prompt)],
+            client: Arc::clone(&self.client),
+            retry_policy: self.retry_policy.clone(),
+            usage: Arc::clone(&self.usage),
+            on_rate_limited: self.on_rate_limited.clone(),
+        }
+    }
+}
 
 pub struct Deepseek {
     history: Vec<LLMMessage>,
-    client: DeepSeekClient,
+    client: Arc<DeepSeekClient>,
+    retry_policy: RetryPolicy,
+    usage: Arc<Mutex<LLMUsage>>,
+    on_rate_limited: Option<OnRateLimited>,
 }
 
 impl Deepseek {
-    pub fn new(prompt: &str) -> Self {
-        let history = {
-            let mut h = Vec::new();
-            h.push(Self::build_prompt(&prompt));
-            h
-        };
+    /// A conversation over a client of its own, see `DeepseekBackend` to share one
+    pub fn try_new(prompt: &str) -> Result<Self, DocgenError> {
+        Ok(DeepseekBackend::try_new()?.conversation(prompt))
+    }
 
-        Self {
-            history,
-            client: DeepSeekClient::default().unwrap(),
-        }
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 
     fn build_prompt(prompt: &str) -> LLMMessage {
@@ -39,7 +112,7 @@ impl Deepseek {
         }
     }
 
-    async fn execute(&mut self) -> anyhow::Result<String> {
+    async fn execute(&mut self, format: ResponseFormatType) -> anyhow::Result<String> {
         let messages = self
             .history
             .iter()
@@ -58,14 +131,35 @@ impl Deepseek {
             .with_messages(messages)
             .with_model(deepseek_rs::request::Model::DeepseekChat)
             .with_temperature(Temperature::new(0.0))
-            .with_response_format(ResponseFormat::new(ResponseFormatType::Json));
+            .with_response_format(ResponseFormat::new(format));
 
-        //::new_messages(vec![Message::new_user_message("Hello".to_string())]);
-        let result = self
-            .client
-            .chat_completions(request)
-            .await
-            .context("failed to execute")?;
+        let client = &self.client;
+        let on_rate_limited = |delay: Duration| {
+            if let Some(report) = &self.on_rate_limited {
+                report(delay)
+            }
+        };
+        let result = retry_rate_limited(&self.retry_policy, on_rate_limited, || {
+            let request = request.clone();
+            async move {
+                match client.chat_completions(request).await {
+                    Ok(result) => Attempt::Done(Ok(result)),
+                    Err(RequestErrors::RateLimitExceeded(message)) => Attempt::RateLimited {
+                        retry_after: parse_retry_after(&message),
+                        message,
+                    },
+                    Err(e) => Attempt::Done(Err(anyhow!("failed to execute: {e}"))),
+                }
+            }
+        })
+        .await?;
+
+        {
+            let mut usage = self.usage.lock().expect("usage lock poisoned");
+            usage.calls += 1;
+            usage.prompt_tokens += result.usage.prompt_tokens.max(0) as u64;
+            usage.completion_tokens += result.usage.completion_tokens.max(0) as u64;
+        }
 
         let content = result
             .choices
@@ -94,9 +188,53 @@ impl LLM for Deepseek {
         "deepseek-reasoner".to_owned()
     }
 
-    async fn execute_query(&mut self, req: LLMQueryRequest) -> anyhow::Result<LLMQueryResponse> {
+    async fn execute_query(
+        &mut self,
+        req: LLMQueryRequest,
+    ) -> Result<LLMQueryResponse, DocgenError> {
         self.history.push(self.create_user_message(&req.query));
-        let text = self.execute().await?;
+        let exchange_start = self.history.len();
+        let mut text = self
+            .execute(ResponseFormatType::Json)
+            .await
+            .map_err(|e| DocgenError::LlmRequestFailed(e.to_string()))?;
+
+        // a response cut off by the output token limit is continued, in plain text since the
+        // rest of it isn't valid JSON on its own, and asked for again in a compact form if
+        // that doesn't finish it
+        let mut continuations = 0;
+        while is_truncated_json(&text) && continuations < MAX_CONTINUATIONS {
+            self.history
+                .push(self.create_user_message(CONTINUE_JSON_PROMPT));
+            let rest = self
+                .execute(ResponseFormatType::Text)
+                .await
+                .map_err(|e| DocgenError::LlmRequestFailed(e.to_string()))?;
+            text.push_str(&rest);
+            continuations += 1;
+        }
+        if is_truncated_json(&text) {
+            self.history
+                .push(self.create_user_message(COMPACT_JSON_PROMPT));
+            text = self
+                .execute(ResponseFormatType::Json)
+                .await
+                .map_err(|e| DocgenError::LlmRequestFailed(e.to_string()))?;
+        }
+
+        // follow-up queries see the repaired response as a single answer
+        if self.history.len() > exchange_start + 1 {
+            self.history.truncate(exchange_start);
+            self.history.push(LLMMessage {
+                role: "assistant".to_owned(),
+                content: text.clone(),
+            });
+        }
+
         Ok(LLMQueryResponse { text })
     }
-}
\ No newline at end of file
+
+    fn reset_history(&mut self) {
+        self.history.retain(|m| m.role == "system");
+    }
+}
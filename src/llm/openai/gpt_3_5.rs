@@ -1,4 +1,8 @@
-use crate::llm::{LLMQueryRequest, LLMQueryResponse, LLM};
+use crate::error::DocgenError;
+use crate::llm::{
+    retry::{parse_retry_after, retry_rate_limited, Attempt, OnRateLimited, RetryPolicy},
+    LLMQueryRequest, LLMQueryResponse, LLM,
+};
 use anyhow::anyhow;
 use async_trait::async_trait;
 use derive_builder::Builder;
@@ -9,6 +13,7 @@ use openai::{
     },
     Credentials,
 };
+use std::time::Duration;
 
 #[derive(Default, Builder)]
 #[builder(setter(into))]
@@ -17,15 +22,31 @@ pub struct GPT3_5Options {
     pub temperature: Option<f32>,
     #[builder(default = None)]
     pub prompt: Option<String>,
+    #[builder(default)]
+    pub retry_policy: RetryPolicy,
+    /// where the waits on rate limits are reported
+    #[builder(default)]
+    pub on_rate_limited: Option<OnRateLimited>,
 }
 
+/// Environment variable the OpenAI client reads its API key from
+pub const API_KEY_VAR: &str = "OPENAI_KEY";
+
 pub struct GPT3_5 {
     history: Vec<ChatCompletionMessage>,
     credentials: Credentials,
+    retry_policy: RetryPolicy,
+    on_rate_limited: Option<OnRateLimited>,
 }
 
 impl GPT3_5 {
-    pub fn new(options: GPT3_5Options) -> Self {
+    /// Fails with `DocgenError::MissingApiKey` when `OPENAI_KEY` isn't set, which
+    /// `Credentials::from_env` would panic on
+    pub fn try_new(options: GPT3_5Options) -> Result<Self, DocgenError> {
+        if std::env::var(API_KEY_VAR).is_err() {
+            return Err(DocgenError::MissingApiKey(API_KEY_VAR));
+        }
+
         let history = {
             let mut h = Vec::new();
             let prompt = options
@@ -35,10 +56,12 @@ impl GPT3_5 {
             h
         };
 
-        Self {
+        Ok(Self {
             history,
             credentials: Credentials::from_env(),
-        }
+            retry_policy: options.retry_policy,
+            on_rate_limited: options.on_rate_limited,
+        })
     }
 
     fn build_prompt(prompt: &str) -> ChatCompletionMessage {
@@ -58,13 +81,31 @@ impl GPT3_5 {
     }
 
     async fn execute(&self) -> anyhow::Result<String> {
-        let chat_completion = ChatCompletion::builder(&self.model(), self.history.clone())
-            .credentials(self.credentials.clone())
-            .response_format(ChatCompletionResponseFormat::json_object())
-            .top_p(0.2)
-            .create()
-            .await
-            .unwrap();
+        let on_rate_limited = |delay: Duration| {
+            if let Some(report) = &self.on_rate_limited {
+                report(delay)
+            }
+        };
+        let chat_completion = retry_rate_limited(&self.retry_policy, on_rate_limited, || async {
+            let result = ChatCompletion::builder(&self.model(), self.history.clone())
+                .credentials(self.credentials.clone())
+                .response_format(ChatCompletionResponseFormat::json_object())
+                .top_p(0.2)
+                .create()
+                .await;
+
+            match result {
+                Ok(completion) => Attempt::Done(Ok(completion)),
+                Err(e) if e.code.as_deref() == Some("rate_limit_exceeded") => {
+                    Attempt::RateLimited {
+                        retry_after: parse_retry_after(&e.message),
+                        message: e.message,
+                    }
+                }
+                Err(e) => Attempt::Done(Err(anyhow!("failed to execute: {}", e.message))),
+            }
+        })
+        .await?;
 
         if let Some(returned_message) = chat_completion.choices.first() {
             return returned_message
@@ -89,11 +130,22 @@ impl LLM for GPT3_5 {
         "gpt-3.5-turbo".to_owned()
     }
 
-    async fn execute_query(&mut self, req: LLMQueryRequest) -> anyhow::Result<LLMQueryResponse> {
+    async fn execute_query(
+        &mut self,
+        req: LLMQueryRequest,
+    ) -> Result<LLMQueryResponse, DocgenError> {
         self.history.push(self.create_user_message(&req.query));
-        let text = self.execute().await?;
+        let text = self
+            .execute()
+            .await
+            .map_err(|e| DocgenError::LlmRequestFailed(e.to_string()))?;
         Ok(LLMQueryResponse { text })
     }
+
+    fn reset_history(&mut self) {
+        self.history
+            .retain(|m| m.role == ChatCompletionMessageRole::System);
+    }
 }
 
 #[cfg(test)]
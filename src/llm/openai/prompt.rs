@@ -25,7 +25,7 @@ Your task is to perform the following steps:
 3. For Each Function Call, Extract the Following Details:
   - caller: The identifier of the entry function (or, for nested calls, the immediate caller context).
   - callee: The name of the function being called.
-  - associated_struct: The struct type of the callee, if applicable (otherwise null). If the call is a method (e.g. Foo::baz()), and the identifier (e.g. Foo) starts with an uppercase letter, set this field to that struct name; otherwise, use null.
+  - associated_struct: The struct type of the callee, if applicable (otherwise null). If the call is a method (e.g. Foo::baz()), and the identifier (e.g. Foo) starts with an uppercase letter, set this field to that struct name; otherwise, use null. If the method is called through a trait rather than on a known type (e.g. on a `Box<dyn Routable>` or a generic `T: Routable` value), set this field to the trait's name (e.g. Routable).
   - module: Using the use statements from the file content, determine the full module path from which the callee is imported. For example, if there is a statement like use crate::Foo; and the call is Foo::baz(), the module should be crate::Foo. If the function is from the Rust standard library, use its corresponding std path. For calls using Self:: (e.g. Self::another_fn()), "Self::<StructName>" if the function is a method of the same struct.
   - arguments: An array representing the arguments passed to the function:
     - If argument is a function invocation, first build the arguments to the invocation, then build a Function Call object for the invocation itself (Refer to step 3 to building a function call object), and use the type "FunctionCall". i.e for the code: route("/path", get(my_get_handler)), since get call here is an invocation, first build the arguments to get, then build a function call object for get containing its argument, then build route function call and its argument. Sort of a inverted recursion.
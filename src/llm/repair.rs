@@ -0,0 +1,64 @@
+/// Asks for the rest of a response the output token limit cut off
+pub const CONTINUE_JSON_PROMPT: &str = "Your JSON response was cut off. Continue it from exactly \
+where it stopped, without repeating anything you already sent and without any other text.";
+
+/// Asks for a response the output token limit cut off again, in less space
+pub const COMPACT_JSON_PROMPT: &str = "Your JSON response was cut off by the output limit. \
+Respond again with the complete JSON on a single line, with no whitespace between tokens, \
+leaving out optional fields that are null or empty.";
+
+/// How many continuations are requested for a truncated response before asking for a
+/// compact one instead
+pub const MAX_CONTINUATIONS: usize = 2;
+
+/// Whether `text` is the start of a JSON object or array that stops before its braces and
+/// brackets are balanced, which is how a response cut off by the output token limit looks
+pub fn is_truncated_json(text: &str) -> bool {
+    let text = text.trim();
+    if !text.starts_with(['{', '[']) {
+        return false;
+    }
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    in_string || depth > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_truncated_json;
+
+    #[test]
+    fn detects_truncated_json() {
+        assert!(is_truncated_json(r#"{"routes": [{"path": "/users"}, {"pa"#));
+        assert!(is_truncated_json(r#"{"routes": [{"path": "/users"}"#));
+        assert!(is_truncated_json(
+            r#"[{"summary": "braces } in strings \" don't count"#
+        ));
+
+        assert!(!is_truncated_json(r#"{"routes": [{"path": "/{id}"}]}"#));
+        assert!(!is_truncated_json(" [1, 2]\n"));
+        // not JSON at all, so there's nothing to continue
+        assert!(!is_truncated_json("I can't help with that"));
+    }
+}
@@ -0,0 +1,165 @@
+use regex::Regex;
+use std::{future::Future, sync::Arc, time::Duration};
+
+/// Told the wait before each retry of a rate-limited request, for the caller to report it
+pub type OnRateLimited = Arc<dyn Fn(Duration) + Send + Sync>;
+
+/// How many times a rate-limited (HTTP 429) request is retried, and how long to wait
+/// between attempts when the backend gives no hint of its own
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff for the given (zero based) attempt, capped at `max_delay`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+/// Outcome of a single attempt at an LLM request
+pub enum Attempt<T> {
+    Done(anyhow::Result<T>),
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+}
+
+/// Runs `request` until it succeeds, fails with anything other than a rate limit,
+/// or has been rate limited more than `policy.max_retries` times. `on_rate_limited` is
+/// given the wait before each retry, for the caller to report it.
+pub async fn retry_rate_limited<T, N, F, Fut>(
+    policy: &RetryPolicy,
+    mut on_rate_limited: N,
+    mut request: F,
+) -> anyhow::Result<T>
+where
+    N: FnMut(Duration),
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Attempt<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match request().await {
+            Attempt::Done(result) => return result,
+            Attempt::RateLimited { message, .. } if attempt >= policy.max_retries => {
+                anyhow::bail!("rate limited after {} retries: {}", attempt, message)
+            }
+            Attempt::RateLimited { retry_after, .. } => {
+                let delay = retry_after.unwrap_or_else(|| policy.backoff(attempt));
+                on_rate_limited(delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Reads the wait hint out of a rate-limit error message, e.g. `Please try again in 20s`
+/// or `try again in 350ms`. The client crates don't expose the `Retry-After` header itself,
+/// but both backends repeat it in the error body.
+pub fn parse_retry_after(message: &str) -> Option<Duration> {
+    let hint =
+        Regex::new(r"(?i)(?:try again in|retry after)\s+([0-9]+(?:\.[0-9]+)?)\s*(ms|s|seconds?)?")
+            .unwrap();
+    let captures = hint.captures(message)?;
+    let value = captures.get(1)?.as_str().parse::<f64>().ok()?;
+
+    match captures.get(2).map(|u| u.as_str()) {
+        Some("ms") => Some(Duration::from_secs_f64(value / 1000.0)),
+        _ => Some(Duration::from_secs_f64(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_retry_after, retry_rate_limited, Attempt, RetryPolicy};
+    use std::time::Duration;
+
+    #[test]
+    fn parses_retry_after_hints() {
+        let cases = [
+            (
+                "Rate limit reached. Please try again in 20s.",
+                Some(Duration::from_secs(20)),
+            ),
+            (
+                "Please try again in 1.5s",
+                Some(Duration::from_millis(1500)),
+            ),
+            (
+                "Please try again in 350ms",
+                Some(Duration::from_millis(350)),
+            ),
+            ("Retry after 3 seconds", Some(Duration::from_secs(3))),
+            ("Too many requests", None),
+        ];
+
+        for (message, expected) in cases {
+            assert_eq!(parse_retry_after(message), expected, "message: {message}");
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_limit() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+
+        let mut calls = 0;
+        let mut waits = 0;
+        let count_waits = |_| waits += 1;
+        let result = retry_rate_limited::<(), _, _, _>(&policy, count_waits, || {
+            calls += 1;
+            async {
+                Attempt::RateLimited {
+                    message: "slow down".to_owned(),
+                    retry_after: None,
+                }
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+        assert_eq!(waits, 2);
+
+        let mut calls = 0;
+        let ignore_waits = |_| ();
+        let result = retry_rate_limited(&policy, ignore_waits, || {
+            calls += 1;
+            let attempt = calls;
+            async move {
+                if attempt == 1 {
+                    Attempt::RateLimited {
+                        message: "slow down".to_owned(),
+                        retry_after: Some(Duration::ZERO),
+                    }
+                } else {
+                    Attempt::Done(Ok(attempt))
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+    }
+}
@@ -1,7 +1,6 @@
 use docgen::cli::Cli;
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
-    Cli::init().await
+    Cli::init()
 }
@@ -0,0 +1,45 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+
+/// A crate written to its own temporary directory for tests that read code from disk.
+/// The directory is removed when the fixture is dropped.
+pub struct CrateFixture {
+    dir: TempDir,
+}
+
+impl CrateFixture {
+    /// A crate whose `Cargo.toml` is `manifest`
+    pub fn new(manifest: &str) -> Self {
+        let fixture = Self {
+            dir: tempfile::tempdir().unwrap(),
+        };
+        fixture.write("Cargo.toml", manifest);
+        fixture
+    }
+
+    /// A crate with a `[package]` named `name` and no dependencies
+    pub fn package(name: &str) -> Self {
+        Self::new(&package_manifest(name))
+    }
+
+    pub fn root(&self) -> PathBuf {
+        self.dir.path().to_path_buf()
+    }
+
+    /// Writes `contents` to `path` under the crate root, creating the directories on the way,
+    /// and returns the file's full path
+    pub fn write(&self, path: impl AsRef<Path>, contents: &str) -> PathBuf {
+        let file = self.dir.path().join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, contents).unwrap();
+        file
+    }
+}
+
+/// The `Cargo.toml` of a package named `name` with no dependencies
+pub fn package_manifest(name: &str) -> String {
+    format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n")
+}
@@ -1,6 +1,175 @@
+#[cfg(test)]
+pub mod fixtures;
+
 use regex::Regex;
 
+/// Converts kebab-case, camelCase and PascalCase identifiers to snake_case.
+/// Runs of capitals are kept together, so `HTTPServer` becomes `http_server`.
 pub fn to_snake_case(s: &str) -> String {
-    let r = Regex::new("[-]").unwrap();
-    r.replace_all(s, "_").to_string()
+    let lower_to_upper = Regex::new("([a-z0-9])([A-Z])").unwrap();
+    let acronym_to_word = Regex::new("([A-Z]+)([A-Z][a-z])").unwrap();
+    let hyphens = Regex::new("[-]").unwrap();
+
+    let s = lower_to_upper.replace_all(s, "${1}_${2}");
+    let s = acronym_to_word.replace_all(&s, "${1}_${2}");
+    hyphens.replace_all(&s, "_").to_lowercase()
+}
+
+/// Applies a serde `rename_all` rule (e.g. `camelCase`) to a snake_case field name,
+/// mirroring how serde derives the serialized name. Unknown rules leave the name untouched.
+pub fn apply_serde_rename_rule(field: &str, rule: &str) -> String {
+    let words = field.split('_').filter(|w| !w.is_empty());
+    let capitalize = |w: &str| {
+        let mut chars = w.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+            None => String::new(),
+        }
+    };
+
+    match rule {
+        "lowercase" => field.to_lowercase(),
+        "UPPERCASE" => field.to_uppercase(),
+        "PascalCase" => words.map(capitalize).collect(),
+        "camelCase" => words
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_owned() } else { capitalize(w) })
+            .collect(),
+        "snake_case" => field.to_owned(),
+        "SCREAMING_SNAKE_CASE" => field.to_uppercase(),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.replace('_', "-").to_uppercase(),
+        _ => field.to_owned(),
+    }
+}
+
+/// Collapses repeated slashes and drops a trailing one, so `/api//v1/users/` becomes
+/// `/api/v1/users`. The path always starts with `/`, and case is kept as paths are case-sensitive.
+pub fn normalize_route_path(path: &str) -> String {
+    let segments = path.split('/').filter(|s| !s.is_empty());
+    format!("/{}", segments.collect::<Vec<_>>().join("/"))
+}
+
+/// An axum `:param` or `*wildcard` capture. It starts a segment or follows literal text
+/// that doesn't end in a word character, e.g. `:id` in `/user-:id` or `/:id.json`, so the
+/// colon in `/v1:batch` stays literal.
+fn path_capture_regex() -> Regex {
+    Regex::new(r"(^|[^\w:*])([:*])([A-Za-z_]\w*)").unwrap()
+}
+
+/// The marker (`:` or `*`) and name of every capture in an axum route path, in order
+pub fn path_captures(path: &str) -> Vec<(char, &str)> {
+    path_capture_regex()
+        .captures_iter(path)
+        .filter_map(|captures| {
+            let marker = captures.get(2)?.as_str().chars().next()?;
+            Some((marker, captures.get(3)?.as_str()))
+        })
+        .collect()
+}
+
+/// Rewrites the captures of an axum route path in OpenAPI's `{name}` form, keeping the
+/// literal text around them, e.g. `/files/user-:id.json` becomes `/files/user-{id}.json`
+pub fn to_openapi_path(path: &str) -> String {
+    path_capture_regex()
+        .replace_all(path, "${1}{${3}}")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_serde_rename_rule, normalize_route_path, path_captures, to_openapi_path,
+        to_snake_case,
+    };
+
+    #[test]
+    fn converts_captures_in_mixed_segments() {
+        let cases = [
+            ("/users/:id", "/users/{id}", vec![(':', "id")]),
+            ("/users/:id.json", "/users/{id}.json", vec![(':', "id")]),
+            ("/user-:id", "/user-{id}", vec![(':', "id")]),
+            (
+                "/files/:name.:ext",
+                "/files/{name}.{ext}",
+                vec![(':', "name"), (':', "ext")],
+            ),
+            (
+                "/users/:user_id/files/*rest",
+                "/users/{user_id}/files/{rest}",
+                vec![(':', "user_id"), ('*', "rest")],
+            ),
+            ("/v1:batch", "/v1:batch", vec![]),
+            ("/users/{id}", "/users/{id}", vec![]),
+            ("/health", "/health", vec![]),
+        ];
+
+        for (path, expected, captures) in cases {
+            assert_eq!(to_openapi_path(path), expected, "path: {path}");
+            assert_eq!(path_captures(path), captures, "path: {path}");
+        }
+    }
+
+    #[test]
+    fn normalizes_route_paths() {
+        let cases = [
+            ("/channels", "/channels"),
+            ("/channels/", "/channels"),
+            ("//health", "/health"),
+            ("/api//v1/users", "/api/v1/users"),
+            ("users/:id", "/users/:id"),
+            ("/Users/{ID}", "/Users/{ID}"),
+            ("/", "/"),
+            ("//", "/"),
+            ("", "/"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(normalize_route_path(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn converts_to_snake_case() {
+        let cases = [
+            ("docgen", "docgen"),
+            ("my-crate", "my_crate"),
+            ("my_crate", "my_crate"),
+            ("MyCrate", "my_crate"),
+            ("fooBar", "foo_bar"),
+            ("myCrateName", "my_crate_name"),
+            ("HTTPServer", "http_server"),
+            ("my-Crate", "my_crate"),
+            ("api2Server", "api2_server"),
+            ("", ""),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(to_snake_case(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn applies_serde_rename_rules() {
+        let cases = [
+            ("user_name", "lowercase", "user_name"),
+            ("user_name", "UPPERCASE", "USER_NAME"),
+            ("user_name", "PascalCase", "UserName"),
+            ("user_name", "camelCase", "userName"),
+            ("user_name", "snake_case", "user_name"),
+            ("user_name", "SCREAMING_SNAKE_CASE", "USER_NAME"),
+            ("user_name", "kebab-case", "user-name"),
+            ("user_name", "SCREAMING-KEBAB-CASE", "USER-NAME"),
+            ("id", "camelCase", "id"),
+            ("user_name", "unknown", "user_name"),
+        ];
+
+        for (field, rule, expected) in cases {
+            assert_eq!(
+                apply_serde_rename_rule(field, rule),
+                expected,
+                "field: {field}, rule: {rule}"
+            );
+        }
+    }
 }